@@ -1,13 +1,109 @@
+use anyhow::{bail, Context, Result};
+use eggscript_types::{TypeHandle, P};
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 
 use eggscript_types::TypeStore;
 
+use crate::expressions::Expression;
+use crate::Span;
+
 pub struct AstContext {
+	/// Values of the constants that are in scope, functions get a scope of their own
+	pub constant_scopes: Vec<HashMap<String, P<Expression>>>,
+	/// Index of the file that is being parsed
+	pub file: u32,
+	/// Functions declared in the file that is being parsed
+	pub local_functions: HashSet<String>,
+	/// Namespace of the file that is being parsed, `None` for the main file
+	pub namespace: Option<String>,
+	/// Package whose functions are being parsed
+	pub package: Option<String>,
 	pub type_store: Arc<Mutex<TypeStore>>,
 }
 
 impl AstContext {
-	pub fn new(type_store: Arc<Mutex<TypeStore>>) -> AstContext {
-		AstContext { type_store }
+	pub fn new(
+		type_store: Arc<Mutex<TypeStore>>,
+		file: u32,
+		namespace: Option<String>,
+		local_functions: HashSet<String>,
+	) -> AstContext {
+		AstContext {
+			constant_scopes: vec![HashMap::new()],
+			file,
+			local_functions,
+			namespace,
+			package: None,
+			type_store,
+		}
+	}
+
+	pub fn span(&self, span: pest::Span) -> Span {
+		Span::new(span.start() as u32, span.end() as u32, self.file)
+	}
+
+	/// Finds the type a type annotation names, map types are created the first time they are named
+	pub fn resolve_type(&self, name: &str) -> Result<TypeHandle> {
+		let mut type_store = self.type_store.lock().expect("Could not lock type store");
+		let ty = type_store
+			.resolve_type_name(name)
+			.context(format!("Unknown type '{}'", name.trim()))?;
+
+		// the runtime stores keys and values of maps in the same way as `any` values
+		let map_types = type_store.get_map_types(ty).or_else(|| {
+			type_store
+				.get_optional_inner_type(ty)
+				.and_then(|inner| type_store.get_map_types(inner))
+		});
+
+		if let Some((key, value)) = map_types {
+			for ty in [key, value] {
+				let is_supported = ["number", "string"].iter().any(|name| {
+					type_store
+						.name_to_type_handle(name)
+						.is_some_and(|handle| type_store.are_types_compatible(handle, ty))
+				});
+
+				if !is_supported {
+					bail!("Map keys and values have to be numbers or strings");
+				}
+			}
+		}
+
+		Ok(ty)
+	}
+
+	pub fn declare_constant(&mut self, name: &str, value: P<Expression>) -> Result<()> {
+		if self.get_constant(name).is_some() {
+			bail!("Constant '{}' is already declared", name);
+		}
+
+		self.constant_scopes
+			.last_mut()
+			.expect("Could not get constant scope")
+			.insert(name.into(), value);
+
+		Ok(())
+	}
+
+	pub fn get_constant(&self, name: &str) -> Option<P<Expression>> {
+		return self
+			.constant_scopes
+			.iter()
+			.rev()
+			.find_map(|scope| scope.get(name).cloned());
+	}
+
+	/// Functions declared in a module are namespaced by the module's name, so calls to them from
+	/// within the module have to be qualified
+	pub fn qualify_function_name(&self, name: &str) -> String {
+		if let Some(namespace) = &self.namespace
+			&& self.local_functions.contains(name)
+		{
+			return format!("{}::{}", namespace, name);
+		} else {
+			return name.to_string();
+		}
 	}
 }
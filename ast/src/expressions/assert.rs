@@ -0,0 +1,37 @@
+use anyhow::{bail, Result};
+use eggscript_types::{Primitive, P};
+
+use crate::expressions::{Expression, ExpressionInfo};
+use crate::Span;
+
+impl Expression {
+	/// `assert` is an intrinsic instead of a native, so failures can report the asserted code
+	pub(crate) fn new_assert(span: Span, arguments: Vec<P<Expression>>) -> Result<P<Expression>> {
+		let mut arguments = arguments.into_iter();
+
+		let Some(condition) = arguments.next() else {
+			bail!("assert expects a condition");
+		};
+
+		let message = match arguments.next() {
+			Some(message) => {
+				let ExpressionInfo::Primitive(Primitive::String, message) = &message.info else {
+					bail!("The message of an assert has to be a string literal");
+				};
+
+				Some(message.clone())
+			}
+			None => None,
+		};
+
+		if arguments.next().is_some() {
+			bail!("assert expects a condition and an optional message");
+		}
+
+		Ok(P::new(Expression {
+			info: ExpressionInfo::Assert(condition, message),
+			span,
+			ty: None,
+		}))
+	}
+}
@@ -0,0 +1,121 @@
+use anyhow::{bail, Context, Result};
+use eggscript_types::{Primitive, P};
+use pest::iterators::Pair;
+
+use crate::expressions::{Expression, ExpressionInfo};
+use crate::operators::LogicOperator;
+use crate::parser::Rule;
+use crate::{AstContext, Ident, Span};
+
+impl Expression {
+	pub(crate) fn parse_const_declaration(
+		context: &mut AstContext,
+		pair: Pair<Rule>,
+	) -> Result<P<Expression>> {
+		let span = context.span(pair.as_span());
+		let mut inner = pair.into_inner();
+
+		let name_pair = inner.next().context("Could not get constant name")?;
+		let name = Ident::new(name_pair.as_str(), context.span(name_pair.as_span()));
+
+		let expression = Expression::parse_pair(
+			context,
+			inner.next().context("Could not get constant value")?,
+		)
+		.context("Could not parse pair")??;
+
+		let value = Expression::evaluate_constant(context, &expression)
+			.context(format!("Could not evaluate constant '{}'", name.name()))?;
+
+		context.declare_constant(name.name(), value.clone())?;
+
+		Ok(P::new(Expression {
+			info: ExpressionInfo::Constant(name, value),
+			span,
+			ty: None,
+		}))
+	}
+
+	/// Uses of a constant are replaced by its value, so they lower to a literal
+	pub(crate) fn parse_constant_access(
+		context: &mut AstContext,
+		pair: Pair<Rule>,
+	) -> Result<P<Expression>> {
+		let name = pair.as_str().trim();
+		let value = context
+			.get_constant(name)
+			.context(format!("Unknown constant '{}'", name))?;
+
+		Ok(P::new(Expression {
+			info: value.info.clone(),
+			span: context.span(pair.as_span()),
+			ty: value.ty,
+		}))
+	}
+
+	/// Folds an expression made of literals, constants and operators into a literal
+	pub(crate) fn evaluate_constant(
+		context: &AstContext,
+		expression: &P<Expression>,
+	) -> Result<P<Expression>> {
+		match &expression.info {
+			ExpressionInfo::Primitive(_, _) => Ok(expression.clone()),
+			ExpressionInfo::BinaryOperation(left, right, operator) => {
+				let left = Expression::evaluate_constant_number(context, left)?;
+				let right = Expression::evaluate_constant_number(context, right)?;
+
+				Expression::new_constant_number(
+					context,
+					operator.evaluate(left, right),
+					expression.span,
+				)
+			}
+			ExpressionInfo::LogicOperation(left, right, operator) => {
+				let left = Expression::evaluate_constant_number(context, left)?;
+				let right = Expression::evaluate_constant_number(context, right)?;
+
+				// matches the values the interpreter's short circuiting produces
+				let value = match operator {
+					LogicOperator::And if left == 0.0 => 0.0,
+					LogicOperator::Or if left != 0.0 => left,
+					_ => right,
+				};
+
+				Expression::new_constant_number(context, value, expression.span)
+			}
+			ExpressionInfo::UnaryOperation(value, operator) => {
+				let value = Expression::evaluate_constant_number(context, value)?;
+
+				Expression::new_constant_number(context, operator.evaluate(value), expression.span)
+			}
+			_ => bail!("Constants can only be made of literals, other constants and operators"),
+		}
+	}
+
+	fn evaluate_constant_number(context: &AstContext, expression: &P<Expression>) -> Result<f64> {
+		let value = Expression::evaluate_constant(context, expression)?;
+		let ExpressionInfo::Primitive(Primitive::Number, number) = &value.info else {
+			bail!("Operators in constants only work on numbers");
+		};
+
+		return number
+			.trim()
+			.parse::<f64>()
+			.context(format!("Could not parse f64 '{}'", number));
+	}
+
+	fn new_constant_number(context: &AstContext, value: f64, span: Span) -> Result<P<Expression>> {
+		let ty = context
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.name_to_type_handle("number")
+			.context("Could not get 'number' literal type")?;
+
+		Ok(P::new(Expression {
+			info: ExpressionInfo::Primitive(Primitive::Number, format!("{}", value)),
+			span,
+			ty: Some(ty),
+		}))
+	}
+}
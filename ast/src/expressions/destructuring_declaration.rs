@@ -0,0 +1,42 @@
+use anyhow::{Context, Result};
+use eggscript_types::P;
+use pest::iterators::Pair;
+
+use crate::expressions::{Expression, ExpressionInfo};
+use crate::parser::Rule;
+use crate::{AstContext, Ident};
+
+impl Expression {
+	pub(crate) fn parse_destructuring_declaration(
+		context: &mut AstContext,
+		pair: Pair<Rule>,
+	) -> Result<P<Expression>> {
+		let span = context.span(pair.as_span());
+		let mut names = vec![];
+		let mut expression = None;
+		for pair in pair.into_inner() {
+			match pair.as_rule() {
+				Rule::variable_declaration_ident => {
+					names.push(Ident::new(
+						pair.as_str().trim(),
+						context.span(pair.as_span()),
+					));
+				}
+				_ => {
+					expression = Some(
+						Expression::parse_pair(context, pair).context("Could not parse pair")??,
+					);
+				}
+			}
+		}
+
+		Ok(P::new(Expression {
+			info: ExpressionInfo::Destructure(
+				names,
+				expression.context("Could not get rvalue expression")?,
+			),
+			span,
+			ty: None,
+		}))
+	}
+}
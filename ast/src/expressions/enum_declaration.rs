@@ -0,0 +1,82 @@
+use anyhow::{bail, Context, Result};
+use eggscript_types::{Primitive, P};
+use pest::iterators::Pair;
+
+use crate::expressions::{Expression, ExpressionInfo};
+use crate::parser::Rule;
+use crate::AstContext;
+
+impl Expression {
+	/// Enums are declared before the rest of the file is parsed, so they can be used above their
+	/// declaration
+	pub(crate) fn parse_enum_declaration(context: &mut AstContext, pair: Pair<Rule>) -> Result<()> {
+		let mut inner = pair.into_inner();
+		let name = inner.next().context("Could not get enum name")?.as_str();
+
+		let mut variants: Vec<String> = vec![];
+		for variant in inner {
+			let variant = variant.as_str().to_string();
+			if variants.contains(&variant) {
+				bail!(
+					"Variant '{}' is declared more than once in enum '{}'",
+					variant,
+					name
+				);
+			}
+
+			variants.push(variant);
+		}
+
+		if variants.is_empty() {
+			bail!("Enum '{}' has no variants", name);
+		}
+
+		context
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.create_enum_type(name, variants)
+			.context(format!("Type '{}' is already declared", name))?;
+
+		Ok(())
+	}
+
+	/// `Enum::Variant` lowers to the index of the variant, typed as the enum
+	pub(crate) fn parse_enum_variant_access(
+		context: &mut AstContext,
+		pair: Pair<Rule>,
+	) -> Result<P<Expression>> {
+		let span = context.span(pair.as_span());
+		let path = pair.as_str().trim();
+		let segments = pair
+			.into_inner()
+			.map(|pair| pair.as_str())
+			.collect::<Vec<&str>>();
+
+		let [enum_name, variant] = segments[..] else {
+			bail!("Unknown path '{}'", path);
+		};
+
+		let type_store = context
+			.type_store
+			.lock()
+			.expect("Could not lock type store");
+		let ty = type_store
+			.name_to_type_handle(enum_name)
+			.filter(|ty| type_store.get_enum_variants(*ty).is_some())
+			.context(format!("Unknown enum '{}'", enum_name))?;
+
+		let index = type_store
+			.get_enum_variants(ty)
+			.context("Could not get enum variants")?
+			.iter()
+			.position(|name| name == variant)
+			.context(format!("Enum '{}' has no variant '{}'", enum_name, variant))?;
+
+		Ok(P::new(Expression {
+			info: ExpressionInfo::Primitive(Primitive::Number, index.to_string()),
+			span,
+			ty: Some(ty),
+		}))
+	}
+}
@@ -1,16 +1,14 @@
-use anyhow::{Context, Result};
-use eggscript_types::{TypeHandle, TypeStore, P};
-use pest::iterators::{Pair, Pairs};
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use anyhow::Result;
+use eggscript_mir::MapOperation;
+use eggscript_types::{TypeHandle, P};
+use pest::iterators::Pair;
 
 use crate::expressions::Block;
+use crate::module::ModuleLoader;
 use crate::operators::LogicOperator;
-use crate::parser::{Program, Rule};
+use crate::parser::{CompileOptions, Program, Rule};
 use crate::{AstContext, BinaryOperator, Ident, Span, UnaryOperator};
 
-use super::FunctionArgument;
-
 #[derive(Clone, Debug)]
 pub struct Expression {
 	pub(crate) info: ExpressionInfo,
@@ -20,12 +18,22 @@ pub struct Expression {
 
 #[derive(Clone, Debug)]
 pub enum ExpressionInfo {
+	/// Raises an exception if the condition is false, with an optional message
+	Assert(P<Expression>, Option<String>),
 	/// Assigns the resulting value of an expression to a variable.
 	Assign(Ident, BinaryOperator, P<Expression>),
 	/// Operation between two expressions
 	BinaryOperation(P<Expression>, P<Expression>, BinaryOperator),
+	/// Jumps past the end of the closest enclosing loop
+	Break,
+	/// Declares a constant, whose uses are replaced by the value it evaluated to while parsing
+	Constant(Ident, P<Expression>),
+	/// Jumps to the next iteration of the closest enclosing loop
+	Continue,
 	/// Else block that follows if/else-if blocks
 	Else(P<Block>),
+	/// Declares several variables from the values of a tuple
+	Destructure(Vec<Ident>, P<Expression>),
 	/// Acessing a variable
 	FieldAccess(Ident),
 	/// For loop
@@ -34,18 +42,38 @@ pub enum ExpressionInfo {
 	FunctionCall(Ident, Vec<P<Expression>>),
 	/// If or else-if block, with optional continuing else-if/else block
 	If(P<Expression>, P<Block>, Option<P<Expression>>),
+	/// Gets the value of a key in a map
+	Index(P<Expression>, P<Expression>),
+	/// Assigns the resulting value of an expression to a key in a map
+	IndexAssign(P<Expression>, P<Expression>, BinaryOperator, P<Expression>),
 	/// Represents a logic operation (&& or ||)
 	LogicOperation(P<Expression>, P<Expression>, LogicOperator),
+	/// Operation on a map that looks like a function call, like `count(%map)`
+	MapCall(MapOperation, Vec<P<Expression>>),
+	/// Map with its keys and values, in the order they are inserted in
+	MapLiteral(Vec<(P<Expression>, P<Expression>)>),
 	/// A literal value.
 	Primitive(eggscript_types::Primitive, String),
-	/// Return statement
-	Return(Option<P<Expression>>),
+	/// Return statement, with one expression per returned value
+	Return(Vec<P<Expression>>),
 	/// Represents variable scope.
 	Scope(P<Block>),
+	/// Switch statement, with the values each case matches and the optional default block
+	Switch(
+		P<Expression>,
+		Vec<(Vec<P<Expression>>, P<Block>)>,
+		Option<P<Block>>,
+	),
+	/// Raises an exception that unwinds to the closest enclosing try block
+	Throw(P<Expression>),
+	/// Try block, followed by the variable the exception is stored in and the catch block
+	Try(P<Block>, Ident, P<Block>),
 	/// Operation performed on a single expression
 	UnaryOperation(P<Expression>, UnaryOperator),
 	/// While loop
 	While(P<Expression>, P<Block>),
+	/// Pauses the program and hands the optional value to the host
+	Yield(Option<P<Expression>>),
 }
 
 impl ExpressionInfo {
@@ -68,63 +96,20 @@ impl ExpressionInfo {
 
 impl Expression {
 	pub(crate) fn parse_program(
-		file_name: &str,
-		type_store: Arc<Mutex<TypeStore>>,
-		pairs: Pairs<Rule>,
+		loader: ModuleLoader,
+		file: u32,
+		global_scope: Vec<Result<P<Expression>>>,
+		options: CompileOptions,
 	) -> Result<Program> {
-		let mut context = AstContext::new(type_store.clone());
-
-		let mut functions = vec![];
-		let mut function_name_to_function = HashMap::new();
-		let mut global_scope = vec![];
-		for pair in pairs.into_iter() {
-			match pair.as_rule() {
-				Rule::function_declaration => {
-					let id = functions.len();
-					let function = Expression::parse_function_declaration(&mut context, pair, id)
-						.context("Could not parse function declaration")?;
-
-					functions.push(function.clone());
-					function_name_to_function.insert(function.name.clone(), function);
-				}
-				_ => {
-					if let Some(parsed) = Expression::parse_pair(&mut context, pair) {
-						global_scope.push(parsed);
-					}
-				}
-			}
-		}
-
-		let mut program = Program {
-			file_name: file_name.to_string(),
-			function_name_to_function,
-			functions,
-			global_scope: Expression::new_scope(global_scope, Span::new(0, 0))?,
-			type_store,
-		};
-
-		let type_store = context
-			.type_store
-			.lock()
-			.expect("Could not lock type store");
-
-		let number = type_store
-			.name_to_type_handle("number")
-			.context("Could not get 'number' type")?;
-
-		drop(type_store);
-
-		program.add_native_function(
-			vec![FunctionArgument {
-				name: "value".into(),
-				span: Span::new(0, 0),
-				ty: number,
-			}],
-			"printNumber",
-			number,
-		);
-
-		Ok(program)
+		Ok(Program {
+			files: loader.files,
+			function_name_to_function: loader.function_name_to_function,
+			functions: loader.functions,
+			global_scope: Expression::new_scope(global_scope, Span::new(0, 0, file))?,
+			options,
+			packages: loader.packages,
+			type_store: loader.type_store,
+		})
 	}
 
 	pub(crate) fn parse_pair(
@@ -132,19 +117,38 @@ impl Expression {
 		pair: Pair<Rule>,
 	) -> Option<Result<P<Expression>>> {
 		match pair.as_rule() {
+			Rule::null_literal => Some(Expression::parse_null_literal(context, pair)),
 			Rule::number_literal => Some(Expression::parse_number_literal(context, pair)),
+			Rule::break_statement => Some(Expression::parse_break_statement(context, pair)),
+			Rule::continue_statement => Some(Expression::parse_continue_statement(context, pair)),
+			Rule::const_declaration => Some(Expression::parse_const_declaration(context, pair)),
+			Rule::destructuring_declaration => {
+				Some(Expression::parse_destructuring_declaration(context, pair))
+			}
 			Rule::else_block => Some(Expression::parse_else_block(context, pair)),
 			Rule::field_access => Some(Expression::parse_field_access(context, pair)),
+			Rule::foreach_block => Some(Expression::parse_foreach_block(context, pair)),
 			Rule::for_block => Some(Expression::parse_for_block(context, pair)),
 			Rule::function_call => Some(Expression::parse_function_call(context, pair)),
 			Rule::if_block => Some(Expression::parse_if_block(context, pair)),
+			Rule::index_access => Some(Expression::parse_index_access(context, pair)),
+			Rule::map_literal => Some(Expression::parse_map_literal(context, pair)),
 			Rule::math => Some(Expression::parse_math(context, pair)),
+			Rule::path_access if pair.as_str().contains("::") => {
+				Some(Expression::parse_enum_variant_access(context, pair))
+			}
+			Rule::path_access => Some(Expression::parse_constant_access(context, pair)),
 			Rule::return_statement => Some(Expression::parse_return_statement(context, pair)),
+			Rule::string_literal => Some(Expression::parse_string_literal(context, pair)),
+			Rule::switch_block => Some(Expression::parse_switch_block(context, pair)),
+			Rule::throw_statement => Some(Expression::parse_throw_statement(context, pair)),
+			Rule::try_block => Some(Expression::parse_try_block(context, pair)),
 			Rule::variable_assignment => Some(Expression::parse_variable_assignment(context, pair)),
 			Rule::variable_declaration => {
 				Some(Expression::parse_variable_declaration(context, pair))
 			}
 			Rule::while_block => Some(Expression::parse_while_block(context, pair)),
+			Rule::yield_statement => Some(Expression::parse_yield_statement(context, pair)),
 			Rule::EOI => None,
 			rule => unimplemented!("{:?} not implemented", rule),
 		}
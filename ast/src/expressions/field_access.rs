@@ -11,7 +11,7 @@ impl Expression {
 		context: &mut AstContext,
 		pair: Pair<Rule>,
 	) -> Result<P<Expression>> {
-		let span = pair.as_span().into();
+		let span = context.span(pair.as_span());
 		let symbol = pair.as_str().to_string();
 		let symbol = symbol.trim();
 
@@ -26,7 +26,7 @@ impl Expression {
 
 		Ok(P::new(Expression {
 			info: ExpressionInfo::FieldAccess(variable_ident),
-			span: pair.as_span().into(),
+			span: context.span(pair.as_span()),
 			ty: Some(type_handle),
 		}))
 	}
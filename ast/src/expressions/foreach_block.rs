@@ -0,0 +1,160 @@
+use anyhow::{Context, Result};
+use eggscript_mir::MapOperation;
+use eggscript_types::P;
+use pest::iterators::Pair;
+
+use crate::expressions::{Block, Expression, ExpressionInfo};
+use crate::parser::Rule;
+use crate::{AstContext, BinaryOperator, Ident};
+
+impl Expression {
+	/// `foreach` loops are desugared into a `for` loop over a counter that user code cannot name,
+	/// so changing the loop variable does not change how often the loop runs. Maps are iterated
+	/// over by the index of their keys
+	pub(crate) fn parse_foreach_block(
+		context: &mut AstContext,
+		pair: Pair<Rule>,
+	) -> Result<P<Expression>> {
+		let span = context.span(pair.as_span());
+		let mut pairs = pair.into_inner();
+
+		let variable_pair = pairs.next().context("Could not get foreach variable")?;
+		let variable = Ident::new(
+			variable_pair.as_str().trim(),
+			context.span(variable_pair.as_span()),
+		);
+
+		let iterable = pairs.next().context("Could not get foreach iterable")?;
+		let is_range = iterable.as_rule() == Rule::range;
+		let (start, end) = if is_range {
+			let mut range = iterable.into_inner();
+			let start =
+				Expression::parse_pair(context, range.next().context("Could not get range start")?)
+					.context("Could not parse range start")??;
+			let end =
+				Expression::parse_pair(context, range.next().context("Could not get range end")?)
+					.context("Could not parse range end")??;
+
+			(start, Some(end))
+		} else {
+			let map = Expression::parse_pair(context, iterable)
+				.context("Could not parse foreach iterable")??;
+
+			(map, None)
+		};
+
+		let block = pairs.next().context("Could not get foreach block")?;
+		let mut expressions = block
+			.into_inner()
+			.map(|p| {
+				Expression::parse_pair(context, p).expect("Expected expression where there is none")
+			})
+			.collect::<Result<Vec<P<Expression>>>>()?;
+
+		let name = |part: &str| format!("foreach:{}:{}:{}", span.file(), span.start(), part);
+		let counter = Ident::new(&name("counter"), span);
+		let length = Ident::new(&name("length"), span);
+		let map = Ident::new(&name("map"), span);
+
+		let number = context
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.name_to_type_handle("number")
+			.context("Could not get 'number' literal type")?;
+
+		let field_access = |ident: &Ident| {
+			P::new(Expression {
+				info: ExpressionInfo::FieldAccess(ident.clone()),
+				span,
+				ty: Some(number),
+			})
+		};
+
+		let assign = |ident: &Ident, operator: BinaryOperator, value: P<Expression>| {
+			P::new(Expression {
+				info: ExpressionInfo::Assign(ident.clone(), operator, value),
+				span,
+				ty: None,
+			})
+		};
+
+		let one = P::new(Expression {
+			info: ExpressionInfo::Primitive(eggscript_types::Primitive::Number, "1".into()),
+			span,
+			ty: Some(number),
+		});
+
+		let conditional = P::new(Expression {
+			info: ExpressionInfo::BinaryOperation(
+				field_access(&counter),
+				field_access(&length),
+				BinaryOperator::LessThan,
+			),
+			span,
+			ty: Some(number),
+		});
+
+		let zero = P::new(Expression {
+			info: ExpressionInfo::Primitive(eggscript_types::Primitive::Number, "0".into()),
+			span,
+			ty: Some(number),
+		});
+
+		// maps are evaluated once, and the loop runs once for every key they had at the start
+		let (setup, start, end, key) = match end {
+			Some(end) => (None, start, end, field_access(&counter)),
+			None => {
+				let map_span = start.span;
+				let map_access = P::new(Expression {
+					info: ExpressionInfo::FieldAccess(map.clone()),
+					span: map_span,
+					ty: Some(number),
+				});
+
+				let count = Expression::new_map_call(
+					map_span,
+					MapOperation::Count,
+					vec![map_access.clone()],
+				)?;
+
+				let key = Expression::new_map_call(
+					map_span,
+					MapOperation::KeyAt,
+					vec![map_access, field_access(&counter)],
+				)?;
+
+				(
+					Some(assign(&map, BinaryOperator::Equal, start)),
+					zero,
+					count,
+					key,
+				)
+			}
+		};
+
+		// the loop variable is a copy of the counter, or the key at it, at the start of every
+		// iteration
+		expressions.insert(0, assign(&variable, BinaryOperator::Equal, key));
+
+		let for_block = P::new(Expression {
+			info: ExpressionInfo::For(
+				assign(&counter, BinaryOperator::Equal, start),
+				conditional,
+				assign(&counter, BinaryOperator::Plus, one),
+				P::new(Block { expressions, span }),
+			),
+			span,
+			ty: None,
+		});
+
+		// the end of the range is only evaluated once, before the loop starts
+		Expression::new_scope(
+			setup.into_iter().map(Ok).chain([
+				Ok(assign(&length, BinaryOperator::Equal, end)),
+				Ok(for_block),
+			]),
+			span,
+		)
+	}
+}
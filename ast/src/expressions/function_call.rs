@@ -1,4 +1,5 @@
 use anyhow::{Context, Result};
+use eggscript_mir::MapOperation;
 use eggscript_types::{Type, P};
 use pest::iterators::Pair;
 
@@ -11,13 +12,14 @@ impl Expression {
 		context: &mut AstContext,
 		pair: Pair<Rule>,
 	) -> Result<P<Expression>> {
-		let span = pair.as_span().into();
+		let span = context.span(pair.as_span());
 		let mut pairs = pair.into_inner();
 
 		let name_pair = pairs.next().context("Could not get function name")?;
 
-		let name = name_pair.as_str();
-		let name_span = name_pair.as_span().into();
+		let name = context.qualify_function_name(name_pair.as_str());
+		let name = name.as_str();
+		let name_span = context.span(name_pair.as_span());
 
 		let mut arguments = vec![];
 
@@ -29,6 +31,21 @@ impl Expression {
 			);
 		}
 
+		if name_pair.as_str() == "assert" {
+			return Expression::new_assert(span, arguments);
+		}
+
+		let map_operation = match name_pair.as_str() {
+			"contains" => Some(MapOperation::Contains),
+			"count" => Some(MapOperation::Count),
+			"remove" => Some(MapOperation::Remove),
+			_ => None,
+		};
+
+		if let Some(operation) = map_operation {
+			return Expression::new_map_call(span, operation, arguments);
+		}
+
 		let ty = context
 			.type_store
 			.lock()
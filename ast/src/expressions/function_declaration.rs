@@ -1,7 +1,8 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use colored::Colorize;
 use eggscript_types::{FunctionType, TypeHandle, P};
 use pest::iterators::Pair;
+use std::collections::HashMap;
 
 use crate::expressions::Expression;
 use crate::parser::Rule;
@@ -9,6 +10,9 @@ use crate::{AstContext, Span};
 
 #[derive(Clone, Debug)]
 pub struct FunctionArgument {
+	/// Literal the call site passes when the argument is omitted. Defaults are folded when they are
+	/// parsed, so they do not depend on the scope of the call site
+	pub default: Option<P<Expression>>,
 	pub name: String,
 	#[allow(dead_code)]
 	pub span: Span,
@@ -18,11 +22,14 @@ pub struct FunctionArgument {
 #[derive(Debug)]
 pub struct Function {
 	pub arguments: Vec<FunctionArgument>,
+	/// Whether other files can call the function through the module's namespace
+	pub exported: bool,
 	pub id: usize,
 	pub name: String,
+	/// Name of the function a package function overrides
+	pub overrides: Option<String>,
 	pub return_ty: Option<TypeHandle>,
 	pub scope: Option<P<Expression>>,
-	#[allow(dead_code)]
 	pub span: Span,
 	pub ty: FunctionType,
 }
@@ -62,6 +69,15 @@ impl Function {
 				format!("{:?}", argument.ty).cyan(),
 			))?;
 
+			if let Some(default) = argument.default.as_ref() {
+				let child_prefix = if index == length - 1 { "   " } else { "|  " };
+				default.pretty_print(
+					f,
+					&format!("{}|  {}`- ", prefix, child_prefix),
+					&format!("{}|  {}   ", prefix, child_prefix),
+				)?;
+			}
+
 			index += 1;
 		}
 
@@ -85,21 +101,41 @@ impl Expression {
 		pair: Pair<Rule>,
 		id: usize,
 	) -> Result<P<Function>> {
-		let span = pair.as_span().into();
+		let span = context.span(pair.as_span());
 		let mut pairs = pair.into_inner();
 
+		let exported =
+			if let Rule::export_keyword = pairs.peek().context("Could not peek next")?.as_rule() {
+				pairs.next();
+				true
+			} else {
+				false
+			};
+
 		let name_pair = pairs.next().context("Could not get function name")?;
-		let name = name_pair.as_str();
+
+		// package functions live next to the functions they override, so they get a name of their
+		// own
+		let (name, overrides) = if let Some(package) = &context.package {
+			(
+				format!("{}::{}", package, name_pair.as_str()),
+				Some(name_pair.as_str().to_string()),
+			)
+		} else {
+			(context.qualify_function_name(name_pair.as_str()), None)
+		};
+
+		let name = name.as_str();
 
 		let function_arg_list = pairs
 			.next()
 			.context("Could not get function arg list")?
 			.into_inner();
 
-		let mut arguments = vec![];
+		let mut arguments: Vec<FunctionArgument> = vec![];
 		for function_arg in function_arg_list.into_iter() {
-			let span = function_arg.as_span().into();
-			let mut function_arg_inner = function_arg.into_inner();
+			let span = context.span(function_arg.as_span());
+			let mut function_arg_inner = function_arg.into_inner().peekable();
 
 			let name = function_arg_inner
 				.next()
@@ -107,19 +143,48 @@ impl Expression {
 				.as_str()
 				.to_string();
 
+			// untyped arguments are dynamically typed, like all arguments in TorqueScript
 			let type_name = function_arg_inner
-				.next()
-				.expect("Could not get function arg type")
-				.as_str();
+				.next_if(|pair| pair.as_rule() == Rule::type_ident)
+				.map_or("any", |pair| pair.as_str());
+
+			let ty = context.resolve_type(type_name)?;
+
+			let default = if let Some(default_pair) = function_arg_inner.next() {
+				let default = Expression::parse_pair(
+					context,
+					default_pair
+						.into_inner()
+						.next()
+						.context("Could not get default value")?,
+				)
+				.context("Could not parse default value")??;
 
-			let ty = context
-				.type_store
-				.lock()
-				.expect("Could not lock type store")
-				.name_to_type_handle(type_name)
-				.expect("Could not find argument type");
+				Some(
+					Expression::evaluate_constant(context, &default).context(format!(
+						"Default value of argument '{}' of function '{}' is not constant",
+						name,
+						name_pair.as_str()
+					))?,
+				)
+			} else {
+				None
+			};
 
-			arguments.push(FunctionArgument { name, span, ty });
+			if default.is_none() && arguments.iter().any(|argument| argument.default.is_some()) {
+				bail!(
+					"Argument '{}' of function '{}' needs a default value since it follows an argument with a default value",
+					name,
+					name_pair.as_str()
+				);
+			}
+
+			arguments.push(FunctionArgument {
+				default,
+				name,
+				span,
+				ty,
+			});
 		}
 
 		let return_type = if let Rule::function_return_type =
@@ -130,23 +195,34 @@ impl Expression {
 				.context("Could not get return type")?
 				.into_inner()
 				.next()
-				.context("Could not get return type")?
-				.as_str();
-
-			Some(
-				context
-					.type_store
-					.lock()
-					.expect("Could not lock type store")
-					.name_to_type_handle(return_type)
-					.context("Could not find return type")?,
-			)
+				.context("Could not get return type")?;
+
+			if let Rule::tuple_type = return_type.as_rule() {
+				let mut element_types = vec![];
+				for element_type in return_type.into_inner() {
+					element_types.push(context.resolve_type(element_type.as_str())?);
+				}
+
+				Some(
+					context
+						.type_store
+						.lock()
+						.expect("Could not lock type store")
+						.create_tuple_type(element_types)
+						.context("Could not create tuple type")?,
+				)
+			} else {
+				Some(context.resolve_type(return_type.as_str())?)
+			}
 		} else {
 			None
 		};
 
 		let block = pairs.next().context("Could not get next pair")?;
-		let block_span = block.as_span().into();
+		let block_span = context.span(block.as_span());
+
+		// constants declared in the function are only visible inside of it
+		context.constant_scopes.push(HashMap::new());
 
 		let expressions = block
 			.into_inner()
@@ -155,6 +231,8 @@ impl Expression {
 			})
 			.collect::<Vec<Result<P<Expression>>>>();
 
+		context.constant_scopes.pop();
+
 		let function_ty = context
 			.type_store
 			.lock()
@@ -165,13 +243,21 @@ impl Expression {
 					.iter()
 					.map(|argument| argument.ty)
 					.collect::<Vec<TypeHandle>>(),
+				arguments
+					.iter()
+					.filter(|argument| argument.default.is_none())
+					.count(),
 				return_type,
+				// only natives can be variadic
+				None,
 			);
 
 		Ok(P::new(Function {
 			arguments,
+			exported,
 			id,
 			name: name.into(),
+			overrides,
 			return_ty: return_type,
 			scope: Some(Expression::new_scope(expressions, block_span)?),
 			span,
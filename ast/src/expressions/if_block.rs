@@ -13,7 +13,7 @@ impl Expression {
 		context: &mut AstContext,
 		pair: Pair<Rule>,
 	) -> Result<P<Expression>> {
-		let span = pair.as_span().into();
+		let span = context.span(pair.as_span());
 		let mut pairs = pair.into_inner();
 
 		let conditional = Expression::parse_pair(
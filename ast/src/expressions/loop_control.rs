@@ -0,0 +1,31 @@
+use anyhow::Result;
+use eggscript_types::P;
+use pest::iterators::Pair;
+
+use crate::expressions::{Expression, ExpressionInfo};
+use crate::parser::Rule;
+use crate::AstContext;
+
+impl Expression {
+	pub(crate) fn parse_break_statement(
+		context: &mut AstContext,
+		pair: Pair<Rule>,
+	) -> Result<P<Expression>> {
+		Ok(P::new(Expression {
+			info: ExpressionInfo::Break,
+			span: context.span(pair.as_span()),
+			ty: None,
+		}))
+	}
+
+	pub(crate) fn parse_continue_statement(
+		context: &mut AstContext,
+		pair: Pair<Rule>,
+	) -> Result<P<Expression>> {
+		Ok(P::new(Expression {
+			info: ExpressionInfo::Continue,
+			span: context.span(pair.as_span()),
+			ty: None,
+		}))
+	}
+}
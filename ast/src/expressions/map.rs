@@ -0,0 +1,95 @@
+use anyhow::{bail, Context, Result};
+use eggscript_mir::MapOperation;
+use eggscript_types::P;
+use pest::iterators::Pair;
+
+use crate::expressions::{Expression, ExpressionInfo};
+use crate::parser::Rule;
+use crate::{AstContext, Span};
+
+impl Expression {
+	/// The type of a map literal is decided when it is lowered, either by the type of the variable
+	/// it is assigned to or by its first entry
+	pub(crate) fn parse_map_literal(
+		context: &mut AstContext,
+		pair: Pair<Rule>,
+	) -> Result<P<Expression>> {
+		let span = context.span(pair.as_span());
+
+		let mut entries = vec![];
+		for entry in pair.into_inner() {
+			let mut entry = entry.into_inner();
+			let key = Expression::parse_pair(context, entry.next().context("Could not get key")?)
+				.context("Could not parse key")??;
+			let value =
+				Expression::parse_pair(context, entry.next().context("Could not get value")?)
+					.context("Could not parse value")??;
+
+			entries.push((key, value));
+		}
+
+		Ok(P::new(Expression {
+			info: ExpressionInfo::MapLiteral(entries),
+			span,
+			ty: None,
+		}))
+	}
+
+	pub(crate) fn parse_index_access(
+		context: &mut AstContext,
+		pair: Pair<Rule>,
+	) -> Result<P<Expression>> {
+		let span = context.span(pair.as_span());
+		let (map, key) = Expression::parse_index_operands(context, pair)?;
+
+		Ok(P::new(Expression {
+			info: ExpressionInfo::Index(map, key),
+			span,
+			ty: None,
+		}))
+	}
+
+	/// Parses the map and the key of an `index_access` pair
+	pub(crate) fn parse_index_operands(
+		context: &mut AstContext,
+		pair: Pair<Rule>,
+	) -> Result<(P<Expression>, P<Expression>)> {
+		let mut inner = pair.into_inner();
+		let map = Expression::parse_field_access(
+			context,
+			inner.next().context("Could not get indexed map")?,
+		)?;
+
+		let key = Expression::parse_pair(context, inner.next().context("Could not get index")?)
+			.context("Could not parse index")??;
+
+		Ok((map, key))
+	}
+
+	/// `contains`, `remove` and `count` are intrinsics, since natives cannot be generic over the
+	/// types of maps
+	pub(crate) fn new_map_call(
+		span: Span,
+		operation: MapOperation,
+		arguments: Vec<P<Expression>>,
+	) -> Result<P<Expression>> {
+		match operation {
+			MapOperation::Contains | MapOperation::Remove if arguments.len() != 2 => {
+				bail!(
+					"{} expects a map and a key",
+					format!("{:?}", operation).to_lowercase()
+				);
+			}
+			MapOperation::Count if arguments.len() != 1 => {
+				bail!("count expects a map");
+			}
+			_ => {}
+		}
+
+		Ok(P::new(Expression {
+			info: ExpressionInfo::MapCall(operation, arguments),
+			span,
+			ty: None,
+		}))
+	}
+}
@@ -8,7 +8,7 @@ use pest::iterators::Pair;
 use crate::expressions::Expression;
 use crate::operators::LogicOperator;
 use crate::parser::{configure_pratt, Rule};
-use crate::{AstContext, BinaryOperator, Span, UnaryOperator};
+use crate::{AstContext, BinaryOperator, UnaryOperator};
 
 use super::ExpressionInfo;
 
@@ -36,7 +36,17 @@ impl Expression {
 				let lhs = lhs?;
 				let rhs = rhs?;
 
-				let ty = if lhs.ty == rhs.ty {
+				let is_comparison = BinaryOperator::parse_binary(op.as_str())
+					.is_some_and(|operator| operator.is_comparison());
+
+				let ty = if is_comparison {
+					context
+						.borrow()
+						.type_store
+						.lock()
+						.expect("Could not lock type store")
+						.name_to_type_handle("number")
+				} else if lhs.ty == rhs.ty {
 					lhs.ty
 				} else {
 					Some(
@@ -51,13 +61,13 @@ impl Expression {
 
 				if let Some(operator) = BinaryOperator::parse_binary(op.as_str()) {
 					Ok(P::new(Expression {
-						span: Span::new(lhs.span.start(), rhs.span.end()),
+						span: lhs.span.combine(&rhs.span),
 						info: ExpressionInfo::BinaryOperation(lhs, rhs, operator),
 						ty,
 					}))
 				} else if let Some(operator) = LogicOperator::parse_logic(op.as_str()) {
 					Ok(P::new(Expression {
-						span: Span::new(lhs.span.start(), rhs.span.end()),
+						span: lhs.span.combine(&rhs.span),
 						info: ExpressionInfo::LogicOperation(lhs, rhs, operator),
 						ty,
 					}))
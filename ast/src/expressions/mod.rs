@@ -1,22 +1,35 @@
+mod assert;
 mod block;
+mod const_declaration;
+mod destructuring_declaration;
 mod else_block;
+mod enum_declaration;
 mod expression;
 mod field_access;
 mod for_block;
+mod foreach_block;
 mod function_call;
 mod function_declaration;
 mod if_block;
+mod loop_control;
+mod map;
 mod math;
+mod package_declaration;
 mod pretty_print;
 mod primitive;
 mod return_statement;
 mod scope;
+mod switch_block;
+mod throw_statement;
+mod try_block;
 mod variable_assignment;
 mod variable_declaration;
 mod while_block;
+mod yield_statement;
 
 pub use block::Block;
 pub use expression::Expression;
 pub use expression::ExpressionInfo;
 pub use function_declaration::Function;
 pub use function_declaration::FunctionArgument;
+pub use package_declaration::Package;
@@ -0,0 +1,44 @@
+use anyhow::{Context, Result};
+use eggscript_types::P;
+use pest::iterators::Pair;
+
+use crate::expressions::{Expression, Function};
+use crate::parser::Rule;
+use crate::AstContext;
+
+/// Functions that override functions of the same name while the package is active
+#[derive(Clone, Debug)]
+pub struct Package {
+	pub functions: Vec<P<Function>>,
+	pub name: String,
+}
+
+impl Expression {
+	/// Function ids are handed out in order, starting with `first_id`
+	pub(crate) fn parse_package_declaration(
+		context: &mut AstContext,
+		pair: Pair<Rule>,
+		first_id: usize,
+	) -> Result<Package> {
+		let mut pairs = pair.into_inner();
+		let name = pairs
+			.next()
+			.context("Could not get package name")?
+			.as_str()
+			.to_string();
+
+		context.package = Some(name.clone());
+
+		let mut functions = vec![];
+		for pair in pairs {
+			functions.push(
+				Expression::parse_function_declaration(context, pair, first_id + functions.len())
+					.context("Could not parse function declaration")?,
+			);
+		}
+
+		context.package = None;
+
+		Ok(Package { functions, name })
+	}
+}
@@ -11,6 +11,16 @@ impl Expression {
 		prefix: &str,
 	) -> std::fmt::Result {
 		match &self.info {
+			ExpressionInfo::Assert(condition, message) => {
+				f.write_fmt(format_args!(
+					"{}{} message:'{}'\n",
+					initial_prefix,
+					"Assert".yellow(),
+					message.as_deref().unwrap_or("").cyan(),
+				))?;
+
+				condition.pretty_print(f, &format!("{}`- ", prefix), &format!("{}   ", prefix))
+			}
 			ExpressionInfo::Assign(name, operator, expression) => {
 				f.write_fmt(format_args!(
 					"{}{} name:'{}', op:'{}', type:'{}'\n",
@@ -34,6 +44,37 @@ impl Expression {
 				left.pretty_print(f, &format!("{}|- ", prefix), &format!("{}|  ", prefix))?;
 				right.pretty_print(f, &format!("{}`- ", prefix), &format!("{}|  ", prefix))
 			}
+			ExpressionInfo::Break => {
+				f.write_fmt(format_args!("{}{}\n", initial_prefix, "Break".yellow()))
+			}
+			ExpressionInfo::Constant(name, value) => {
+				f.write_fmt(format_args!(
+					"{}{} name:'{}'\n",
+					initial_prefix,
+					"Constant".yellow(),
+					name.name().cyan(),
+				))?;
+
+				value.pretty_print(f, &format!("{}`- ", prefix), &format!("{}   ", prefix))
+			}
+			ExpressionInfo::Destructure(names, expression) => {
+				let names = names
+					.iter()
+					.map(|name| name.name().to_string())
+					.collect::<Vec<String>>();
+
+				f.write_fmt(format_args!(
+					"{}{} names:'{}'\n",
+					initial_prefix,
+					"Destructure".yellow(),
+					names.join(", ").cyan(),
+				))?;
+
+				expression.pretty_print(f, &format!("{}`- ", prefix), &format!("{}   ", prefix))
+			}
+			ExpressionInfo::Continue => {
+				f.write_fmt(format_args!("{}{}\n", initial_prefix, "Continue".yellow()))
+			}
 			ExpressionInfo::Else(block) => {
 				f.write_fmt(format_args!("{}{}\n", initial_prefix, "Else".yellow()))?;
 				pretty_print_block(
@@ -133,6 +174,24 @@ impl Expression {
 
 				Ok(())
 			}
+			ExpressionInfo::Index(map, key) => {
+				f.write_fmt(format_args!("{}{}\n", initial_prefix, "Index".yellow()))?;
+
+				map.pretty_print(f, &format!("{}|- ", prefix), &format!("{}|  ", prefix))?;
+				key.pretty_print(f, &format!("{}`- ", prefix), &format!("{}   ", prefix))
+			}
+			ExpressionInfo::IndexAssign(map, key, operator, expression) => {
+				f.write_fmt(format_args!(
+					"{}{} op:'{}'\n",
+					initial_prefix,
+					"IndexAssign".yellow(),
+					format!("{:?}", operator).cyan(),
+				))?;
+
+				map.pretty_print(f, &format!("{}|- ", prefix), &format!("{}|  ", prefix))?;
+				key.pretty_print(f, &format!("{}|- ", prefix), &format!("{}|  ", prefix))?;
+				expression.pretty_print(f, &format!("{}`- ", prefix), &format!("{}   ", prefix))
+			}
 			ExpressionInfo::LogicOperation(left, right, operator) => {
 				f.write_fmt(format_args!(
 					"{}{} op:'{}'\n",
@@ -144,6 +203,55 @@ impl Expression {
 				left.pretty_print(f, &format!("{}|- ", prefix), &format!("{}|  ", prefix))?;
 				right.pretty_print(f, &format!("{}`- ", prefix), &format!("{}|  ", prefix))
 			}
+			ExpressionInfo::MapCall(operation, arguments) => {
+				f.write_fmt(format_args!(
+					"{}{} op:'{}'\n",
+					initial_prefix,
+					"MapCall".yellow(),
+					format!("{:?}", operation).cyan(),
+				))?;
+
+				for (index, argument) in arguments.iter().enumerate() {
+					if index == arguments.len() - 1 {
+						argument.pretty_print(
+							f,
+							&format!("{}`- ", prefix),
+							&format!("{}   ", prefix),
+						)?;
+					} else {
+						argument.pretty_print(
+							f,
+							&format!("{}|- ", prefix),
+							&format!("{}|  ", prefix),
+						)?;
+					}
+				}
+
+				Ok(())
+			}
+			ExpressionInfo::MapLiteral(entries) => {
+				f.write_fmt(format_args!(
+					"{}{}\n",
+					initial_prefix,
+					"MapLiteral".yellow()
+				))?;
+
+				for (key, value) in entries.iter() {
+					f.write_fmt(format_args!("{}|- {}\n", prefix, "(Entry)".yellow()))?;
+					key.pretty_print(
+						f,
+						&format!("{}|  |- ", prefix),
+						&format!("{}|  |  ", prefix),
+					)?;
+					value.pretty_print(
+						f,
+						&format!("{}|  `- ", prefix),
+						&format!("{}|     ", prefix),
+					)?;
+				}
+
+				Ok(())
+			}
 			ExpressionInfo::Primitive(_, value) => f.write_fmt(format_args!(
 				"{}{} type:'{}', value: '{}'\n",
 				initial_prefix,
@@ -151,10 +259,10 @@ impl Expression {
 				format!("{:?}", self.ty).cyan(),
 				value.cyan()
 			)),
-			ExpressionInfo::Return(expression) => {
+			ExpressionInfo::Return(expressions) => {
 				f.write_fmt(format_args!("{}{}\n", initial_prefix, "Return".yellow()))?;
 
-				if let Some(expression) = expression {
+				for expression in expressions.iter() {
 					expression.pretty_print(
 						f,
 						&format!("{}`- ", prefix),
@@ -174,6 +282,79 @@ impl Expression {
 					&format!("{}|  ", prefix),
 				)
 			}
+			ExpressionInfo::Switch(value, cases, default) => {
+				f.write_fmt(format_args!("{}{}\n", initial_prefix, "Switch".yellow()))?;
+
+				value.pretty_print(f, &format!("{}|- ", prefix), &format!("{}|  ", prefix))?;
+
+				for (index, (values, block)) in cases.iter().enumerate() {
+					let is_last = index == cases.len() - 1 && default.is_none();
+					let (case_prefix, inner_prefix) = if is_last {
+						(format!("{}`- ", prefix), format!("{}   ", prefix))
+					} else {
+						(format!("{}|- ", prefix), format!("{}|  ", prefix))
+					};
+
+					f.write_fmt(format_args!("{}{}\n", case_prefix, "(Case)".yellow()))?;
+
+					for value in values.iter() {
+						value.pretty_print(
+							f,
+							&format!("{}|- ", inner_prefix),
+							&format!("{}|  ", inner_prefix),
+						)?;
+					}
+
+					pretty_print_block(
+						&block,
+						f,
+						&format!("{}`- ", inner_prefix),
+						&format!("{}   ", inner_prefix),
+					)?;
+				}
+
+				if let Some(default) = default {
+					f.write_fmt(format_args!("{}`- {}\n", prefix, "(Default)".yellow()))?;
+
+					pretty_print_block(
+						&default,
+						f,
+						&format!("{}   `- ", prefix),
+						&format!("{}      ", prefix),
+					)?;
+				}
+
+				Ok(())
+			}
+			ExpressionInfo::Throw(value) => {
+				f.write_fmt(format_args!("{}{}\n", initial_prefix, "Throw".yellow()))?;
+
+				value.pretty_print(f, &format!("{}`- ", prefix), &format!("{}   ", prefix))
+			}
+			ExpressionInfo::Try(try_block, variable, catch_block) => {
+				f.write_fmt(format_args!("{}{}\n", initial_prefix, "Try".yellow()))?;
+
+				pretty_print_block(
+					&try_block,
+					f,
+					&format!("{}|- ", prefix),
+					&format!("{}|  ", prefix),
+				)?;
+
+				f.write_fmt(format_args!(
+					"{}|- {} name:'{}'\n",
+					prefix,
+					"(Catch variable)".yellow(),
+					variable.name().cyan(),
+				))?;
+
+				pretty_print_block(
+					&catch_block,
+					f,
+					&format!("{}`- ", prefix),
+					&format!("{}   ", prefix),
+				)
+			}
 			ExpressionInfo::UnaryOperation(expression, operator) => {
 				f.write_fmt(format_args!(
 					"{}{} op:'{}'\n",
@@ -202,6 +383,16 @@ impl Expression {
 					&format!("{}|  ", prefix),
 				)
 			}
+			ExpressionInfo::Yield(value) => {
+				f.write_fmt(format_args!("{}{}\n", initial_prefix, "Yield".yellow()))?;
+
+				match value {
+					Some(value) => {
+						value.pretty_print(f, &format!("{}`- ", prefix), &format!("{}   ", prefix))
+					}
+					None => Ok(()),
+				}
+			}
 		}
 	}
 }
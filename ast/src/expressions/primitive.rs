@@ -21,7 +21,46 @@ impl Expression {
 		let symbol = pair.as_str().to_string();
 		Ok(P::new(Expression {
 			info: ExpressionInfo::Primitive(eggscript_types::Primitive::Number, symbol),
-			span: pair.as_span().into(),
+			span: context.span(pair.as_span()),
+			ty: Some(type_handle),
+		}))
+	}
+
+	pub(crate) fn parse_null_literal(
+		context: &mut AstContext,
+		pair: Pair<Rule>,
+	) -> Result<P<Expression>> {
+		let type_handle = context
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.name_to_type_handle("null")
+			.context("Could not get 'null' literal type")?;
+
+		Ok(P::new(Expression {
+			info: ExpressionInfo::Primitive(eggscript_types::Primitive::Null, "null".into()),
+			span: context.span(pair.as_span()),
+			ty: Some(type_handle),
+		}))
+	}
+
+	pub(crate) fn parse_string_literal(
+		context: &mut AstContext,
+		pair: Pair<Rule>,
+	) -> Result<P<Expression>> {
+		let type_handle = context
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.name_to_type_handle("string")
+			.context("Could not get 'string' literal type")?;
+
+		// strip the surrounding quotes
+		let literal = pair.as_str();
+		let symbol = literal[1..literal.len() - 1].to_string();
+		Ok(P::new(Expression {
+			info: ExpressionInfo::Primitive(eggscript_types::Primitive::String, symbol),
+			span: context.span(pair.as_span()),
 			ty: Some(type_handle),
 		}))
 	}
@@ -11,20 +11,19 @@ impl Expression {
 		context: &mut AstContext,
 		pair: Pair<Rule>,
 	) -> Result<P<Expression>> {
-		let span = pair.as_span().into();
-		let mut pairs = pair.into_inner();
+		let span = context.span(pair.as_span());
+		let pairs = pair.into_inner();
 
-		let value = if let Some(pair) = pairs.next() {
-			Some(
+		let mut values = vec![];
+		for pair in pairs {
+			values.push(
 				Expression::parse_pair(context, pair)
 					.context("Could not parse return statement pair")??,
-			)
-		} else {
-			None
-		};
+			);
+		}
 
 		Ok(P::new(Expression {
-			info: ExpressionInfo::Return(value),
+			info: ExpressionInfo::Return(values),
 			span,
 			ty: None,
 		}))
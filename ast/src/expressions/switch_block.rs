@@ -0,0 +1,65 @@
+use anyhow::{Context, Result};
+use eggscript_types::P;
+use pest::iterators::Pair;
+
+use crate::expressions::{Expression, ExpressionInfo};
+use crate::parser::Rule;
+use crate::AstContext;
+
+use super::Block;
+
+impl Expression {
+	pub(crate) fn parse_switch_block(
+		context: &mut AstContext,
+		pair: Pair<Rule>,
+	) -> Result<P<Expression>> {
+		let span = context.span(pair.as_span());
+		let mut pairs = pair.into_inner();
+
+		let value =
+			Expression::parse_pair(context, pairs.next().context("Could not get switch value")?)
+				.context("Could not parse pair")??;
+
+		let mut cases = vec![];
+		let mut default = None;
+		for pair in pairs {
+			let rule = pair.as_rule();
+			let case_span = context.span(pair.as_span());
+
+			let mut values = vec![];
+			let mut expressions = vec![];
+			for pair in pair.into_inner() {
+				if let Rule::block_inner = pair.as_rule() {
+					expressions = pair
+						.into_inner()
+						.map(|p| {
+							Expression::parse_pair(context, p)
+								.expect("Expected expression where there is none")
+						})
+						.collect::<Result<Vec<P<Expression>>>>()?;
+				} else {
+					values.push(
+						Expression::parse_pair(context, pair).context("Could not parse pair")??,
+					);
+				}
+			}
+
+			let block = P::new(Block {
+				expressions,
+				span: case_span,
+			});
+
+			if let Rule::switch_default = rule {
+				default = Some(block);
+			} else {
+				cases.push((values, block));
+			}
+		}
+
+		Ok(P::new(Expression {
+			info: ExpressionInfo::Switch(value, cases, default),
+			span,
+			ty: None,
+		}))
+	}
+}
@@ -0,0 +1,27 @@
+use anyhow::{Context, Result};
+use eggscript_types::P;
+use pest::iterators::Pair;
+
+use crate::expressions::{Expression, ExpressionInfo};
+use crate::parser::Rule;
+use crate::AstContext;
+
+impl Expression {
+	pub(crate) fn parse_throw_statement(
+		context: &mut AstContext,
+		pair: Pair<Rule>,
+	) -> Result<P<Expression>> {
+		let span = context.span(pair.as_span());
+		let mut pairs = pair.into_inner();
+
+		let value =
+			Expression::parse_pair(context, pairs.next().context("Could not get thrown value")?)
+				.context("Could not parse thrown value")??;
+
+		Ok(P::new(Expression {
+			info: ExpressionInfo::Throw(value),
+			span,
+			ty: None,
+		}))
+	}
+}
@@ -0,0 +1,51 @@
+use anyhow::{Context, Result};
+use eggscript_types::P;
+use pest::iterators::Pair;
+
+use crate::expressions::{Block, Expression, ExpressionInfo};
+use crate::parser::Rule;
+use crate::{AstContext, Ident};
+
+impl Expression {
+	pub(crate) fn parse_try_block(
+		context: &mut AstContext,
+		pair: Pair<Rule>,
+	) -> Result<P<Expression>> {
+		let span = context.span(pair.as_span());
+		let mut pairs = pair.into_inner();
+
+		let try_block = Expression::parse_block_inner(
+			context,
+			pairs.next().context("Could not get try block")?,
+		)?;
+
+		let variable_pair = pairs.next().context("Could not get catch variable")?;
+		let variable = Ident::new(
+			variable_pair.as_str(),
+			context.span(variable_pair.as_span()),
+		);
+
+		let catch_block = Expression::parse_block_inner(
+			context,
+			pairs.next().context("Could not get catch block")?,
+		)?;
+
+		Ok(P::new(Expression {
+			info: ExpressionInfo::Try(try_block, variable, catch_block),
+			span,
+			ty: None,
+		}))
+	}
+
+	fn parse_block_inner(context: &mut AstContext, pair: Pair<Rule>) -> Result<P<Block>> {
+		let span = context.span(pair.as_span());
+		let expressions = pair
+			.into_inner()
+			.map(|p| {
+				Expression::parse_pair(context, p).expect("Expected expression where there is none")
+			})
+			.collect::<Result<Vec<P<Expression>>>>()?;
+
+		Ok(P::new(Block { expressions, span }))
+	}
+}
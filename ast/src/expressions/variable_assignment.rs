@@ -1,6 +1,6 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use eggscript_types::P;
-use pest::iterators::Pair;
+use pest::iterators::{Pair, Pairs};
 
 use crate::expressions::{Expression, ExpressionInfo};
 use crate::parser::Rule;
@@ -11,17 +11,48 @@ impl Expression {
 		context: &mut AstContext,
 		pair: Pair<Rule>,
 	) -> Result<P<Expression>> {
-		let span = pair.as_span().into();
+		let span = context.span(pair.as_span());
 		let mut inner = pair.into_inner();
-		let mut symbol = inner
-			.next()
-			.context("Could not get variable name")?
-			.as_str();
+		let variable_pair = inner.next().context("Could not get variable name")?;
+		let symbol = variable_pair.as_str().trim();
 
-		symbol = symbol.trim();
+		if let Rule::path_access = variable_pair.as_rule() {
+			if context.get_constant(symbol).is_some() {
+				bail!("Cannot assign to constant '{}'", symbol);
+			} else {
+				bail!(
+					"Cannot assign to '{}', variable names start with '%'",
+					symbol
+				);
+			}
+		}
+
+		if let Rule::index_access = variable_pair.as_rule() {
+			let (map, key) = Expression::parse_index_operands(context, variable_pair)?;
+			let (operator, expression) = Expression::parse_assignment_rvalue(context, inner)?;
+
+			return Ok(P::new(Expression {
+				ty: None,
+				info: ExpressionInfo::IndexAssign(map, key, operator, expression),
+				span,
+			}));
+		}
 
 		let variable_ident = Ident::new(symbol, span);
 
+		let (operator, expression) = Expression::parse_assignment_rvalue(context, inner)?;
+
+		Ok(P::new(Expression {
+			ty: None,
+			info: ExpressionInfo::Assign(variable_ident, operator, expression),
+			span,
+		}))
+	}
+
+	fn parse_assignment_rvalue(
+		context: &mut AstContext,
+		mut inner: Pairs<Rule>,
+	) -> Result<(BinaryOperator, P<Expression>)> {
 		let operator = BinaryOperator::parse_assignment(
 			inner
 				.next()
@@ -36,10 +67,6 @@ impl Expression {
 		)
 		.context("Could not parse pair")??;
 
-		Ok(P::new(Expression {
-			ty: expression.ty,
-			info: ExpressionInfo::Assign(variable_ident, operator, expression),
-			span,
-		}))
+		Ok((operator, expression))
 	}
 }
@@ -11,7 +11,7 @@ impl Expression {
 		context: &mut AstContext,
 		pair: Pair<Rule>,
 	) -> Result<P<Expression>> {
-		let span = pair.as_span().into();
+		let span = context.span(pair.as_span());
 		let mut inner = pair.into_inner();
 		let mut symbol = inner
 			.next()
@@ -19,28 +19,18 @@ impl Expression {
 			.as_str();
 		symbol = symbol.trim();
 
-		let type_store = context
-			.type_store
-			.lock()
-			.expect("Could not lock type store");
-
+		// variables without a type take the type of the value they are first assigned when lowered
 		let ty = if let Rule::type_ident = inner
 			.peek()
 			.context("Could not peek variable type")?
 			.as_rule()
 		{
 			let type_pair = inner.next().context("Could not get variable type")?;
-			Some(
-				type_store
-					.name_to_type_handle(type_pair.as_str())
-					.context("Could not find type")?,
-			)
+			Some(context.resolve_type(type_pair.as_str())?)
 		} else {
 			None
 		};
 
-		drop(type_store);
-
 		let variable_ident = Ident::new(symbol, span);
 
 		let expression = Expression::parse_pair(
@@ -49,22 +39,6 @@ impl Expression {
 		)
 		.context("Could not parse pair")??;
 
-		let ty = if let None = ty
-			&& let Some(expression_ty) = expression.ty
-		{
-			Some(expression_ty)
-		} else if let None = ty {
-			Some(
-				context
-					.type_store
-					.lock()
-					.expect("Could not lock type store")
-					.create_unknown(),
-			)
-		} else {
-			ty
-		};
-
 		Ok(P::new(Expression {
 			info: ExpressionInfo::Assign(variable_ident, BinaryOperator::Equal, expression),
 			span,
@@ -0,0 +1,31 @@
+use anyhow::{Context, Result};
+use eggscript_types::P;
+use pest::iterators::Pair;
+
+use crate::expressions::{Expression, ExpressionInfo};
+use crate::parser::Rule;
+use crate::AstContext;
+
+impl Expression {
+	pub(crate) fn parse_yield_statement(
+		context: &mut AstContext,
+		pair: Pair<Rule>,
+	) -> Result<P<Expression>> {
+		let span = context.span(pair.as_span());
+
+		// the first pair is the keyword
+		let value = match pair.into_inner().nth(1) {
+			Some(value) => Some(
+				Expression::parse_pair(context, value)
+					.context("Could not parse yielded value")??,
+			),
+			None => None,
+		};
+
+		Ok(P::new(Expression {
+			info: ExpressionInfo::Yield(value),
+			span,
+			ty: None,
+		}))
+	}
+}
@@ -3,6 +3,7 @@
 mod context;
 mod expressions;
 mod lower;
+mod module;
 mod operators;
 mod parser;
 mod pretty_error;
@@ -11,12 +12,17 @@ mod symbol;
 pub(crate) use context::AstContext;
 pub use expressions::Function;
 pub use expressions::FunctionArgument;
+pub use expressions::Package;
 pub use lower::compile_expression;
 pub use lower::compile_function;
+pub use module::SourceFile;
 pub use operators::BinaryOperator;
 pub use operators::UnaryOperator;
+pub use parser::parse_additional_string;
 pub use parser::parse_file;
 pub use parser::parse_string;
+pub use parser::parse_string_with_options;
+pub use parser::CompileOptions;
 pub use parser::Program;
 pub use symbol::Ident;
 pub use symbol::Span;
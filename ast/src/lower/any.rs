@@ -0,0 +1,101 @@
+use anyhow::{Context, Result};
+use eggscript_mir::{MIRInfo, Transition, UnitHandle, Value, MIR};
+use eggscript_types::{TypeHandle, P};
+
+use crate::expressions::Expression;
+use crate::lower::AstLowerContext;
+
+impl AstLowerContext {
+	/// `any` values are converted to numbers when math or conditions use them, other values are
+	/// left as they are. Operators only work on numbers, so this is the one coercion they need: the
+	/// conversion looks at what the value holds at runtime, passes numbers through, parses strings
+	/// and raises an exception for anything else
+	pub(crate) fn convert_any_to_number(
+		&mut self,
+		value: P<Value>,
+		expression: &P<Expression>,
+	) -> Result<(Vec<UnitHandle>, P<Value>)> {
+		let number = {
+			let type_store = self
+				.program
+				.type_store
+				.lock()
+				.expect("Could not lock type store");
+
+			if !type_store.is_any(value.ty()) {
+				return Ok((vec![], value));
+			}
+
+			type_store
+				.name_to_type_handle("number")
+				.context("Could not get 'number' type")?
+		};
+
+		return Ok(self.convert_any(value, number, expression));
+	}
+
+	/// Converts between `any` and the types it can hold, returns `None` if neither side is `any`
+	pub(crate) fn coerce_any(
+		&mut self,
+		value: &P<Value>,
+		ty: TypeHandle,
+		expression: &P<Expression>,
+	) -> Option<(Vec<UnitHandle>, P<Value>)> {
+		let (to_any, from_any) = {
+			let type_store = self
+				.program
+				.type_store
+				.lock()
+				.expect("Could not lock type store");
+
+			let can_hold = |ty: TypeHandle| {
+				["number", "string"].iter().any(|name| {
+					type_store
+						.name_to_type_handle(name)
+						.is_some_and(|handle| type_store.are_types_compatible(handle, ty))
+				})
+			};
+
+			(
+				type_store.is_any(ty) && can_hold(value.ty()),
+				type_store.is_any(value.ty()) && can_hold(ty),
+			)
+		};
+
+		if to_any {
+			let result = self.value_store.new_temp(ty);
+			let unit = self.unit_store.new_unit(
+				vec![MIR::new(
+					MIRInfo::ToAny(result.clone(), value.clone()),
+					expression.span,
+				)],
+				Transition::Next,
+			);
+
+			return Some((vec![unit], result));
+		} else if from_any {
+			return Some(self.convert_any(value.clone(), ty, expression));
+		} else {
+			return None;
+		}
+	}
+
+	/// Checked conversion that raises an exception at runtime if the value cannot be converted
+	fn convert_any(
+		&mut self,
+		value: P<Value>,
+		ty: TypeHandle,
+		expression: &P<Expression>,
+	) -> (Vec<UnitHandle>, P<Value>) {
+		let result = self.value_store.new_temp(ty);
+		let unit = self.unit_store.new_unit(
+			vec![MIR::new(
+				MIRInfo::FromAny(result.clone(), value),
+				expression.span,
+			)],
+			Transition::Next,
+		);
+
+		return (vec![unit], result);
+	}
+}
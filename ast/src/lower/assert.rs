@@ -0,0 +1,65 @@
+use anyhow::{bail, Result};
+use eggscript_mir::{PrimitiveValue, Transition, UnitHandle, Value};
+use eggscript_types::P;
+
+use crate::expressions::{Expression, ExpressionInfo};
+use crate::lower::AstLowerContext;
+
+impl AstLowerContext {
+	pub(crate) fn lower_assert(
+		&mut self,
+		expression: &P<Expression>,
+	) -> Result<(Vec<UnitHandle>, Option<P<Value>>)> {
+		let ExpressionInfo::Assert(condition, message) = &expression.info else {
+			unreachable!();
+		};
+
+		if self.program.options.strip_asserts {
+			return Ok((vec![], None));
+		}
+
+		let (mut units, condition_value) = self.lower_expression(condition)?;
+		let condition_value =
+			condition_value.expect("Expected assert condition where there is none");
+
+		if self.is_string(&condition_value) {
+			bail!("The condition of an assert has to be a number");
+		}
+
+		let (line, column) = self.program.line_and_column(&expression.span);
+		let mut failure = format!(
+			"{}:{}:{}: assertion `{}` failed",
+			self.program.files[expression.span.file() as usize].name,
+			line,
+			column,
+			self.program.source_text(&condition.span).trim(),
+		);
+
+		if let Some(message) = message {
+			failure = format!("{}: {}", failure, message);
+		}
+
+		let string = self
+			.program
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.name_to_type_handle("string")
+			.expect("Could not get 'string' type");
+
+		let failure = self
+			.value_store
+			.new_primitive(string, PrimitiveValue::String(failure));
+
+		let unit_after = self.unit_store.new_unit(vec![], Transition::Next);
+
+		units.push(
+			self.unit_store
+				.new_unit(vec![], Transition::GotoIfTrue(unit_after, condition_value)),
+		);
+		units.push(self.unit_store.new_unit(vec![], Transition::Throw(failure)));
+		units.push(unit_after);
+
+		Ok((units, None))
+	}
+}
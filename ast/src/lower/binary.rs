@@ -1,9 +1,10 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use eggscript_mir::{MIRInfo, Transition, UnitHandle, Value, MIR};
 use eggscript_types::P;
 
 use crate::expressions::{Expression, ExpressionInfo};
 use crate::lower::AstLowerContext;
+use crate::BinaryOperator;
 
 impl AstLowerContext {
 	pub(crate) fn lower_binary_operation(
@@ -14,13 +15,59 @@ impl AstLowerContext {
 			unreachable!();
 		};
 
-		let (mut left_units, left_value) = self.lower_expression(left)?;
-		let (mut right_units, right_value) = self.lower_expression(right)?;
+		if (operator == &BinaryOperator::Equal || operator == &BinaryOperator::NotEqual)
+			&& let Some(checked) = AstLowerContext::get_null_checked_operand(left, right)
+		{
+			return self.lower_null_check(expression, checked, operator);
+		}
 
+		// each side is converted right after it is evaluated, so the operands stay in order
+		let (mut left_units, left_value) = self.lower_expression(left)?;
 		let left_value = left_value.context("Could not find left value")?;
+		self.check_not_optional(&left_value, left)?;
+		let (mut convert_units, left_value) = self.convert_any_to_number(left_value, left)?;
+		left_units.append(&mut convert_units);
+
+		let (mut right_units, right_value) = self.lower_expression(right)?;
 		let right_value = right_value.context("Could not find right value")?;
+		self.check_not_optional(&right_value, right)?;
+		let (mut convert_units, right_value) = self.convert_any_to_number(right_value, right)?;
+		right_units.append(&mut convert_units);
 
-		let ty = right_value.ty();
+		if self.is_string(&left_value) || self.is_string(&right_value) {
+			bail!("Operator '{:?}' cannot be used on strings", operator);
+		}
+
+		if self.is_map(&left_value) || self.is_map(&right_value) {
+			bail!("Operator '{:?}' cannot be used on maps", operator);
+		}
+
+		if self.is_enum(&left_value) || self.is_enum(&right_value) {
+			if operator != &BinaryOperator::Equal && operator != &BinaryOperator::NotEqual {
+				bail!("Operator '{:?}' cannot be used on enum values", operator);
+			}
+
+			if !self
+				.program
+				.type_store
+				.lock()
+				.expect("Could not lock type store")
+				.are_types_compatible(left_value.ty(), right_value.ty())
+			{
+				bail!("Enum values can only be compared to values of the same enum");
+			}
+		}
+
+		let ty = if operator.is_comparison() {
+			self.program
+				.type_store
+				.lock()
+				.expect("Could not lock type store")
+				.name_to_type_handle("number")
+				.context("Could not get 'number' type")?
+		} else {
+			right_value.ty()
+		};
 
 		let result = self.value_store.new_temp(ty); // TODO fill out type handle correctly
 
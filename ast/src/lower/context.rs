@@ -13,6 +13,15 @@ use crate::{
 	Function, Program,
 };
 
+/// Units that `break` and `continue` jump to in the closest enclosing loop
+#[derive(Clone)]
+pub struct Loop {
+	pub break_unit: UnitHandle,
+	pub continue_unit: UnitHandle,
+	/// Try blocks that were entered outside of the loop, the ones inside it are exited by jumps
+	pub try_depth: usize,
+}
+
 #[derive(Clone)]
 pub struct Logic {
 	pub operator: LogicOperator,
@@ -21,23 +30,34 @@ pub struct Logic {
 }
 
 pub struct AstLowerContext {
+	/// Function that is being lowered, `None` for the global scope
+	pub function: Option<P<Function>>,
 	pub logic_stack: Vec<Logic>,
+	pub loop_stack: Vec<Loop>,
+	/// Optional variables that cannot be null in the code that is being lowered
+	pub narrowed: Vec<String>,
 	pub program: P<Program>,
+	/// Try blocks the code that is being lowered is in
+	pub try_depth: usize,
 	pub unit_store: UnitStore,
 	pub value_store: ValueStore,
 }
 
 impl Into<EggscriptLowerContext> for AstLowerContext {
 	fn into(self) -> EggscriptLowerContext {
-		EggscriptLowerContext::new(self.program.type_store.clone(), &self.program.file_name)
+		EggscriptLowerContext::new(self.program.type_store.clone(), self.program.file_names())
 	}
 }
 
 impl AstLowerContext {
 	pub fn new(program: P<Program>) -> AstLowerContext {
 		AstLowerContext {
+			function: None,
 			logic_stack: vec![],
+			loop_stack: vec![],
+			narrowed: vec![],
 			program,
+			try_depth: 0,
 			unit_store: UnitStore::new(),
 			value_store: ValueStore::new(),
 		}
@@ -54,7 +74,7 @@ impl AstLowerContext {
 			builder,
 			module,
 			self.program.type_store.clone(),
-			&self.program.file_name,
+			self.program.file_names(),
 		)
 	}
 
@@ -63,22 +83,71 @@ impl AstLowerContext {
 		expression: &P<Expression>,
 	) -> Result<(Vec<UnitHandle>, Option<P<Value>>)> {
 		match expression.info {
+			ExpressionInfo::Assert(_, _) => self.lower_assert(expression),
 			ExpressionInfo::Assign(_, _, _) => self.lower_variable_assignment(expression),
 			ExpressionInfo::BinaryOperation(_, _, _) => self.lower_binary_operation(expression),
+			ExpressionInfo::Break => self.lower_break_statement(expression),
+			// uses of constants were replaced by their values while parsing
+			ExpressionInfo::Constant(_, _) => Ok((vec![], None)),
+			ExpressionInfo::Continue => self.lower_continue_statement(expression),
+			ExpressionInfo::Destructure(_, _) => self.lower_destructure(expression),
 			ExpressionInfo::Else(_) => unreachable!(),
 			ExpressionInfo::FieldAccess(_) => self.lower_field_access(expression),
 			ExpressionInfo::For(_, _, _, _) => self.lower_for_block(expression),
 			ExpressionInfo::FunctionCall(_, _) => self.lower_function_call(expression),
 			ExpressionInfo::If(_, _, _) => self.lower_if_block(expression),
+			ExpressionInfo::Index(_, _) => self.lower_index(expression),
+			ExpressionInfo::IndexAssign(_, _, _, _) => self.lower_index_assign(expression),
 			ExpressionInfo::LogicOperation(_, _, _) => self.lower_logic_operation(expression),
+			ExpressionInfo::MapCall(_, _) => self.lower_map_call(expression),
+			ExpressionInfo::MapLiteral(_) => self.lower_map_literal(expression, None),
 			ExpressionInfo::Primitive(_, _) => self.lower_primitive(expression),
 			ExpressionInfo::Return(_) => self.lower_return_statement(expression),
 			ExpressionInfo::Scope(_) => self.lower_scope(expression),
+			ExpressionInfo::Switch(_, _, _) => self.lower_switch_block(expression),
+			ExpressionInfo::Throw(_) => self.lower_throw_statement(expression),
+			ExpressionInfo::Try(_, _, _) => self.lower_try_block(expression),
 			ExpressionInfo::UnaryOperation(_, _) => self.lower_unary(expression),
 			ExpressionInfo::While(_, _) => self.lower_while_block(expression),
+			ExpressionInfo::Yield(_) => self.lower_yield_statement(expression),
 		}
 	}
 
+	/// Strings can only be stored and passed around, math is not supported on them
+	pub(crate) fn is_string(&self, value: &P<Value>) -> bool {
+		let type_store = self
+			.program
+			.type_store
+			.lock()
+			.expect("Could not lock type store");
+
+		let Some(string) = type_store.name_to_type_handle("string") else {
+			return false;
+		};
+
+		return type_store.are_types_compatible(value.ty(), string);
+	}
+
+	/// Enum values are numbers underneath, but only support comparing them for equality
+	pub(crate) fn is_enum(&self, value: &P<Value>) -> bool {
+		self.program
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.get_enum_variants(value.ty())
+			.is_some()
+	}
+
+	/// Maps are references to values in the runtime, so math and comparisons are not supported
+	pub(crate) fn is_map(&self, value: &P<Value>) -> bool {
+		self.program
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.get_map_types(value.ty())
+			.is_some()
+	}
+
 	pub fn lower_block(&mut self, block: &P<Block>) -> Result<(Vec<UnitHandle>, Option<P<Value>>)> {
 		let mut units: Vec<UnitHandle> = vec![];
 		for expression in block.expressions.iter() {
@@ -96,6 +165,7 @@ pub fn compile_function(
 	expression: P<Expression>,
 ) -> Result<(AstLowerContext, IndexMap<UnitHandle, Unit>)> {
 	let mut lower_context = AstLowerContext::new(program);
+	lower_context.function = Some(function.clone());
 
 	let mut mir = vec![];
 	let mut index = 0;
@@ -133,7 +203,7 @@ pub fn compile_function(
 		units.push(
 			lower_context
 				.unit_store
-				.new_unit(vec![], eggscript_mir::Transition::Return(None)),
+				.new_unit(vec![], eggscript_mir::Transition::Return(vec![])),
 		);
 	}
 
@@ -0,0 +1,63 @@
+use anyhow::{bail, Context, Result};
+use eggscript_mir::{MIRInfo, Transition, UnitHandle, Value, MIR};
+use eggscript_types::P;
+
+use crate::expressions::{Expression, ExpressionInfo};
+use crate::lower::AstLowerContext;
+
+impl AstLowerContext {
+	pub(crate) fn lower_destructure(
+		&mut self,
+		expression: &P<Expression>,
+	) -> Result<(Vec<UnitHandle>, Option<P<Value>>)> {
+		let ExpressionInfo::Destructure(names, value) = &expression.info else {
+			unreachable!();
+		};
+
+		let (mut units, rvalue) = self.lower_expression(value)?;
+		let rvalue = rvalue.context("Destructured expression does not have a value")?;
+
+		let element_types = self
+			.program
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.get_tuple_element_types(rvalue.ty())
+			.cloned();
+
+		let Some(element_types) = element_types else {
+			bail!("Cannot destructure a value that is not a tuple");
+		};
+
+		if element_types.len() != names.len() {
+			bail!(
+				"Cannot destructure a tuple of {} values into {} variables",
+				element_types.len(),
+				names.len()
+			);
+		}
+
+		let mut mir = vec![];
+		let mut lvalues = vec![];
+		for (name, element_type) in names.iter().zip(element_types.into_iter()) {
+			let (lvalue, is_new) = self.value_store.new_location(name.name(), element_type);
+			if is_new {
+				mir.push(MIR::new(
+					MIRInfo::Allocate(lvalue.clone(), None),
+					name.span(),
+				));
+			}
+
+			lvalues.push(lvalue);
+		}
+
+		mir.push(MIR::new(
+			MIRInfo::Destructure(lvalues, rvalue),
+			&expression.span,
+		));
+
+		units.push(self.unit_store.new_unit(mir, Transition::Next));
+
+		Ok((units, None))
+	}
+}
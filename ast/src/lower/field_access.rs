@@ -23,6 +23,8 @@ impl AstLowerContext {
 
 		assert!(!is_new, "Undefined variable access {}", name.name());
 
-		Ok((vec![], Some(variable_value)))
+		let (units, value) = self.unwrap_narrowed(name.name(), variable_value, expression)?;
+
+		Ok((units, Some(value)))
 	}
 }
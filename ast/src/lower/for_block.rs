@@ -17,6 +17,13 @@ impl AstLowerContext {
 		let (mut declaration_units, _) = self.lower_expression(declaration)?;
 
 		let (mut conditional_units, conditional_value) = self.lower_expression(conditional)?;
+		let conditional_value =
+			conditional_value.expect("Expected conditional value where there is none");
+		self.check_not_optional(&conditional_value, conditional)?;
+		let (mut convert_units, conditional_value) =
+			self.convert_any_to_number(conditional_value, conditional)?;
+		conditional_units.append(&mut convert_units);
+
 		let first_conditional_unit = *conditional_units
 			.iter()
 			.nth(0)
@@ -24,22 +31,22 @@ impl AstLowerContext {
 
 		let (mut update_units, _) = self.lower_expression(update)?;
 
-		let (mut block_units, _) = self.lower_block(block)?;
-
+		// `continue` runs the update before the condition is checked again
+		let continue_unit = self.unit_store.new_unit(vec![], Transition::Next);
 		let unit_after = self.unit_store.new_unit(vec![], Transition::Next);
 
+		let (mut block_units, _) = self.lower_loop_block(block, continue_unit, unit_after)?;
+
 		let mut units = vec![];
 		units.append(&mut declaration_units);
 		units.append(&mut conditional_units);
 		units.push(self.unit_store.new_unit(
 			vec![],
-			Transition::GotoIfFalse(
-				unit_after,
-				conditional_value.expect("Expected conditional value where there is none"),
-			),
+			Transition::GotoIfFalse(unit_after, conditional_value),
 		));
 
 		units.append(&mut block_units);
+		units.push(continue_unit);
 		units.append(&mut update_units);
 		units.push(
 			self.unit_store
@@ -1,5 +1,5 @@
-use anyhow::{Context, Result};
-use eggscript_mir::{MIRInfo, Transition, UnitHandle, Value, MIR};
+use anyhow::{bail, Context, Result};
+use eggscript_mir::{Dispatch, MIRInfo, Transition, UnitHandle, Value, MIR};
 use eggscript_types::P;
 use std::ops::Deref;
 
@@ -15,17 +15,135 @@ impl AstLowerContext {
 			unreachable!();
 		};
 
-		let function = self
-			.program
-			.function_name_to_function
-			.get(name.name())
-			.context(format!("Could not get function '{}'", name.name()))?
-			.clone();
+		let (function, dispatch) = if let Some(parent_name) = name.name().strip_prefix("Parent::") {
+			let caller = self
+				.function
+				.as_ref()
+				.filter(|caller| caller.overrides.as_deref() == Some(parent_name))
+				.context(format!(
+					"'{}' can only be called from a package function that overrides '{}'",
+					name.name(),
+					parent_name
+				))?;
+
+			let function = self
+				.program
+				.function_name_to_function
+				.get(parent_name)
+				.context(format!("Could not get function '{}'", parent_name))?
+				.clone();
+
+			(function, Dispatch::Parent(caller.id))
+		} else {
+			let function = self
+				.program
+				.function_name_to_function
+				.get(name.name())
+				.context(format!("Could not get function '{}'", name.name()))?
+				.clone();
+
+			if function.scope.is_none() && !self.program.options.allows_native(name.name()) {
+				bail!(
+					"Native function '{}' is not allowed in this sandbox",
+					name.name()
+				);
+			}
+
+			if let Some(overrides) = &function.overrides {
+				bail!(
+					"Package function '{}' cannot be called directly, call '{}' instead",
+					name.name(),
+					overrides
+				);
+			}
+
+			// packages in files loaded later with `exec` can override any script function, even
+			// after this call was compiled
+			let dispatch = if function.scope.is_some() {
+				Dispatch::Table
+			} else {
+				Dispatch::Direct
+			};
+
+			(function, dispatch)
+		};
+
+		// functions declared in modules can only be called through an imported namespace, files
+		// loaded with `exec` share the global namespace with the main file
+		let caller_file = expression.span.file();
+		let callee_file = function.span.file();
+		if function.scope.is_some()
+			&& caller_file != callee_file
+			&& let Some(namespace) = &self.program.files[callee_file as usize].namespace
+		{
+			let caller = &self.program.files[caller_file as usize];
+
+			if !caller.imports.contains(namespace) {
+				bail!(
+					"Module '{}' has to be imported before calling '{}'",
+					namespace,
+					name.name()
+				);
+			} else if !function.exported {
+				bail!(
+					"Function '{}' is not exported by module '{}'",
+					name.name(),
+					namespace
+				);
+			}
+		}
+
+		if arguments.len() < function.ty.required_argument_count {
+			bail!(
+				"Function '{}' expects at least {} arguments, got {}",
+				name.name(),
+				function.ty.required_argument_count,
+				arguments.len()
+			);
+		} else if arguments.len() > function.arguments.len() && function.ty.variadic_type.is_none()
+		{
+			bail!(
+				"Function '{}' expects at most {} arguments, got {}",
+				name.name(),
+				function.arguments.len(),
+				arguments.len()
+			);
+		}
+
+		// omitted arguments are filled in with their default values
+		let defaults = function
+			.arguments
+			.iter()
+			.skip(arguments.len())
+			.map(|argument| {
+				argument
+					.default
+					.clone()
+					.context(format!("Argument '{}' has no default value", argument.name))
+			})
+			.collect::<Result<Vec<P<Expression>>>>()?;
 
 		let mut units = vec![];
 		let mut argument_values = vec![];
-		for argument in arguments.iter() {
+		for (index, argument) in arguments.iter().chain(defaults.iter()).enumerate() {
 			let (mut argument_units, value) = self.lower_expression(argument)?;
+
+			let argument_type = function
+				.arguments
+				.get(index)
+				.map(|argument| argument.ty)
+				.or(function.ty.variadic_type);
+
+			let value = match (value, argument_type) {
+				(Some(value), Some(argument_type)) => {
+					let (mut coerce_units, value) =
+						self.coerce_value(value, argument_type, argument)?;
+					argument_units.append(&mut coerce_units);
+					Some(value)
+				}
+				(value, _) => value,
+			};
+
 			let mut extra_unit = None;
 			if let Some(value) = &value {
 				match value.deref() {
@@ -82,6 +200,7 @@ impl AstLowerContext {
 				MIRInfo::CallFunction(
 					function.name.clone(),
 					function.id,
+					dispatch,
 					argument_values,
 					result.clone(),
 				),
@@ -18,6 +18,8 @@ impl AstLowerContext {
 		let mut last_jump_unit = 0;
 		let mut last_conditional_value = None;
 
+		let narrowed = self.narrowed.len();
+
 		let mut next = Some(expression.clone());
 		while let Some(expression) = next.as_ref() {
 			match &expression.deref().info {
@@ -40,6 +42,9 @@ impl AstLowerContext {
 					last_jump_unit = jump_unit;
 					last_conditional_value = conditional_value;
 
+					// the blocks that follow only run if the condition was false
+					self.narrow(AstLowerContext::get_non_null_variables(conditional, false));
+
 					units.append(&mut if_units);
 					next = new_next.clone();
 				}
@@ -68,6 +73,8 @@ impl AstLowerContext {
 
 		units.push(unit_after);
 
+		self.narrowed.truncate(narrowed);
+
 		Ok((units, None))
 	}
 
@@ -78,19 +85,22 @@ impl AstLowerContext {
 		unit_after: UnitHandle,
 	) -> Result<(Vec<UnitHandle>, Option<P<Value>>, UnitHandle, UnitHandle)> {
 		let (mut conditional_units, conditional_value) = self.lower_expression(conditional)?;
+		let conditional_value = conditional_value.context("Could not get conditional value")?;
+		self.check_not_optional(&conditional_value, conditional)?;
+		let (mut convert_units, conditional_value) =
+			self.convert_any_to_number(conditional_value, conditional)?;
+		conditional_units.append(&mut convert_units);
+
+		let narrowed = self.narrow(AstLowerContext::get_non_null_variables(conditional, true));
 		let (mut block_units, _) = self.lower_block(code).context("Could not lower block")?;
+		self.narrowed.truncate(narrowed);
 
 		let mut units = vec![];
 		units.append(&mut conditional_units);
 
 		let jump_unit = self.unit_store.new_unit(
 			vec![],
-			Transition::GotoIfFalse(
-				unit_after,
-				conditional_value
-					.clone()
-					.context("Could not get conditional value")?,
-			),
+			Transition::GotoIfFalse(unit_after, conditional_value.clone()),
 		);
 
 		units.push(jump_unit);
@@ -103,7 +113,12 @@ impl AstLowerContext {
 
 		let conditional_unit_start = units[0];
 
-		Ok((units, conditional_value, conditional_unit_start, jump_unit))
+		Ok((
+			units,
+			Some(conditional_value),
+			conditional_unit_start,
+			jump_unit,
+		))
 	}
 
 	fn lower_else_block_impl(
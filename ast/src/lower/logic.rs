@@ -37,16 +37,31 @@ impl AstLowerContext {
 		};
 
 		let (mut left_units, left_value) = self.lower_expression(left)?;
+
+		// the right side only runs if the left side did not short circuit
+		let narrowed = self.narrow(AstLowerContext::get_non_null_variables(
+			left,
+			operator == &LogicOperator::And,
+		));
 		let (mut right_units, right_value) = self.lower_expression(right)?;
+		self.narrowed.truncate(narrowed);
+
+		let left_value = left_value.context("Could not find left value")?;
+		let right_value = right_value.context("Could not find right value")?;
+
+		self.check_not_optional(&left_value, left)?;
+		self.check_not_optional(&right_value, right)?;
+
+		let (mut convert_units, left_value) = self.convert_any_to_number(left_value, left)?;
+		left_units.append(&mut convert_units);
+		let (mut convert_units, right_value) = self.convert_any_to_number(right_value, right)?;
+		right_units.append(&mut convert_units);
 
 		let logic = self
 			.logic_stack
 			.last_mut()
 			.expect("Could not get last on logic stack");
 
-		let left_value = left_value.context("Could not find left value")?;
-		let right_value = right_value.context("Could not find right value")?;
-
 		let mut units = vec![];
 		units.append(&mut left_units);
 
@@ -0,0 +1,71 @@
+use anyhow::{bail, Context, Result};
+use eggscript_mir::{MIRInfo, Transition, UnitHandle, Value, MIR};
+use eggscript_types::P;
+
+use crate::expressions::{Block, Expression};
+use crate::lower::context::Loop;
+use crate::lower::AstLowerContext;
+
+impl AstLowerContext {
+	/// Lowers the block of a loop, with `break` and `continue` jumping to the given units
+	pub(crate) fn lower_loop_block(
+		&mut self,
+		block: &P<Block>,
+		continue_unit: UnitHandle,
+		break_unit: UnitHandle,
+	) -> Result<(Vec<UnitHandle>, Option<P<Value>>)> {
+		self.loop_stack.push(Loop {
+			break_unit,
+			continue_unit,
+			try_depth: self.try_depth,
+		});
+
+		let result = self.lower_block(block);
+		self.loop_stack.pop();
+
+		return result;
+	}
+
+	pub(crate) fn lower_break_statement(
+		&mut self,
+		expression: &P<Expression>,
+	) -> Result<(Vec<UnitHandle>, Option<P<Value>>)> {
+		let Some(current_loop) = self.loop_stack.last() else {
+			bail!("'break' can only be used inside of a loop");
+		};
+
+		let target = current_loop.break_unit;
+		return Ok((vec![self.lower_loop_jump(expression, target)?], None));
+	}
+
+	pub(crate) fn lower_continue_statement(
+		&mut self,
+		expression: &P<Expression>,
+	) -> Result<(Vec<UnitHandle>, Option<P<Value>>)> {
+		let Some(current_loop) = self.loop_stack.last() else {
+			bail!("'continue' can only be used inside of a loop");
+		};
+
+		let target = current_loop.continue_unit;
+		return Ok((vec![self.lower_loop_jump(expression, target)?], None));
+	}
+
+	/// Jumps out of the try blocks entered inside of the loop before jumping to the target
+	fn lower_loop_jump(
+		&mut self,
+		expression: &P<Expression>,
+		target: UnitHandle,
+	) -> Result<UnitHandle> {
+		let loop_try_depth = self
+			.loop_stack
+			.last()
+			.context("Could not get current loop")?
+			.try_depth;
+
+		let mir = (loop_try_depth..self.try_depth)
+			.map(|_| MIR::new(MIRInfo::ExitTry, &expression.span))
+			.collect::<Vec<MIR>>();
+
+		Ok(self.unit_store.new_unit(mir, Transition::Goto(target)))
+	}
+}
@@ -0,0 +1,397 @@
+use anyhow::{bail, Context, Result};
+use eggscript_mir::{MIRInfo, MapOperation, Transition, UnitHandle, Value, MIR};
+use eggscript_types::{TypeHandle, P};
+use std::ops::Deref;
+
+use crate::expressions::{Expression, ExpressionInfo};
+use crate::lower::AstLowerContext;
+use crate::BinaryOperator;
+
+impl AstLowerContext {
+	/// Map literals take the type that is expected of them, otherwise the types of their first
+	/// entry
+	pub(crate) fn lower_map_literal(
+		&mut self,
+		expression: &P<Expression>,
+		expected: Option<TypeHandle>,
+	) -> Result<(Vec<UnitHandle>, Option<P<Value>>)> {
+		let ExpressionInfo::MapLiteral(entries) = &expression.info else {
+			unreachable!();
+		};
+
+		let expected = expected.and_then(|ty| {
+			let type_store = self
+				.program
+				.type_store
+				.lock()
+				.expect("Could not lock type store");
+
+			let ty = type_store.get_optional_inner_type(ty).unwrap_or(ty);
+			type_store.get_map_types(ty).map(|_| ty)
+		});
+
+		let mut units = vec![];
+		let mut operands = vec![];
+		let mut map_type = expected;
+
+		for (key, value) in entries.iter() {
+			let (mut key_units, key_value) = self.lower_expression(key)?;
+			let key_value = key_value.context("Expected map key where there is none")?;
+			units.append(&mut key_units);
+
+			let (mut value_units, value_value) = self.lower_expression(value)?;
+			let value_value = value_value.context("Expected map value where there is none")?;
+
+			let map_type = match map_type {
+				Some(map_type) => map_type,
+				None => {
+					self.check_map_element_type(&key_value)?;
+					self.check_map_element_type(&value_value)?;
+
+					let ty = self
+						.program
+						.type_store
+						.lock()
+						.expect("Could not lock type store")
+						.create_map_type(key_value.ty(), value_value.ty())
+						.context("Could not create map type")?;
+
+					*map_type.insert(ty)
+				}
+			};
+
+			let (key_type, value_type) = self.get_map_types(map_type)?;
+
+			// keys are materialized before the value is evaluated, so they stay in order
+			let (mut key_units, key_value) =
+				self.lower_map_element(key_value, key_type, key, "key")?;
+			units.append(&mut key_units);
+			operands.push(key_value);
+
+			units.append(&mut value_units);
+			let (mut value_units, value_value) =
+				self.lower_map_element(value_value, value_type, value, "value")?;
+			units.append(&mut value_units);
+			operands.push(value_value);
+		}
+
+		let Some(map_type) = map_type else {
+			bail!(
+				"Cannot infer the type of an empty map, assign it to a variable declared with a type like 'map<string, number>'"
+			);
+		};
+
+		let result = self.value_store.new_temp(map_type);
+		units.push(self.unit_store.new_unit(
+			vec![MIR::new(
+				MIRInfo::MapOperation(Some(result.clone()), MapOperation::New, operands),
+				expression.span,
+			)],
+			Transition::Next,
+		));
+
+		Ok((units, Some(result)))
+	}
+
+	pub(crate) fn lower_index(
+		&mut self,
+		expression: &P<Expression>,
+	) -> Result<(Vec<UnitHandle>, Option<P<Value>>)> {
+		let ExpressionInfo::Index(map, key) = &expression.info else {
+			unreachable!();
+		};
+
+		let (mut units, map_value) = self.lower_map(map)?;
+		let (key_type, value_type) = self.get_map_types(map_value.ty())?;
+
+		let (mut key_units, key_value) = self.lower_expression(key)?;
+		let key_value = key_value.context("Expected map key where there is none")?;
+		units.append(&mut key_units);
+
+		let (mut key_units, key_value) = self.lower_map_element(key_value, key_type, key, "key")?;
+		units.append(&mut key_units);
+
+		let result = self.value_store.new_temp(value_type);
+		units.push(self.unit_store.new_unit(
+			vec![MIR::new(
+				MIRInfo::MapOperation(
+					Some(result.clone()),
+					MapOperation::Get,
+					vec![map_value, key_value],
+				),
+				expression.span,
+			)],
+			Transition::Next,
+		));
+
+		Ok((units, Some(result)))
+	}
+
+	pub(crate) fn lower_index_assign(
+		&mut self,
+		expression: &P<Expression>,
+	) -> Result<(Vec<UnitHandle>, Option<P<Value>>)> {
+		let ExpressionInfo::IndexAssign(map, key, operator, value) = &expression.info else {
+			unreachable!();
+		};
+
+		let (mut units, map_value) = self.lower_map(map)?;
+		let (key_type, value_type) = self.get_map_types(map_value.ty())?;
+
+		let (mut key_units, key_value) = self.lower_expression(key)?;
+		let key_value = key_value.context("Expected map key where there is none")?;
+		units.append(&mut key_units);
+
+		let (mut key_units, key_value) = self.lower_map_element(key_value, key_type, key, "key")?;
+		units.append(&mut key_units);
+
+		if operator == &BinaryOperator::Equal {
+			let (mut value_units, value_value) = self.lower_expression(value)?;
+			let value_value = value_value.context("Expected map value where there is none")?;
+			units.append(&mut value_units);
+
+			let (mut value_units, value_value) =
+				self.lower_map_element(value_value, value_type, value, "value")?;
+			units.append(&mut value_units);
+
+			units.push(self.unit_store.new_unit(
+				vec![MIR::new(
+					MIRInfo::MapOperation(
+						None,
+						MapOperation::Set,
+						vec![map_value, key_value, value_value],
+					),
+					expression.span,
+				)],
+				Transition::Next,
+			));
+
+			return Ok((units, None));
+		}
+
+		// the key is used to get the current value and to set the new one, so it is stored in a
+		// variable user code cannot name
+		let (key_location, is_new) = self.value_store.new_location(
+			&format!(
+				"index:{}:{}",
+				expression.span.file(),
+				expression.span.start()
+			),
+			key_type,
+		);
+
+		let mut mir = vec![];
+		if is_new {
+			mir.push(MIR::new(
+				MIRInfo::Allocate(key_location.clone(), None),
+				expression.span,
+			));
+		}
+
+		mir.push(MIR::new(
+			MIRInfo::StoreValue(key_location.clone(), key_value),
+			expression.span,
+		));
+
+		let current_value = self.value_store.new_temp(value_type);
+		mir.push(MIR::new(
+			MIRInfo::MapOperation(
+				Some(current_value.clone()),
+				MapOperation::Get,
+				vec![map_value.clone(), key_location.clone()],
+			),
+			expression.span,
+		));
+
+		units.push(self.unit_store.new_unit(mir, Transition::Next));
+
+		if self.is_string(&current_value) {
+			bail!("Operator '{:?}' cannot be used on strings", operator);
+		}
+
+		let (mut value_units, value_value) = self.lower_expression(value)?;
+		let value_value = value_value.context("Expected map value where there is none")?;
+		units.append(&mut value_units);
+		self.check_not_optional(&value_value, value)?;
+		let (mut convert_units, value_value) = self.convert_any_to_number(value_value, value)?;
+		units.append(&mut convert_units);
+
+		if self.is_string(&value_value) {
+			bail!("Operator '{:?}' cannot be used on strings", operator);
+		}
+
+		let result = self.value_store.new_temp(value_type);
+		units.push(self.unit_store.new_unit(
+			vec![
+				MIR::new(
+					MIRInfo::BinaryOperation(
+						result.clone(),
+						current_value,
+						value_value,
+						operator.into(),
+					),
+					expression.span,
+				),
+				MIR::new(
+					MIRInfo::MapOperation(
+						None,
+						MapOperation::Set,
+						vec![map_value, key_location, result],
+					),
+					expression.span,
+				),
+			],
+			Transition::Next,
+		));
+
+		Ok((units, None))
+	}
+
+	pub(crate) fn lower_map_call(
+		&mut self,
+		expression: &P<Expression>,
+	) -> Result<(Vec<UnitHandle>, Option<P<Value>>)> {
+		let ExpressionInfo::MapCall(operation, arguments) = &expression.info else {
+			unreachable!();
+		};
+
+		let (mut units, map_value) = self.lower_map(&arguments[0])?;
+		let (key_type, _) = self.get_map_types(map_value.ty())?;
+
+		let number = self
+			.program
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.name_to_type_handle("number")
+			.context("Could not get 'number' type")?;
+
+		let mut operands = vec![map_value];
+		if let Some(argument) = arguments.get(1) {
+			// `KeyAt` is only created by `foreach`, and is passed the index of the key
+			let (argument_type, name) = if operation == &MapOperation::KeyAt {
+				(number, "index")
+			} else {
+				(key_type, "key")
+			};
+
+			let (mut argument_units, argument_value) = self.lower_expression(argument)?;
+			let argument_value =
+				argument_value.context("Expected map operand where there is none")?;
+			units.append(&mut argument_units);
+
+			let (mut argument_units, argument_value) =
+				self.lower_map_element(argument_value, argument_type, argument, name)?;
+			units.append(&mut argument_units);
+			operands.push(argument_value);
+		}
+
+		let ty = if operation == &MapOperation::KeyAt {
+			key_type
+		} else {
+			number
+		};
+
+		let result = self.value_store.new_temp(ty);
+		units.push(self.unit_store.new_unit(
+			vec![MIR::new(
+				MIRInfo::MapOperation(Some(result.clone()), *operation, operands),
+				expression.span,
+			)],
+			Transition::Next,
+		));
+
+		Ok((units, Some(result)))
+	}
+
+	/// Lowers the map operand of an operation, which has to be a map that cannot be null
+	fn lower_map(&mut self, map: &P<Expression>) -> Result<(Vec<UnitHandle>, P<Value>)> {
+		let (units, map_value) = self.lower_expression(map)?;
+		let map_value = map_value.context("Expected map where there is none")?;
+		self.check_not_optional(&map_value, map)?;
+
+		if !self.is_map(&map_value) {
+			bail!(
+				"'{}' is not a map",
+				self.program.source_text(&map.span).trim()
+			);
+		}
+
+		Ok((units, map_value))
+	}
+
+	/// Converts keys and values to the types of the map. Map operations only take temps and
+	/// variables, so literals are stored in temps first
+	fn lower_map_element(
+		&mut self,
+		value: P<Value>,
+		ty: TypeHandle,
+		expression: &P<Expression>,
+		name: &str,
+	) -> Result<(Vec<UnitHandle>, P<Value>)> {
+		let (mut units, value) = self.coerce_value(value, ty, expression)?;
+
+		if !self
+			.program
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.are_types_compatible(value.ty(), ty)
+		{
+			bail!(
+				"Map {} '{}' does not have the same type as the {}s of the map",
+				name,
+				self.program.source_text(&expression.span).trim(),
+				name
+			);
+		}
+
+		let Value::Primitive {
+			value: primitive, ..
+		} = value.deref()
+		else {
+			return Ok((units, value));
+		};
+
+		let temp = self.value_store.new_temp(ty);
+		units.push(self.unit_store.new_unit(
+			vec![MIR::new(
+				MIRInfo::StoreLiteral(temp.clone(), primitive.clone()),
+				expression.span,
+			)],
+			Transition::Next,
+		));
+
+		Ok((units, temp))
+	}
+
+	fn get_map_types(&self, ty: TypeHandle) -> Result<(TypeHandle, TypeHandle)> {
+		self.program
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.get_map_types(ty)
+			.context("Could not get map types")
+	}
+
+	/// The runtime stores keys and values of maps in the same way as `any` values
+	fn check_map_element_type(&self, value: &P<Value>) -> Result<()> {
+		let type_store = self
+			.program
+			.type_store
+			.lock()
+			.expect("Could not lock type store");
+
+		let is_supported = ["number", "string"].iter().any(|name| {
+			type_store
+				.name_to_type_handle(name)
+				.is_some_and(|handle| type_store.are_types_compatible(handle, value.ty()))
+		});
+
+		if !is_supported {
+			bail!("Map keys and values have to be numbers or strings");
+		}
+
+		Ok(())
+	}
+}
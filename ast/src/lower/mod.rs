@@ -1,16 +1,26 @@
+mod any;
+mod assert;
 mod binary;
 mod context;
+mod destructure;
 mod field_access;
 mod for_block;
 mod function_call;
 mod if_block;
 mod logic;
+mod loop_control;
+mod map;
+mod null_check;
 mod primitive;
 mod return_statement;
 mod scope;
+mod switch_block;
+mod throw_statement;
+mod try_block;
 mod unary;
 mod variable_assignment;
 mod while_block;
+mod yield_statement;
 
 pub use context::compile_expression;
 pub use context::compile_function;
@@ -0,0 +1,254 @@
+use anyhow::{bail, Context, Result};
+use eggscript_mir::{MIRInfo, Transition, UnitHandle, Value, MIR};
+use eggscript_types::{TypeHandle, P};
+
+use crate::expressions::{Expression, ExpressionInfo};
+use crate::lower::AstLowerContext;
+use crate::operators::LogicOperator;
+use crate::{BinaryOperator, UnaryOperator};
+
+impl AstLowerContext {
+	/// Lowers `value == null` and `value != null`, which are the only operations optionals support
+	pub(crate) fn lower_null_check(
+		&mut self,
+		expression: &P<Expression>,
+		checked: &P<Expression>,
+		operator: &BinaryOperator,
+	) -> Result<(Vec<UnitHandle>, Option<P<Value>>)> {
+		// narrowed variables are checked through their optional location instead of the unwrapped
+		// value
+		let (mut units, value) = if let ExpressionInfo::FieldAccess(name) = &checked.info {
+			let (value, is_new) = self.value_store.new_location(
+				name.name(),
+				checked.ty.context("Variable access does not have type")?,
+			);
+
+			if is_new {
+				bail!("Undefined variable access {}", name.name());
+			}
+
+			(vec![], value)
+		} else {
+			let (units, value) = self.lower_expression(checked)?;
+			(units, value.context("Could not get null checked value")?)
+		};
+
+		let number = {
+			let type_store = self
+				.program
+				.type_store
+				.lock()
+				.expect("Could not lock type store");
+
+			if type_store.get_optional_inner_type(value.ty()).is_none() {
+				bail!(
+					"'{}' is not optional, so it cannot be compared to null",
+					self.program.source_text(&checked.span).trim()
+				);
+			}
+
+			type_store
+				.name_to_type_handle("number")
+				.context("Could not get 'number' type")?
+		};
+
+		let is_null = self.value_store.new_temp(number);
+		let mut mir = vec![MIR::new(
+			MIRInfo::IsNull(is_null.clone(), value),
+			expression.span,
+		)];
+
+		let result = if operator == &BinaryOperator::NotEqual {
+			let is_not_null = self.value_store.new_temp(number);
+			mir.push(MIR::new(
+				MIRInfo::Unary(is_not_null.clone(), is_null, (&UnaryOperator::Not).into()),
+				expression.span,
+			));
+
+			is_not_null
+		} else {
+			is_null
+		};
+
+		units.push(self.unit_store.new_unit(mir, Transition::Next));
+
+		Ok((units, Some(result)))
+	}
+
+	/// Returns the operand compared to null if the expression is a null check
+	pub(crate) fn get_null_checked_operand<'a>(
+		left: &'a P<Expression>,
+		right: &'a P<Expression>,
+	) -> Option<&'a P<Expression>> {
+		let is_null = |expression: &P<Expression>| {
+			matches!(
+				expression.info,
+				ExpressionInfo::Primitive(eggscript_types::Primitive::Null, _)
+			)
+		};
+
+		if is_null(right) {
+			return Some(left);
+		} else if is_null(left) {
+			return Some(right);
+		} else {
+			return None;
+		}
+	}
+
+	/// Variables that cannot be null when the condition evaluates to `when_true`
+	pub(crate) fn get_non_null_variables(
+		condition: &P<Expression>,
+		when_true: bool,
+	) -> Vec<String> {
+		match &condition.info {
+			ExpressionInfo::BinaryOperation(left, right, operator) => {
+				let narrows = match operator {
+					BinaryOperator::Equal => !when_true,
+					BinaryOperator::NotEqual => when_true,
+					_ => false,
+				};
+
+				if narrows
+					&& let Some(checked) = AstLowerContext::get_null_checked_operand(left, right)
+					&& let ExpressionInfo::FieldAccess(name) = &checked.info
+				{
+					return vec![name.name().to_string()];
+				}
+
+				return vec![];
+			}
+			ExpressionInfo::LogicOperation(left, right, operator) => {
+				// both sides of `&&` are true if it is, and both sides of `||` are false if it is
+				let both_sides = match operator {
+					LogicOperator::And => when_true,
+					LogicOperator::Or => !when_true,
+				};
+
+				if !both_sides {
+					return vec![];
+				}
+
+				let mut names = AstLowerContext::get_non_null_variables(left, when_true);
+				names.append(&mut AstLowerContext::get_non_null_variables(
+					right, when_true,
+				));
+				return names;
+			}
+			ExpressionInfo::UnaryOperation(value, UnaryOperator::Not) => {
+				AstLowerContext::get_non_null_variables(value, !when_true)
+			}
+			_ => vec![],
+		}
+	}
+
+	/// Marks variables as not null until the returned length is restored with `truncate`
+	pub(crate) fn narrow(&mut self, mut names: Vec<String>) -> usize {
+		let length = self.narrowed.len();
+		self.narrowed.append(&mut names);
+		return length;
+	}
+
+	/// Optionals have to be checked for null before their value can be used
+	pub(crate) fn check_not_optional(
+		&self,
+		value: &P<Value>,
+		expression: &P<Expression>,
+	) -> Result<()> {
+		let type_store = self
+			.program
+			.type_store
+			.lock()
+			.expect("Could not lock type store");
+
+		if type_store.is_null(value.ty()) {
+			bail!("null can only be used as the value of an optional type");
+		} else if type_store.get_optional_inner_type(value.ty()).is_some() {
+			bail!(
+				"'{}' may be null, compare it to null before using its value",
+				self.program.source_text(&expression.span).trim()
+			);
+		}
+
+		Ok(())
+	}
+
+	/// Converts a value into the type it is stored as, wrapping values and null that are stored
+	/// in optionals
+	pub(crate) fn coerce_value(
+		&mut self,
+		value: P<Value>,
+		ty: TypeHandle,
+		expression: &P<Expression>,
+	) -> Result<(Vec<UnitHandle>, P<Value>)> {
+		let type_store = self
+			.program
+			.type_store
+			.lock()
+			.expect("Could not lock type store");
+
+		if type_store.are_types_compatible(value.ty(), ty) {
+			return Ok((vec![], value));
+		}
+
+		if let Some(inner) = type_store.get_optional_inner_type(ty)
+			&& (type_store.is_null(value.ty())
+				|| type_store.are_types_compatible(inner, value.ty()))
+		{
+			drop(type_store);
+
+			let result = self.value_store.new_temp(ty);
+			let unit = self.unit_store.new_unit(
+				vec![MIR::new(
+					MIRInfo::WrapOptional(result.clone(), value),
+					expression.span,
+				)],
+				Transition::Next,
+			);
+
+			return Ok((vec![unit], result));
+		}
+
+		drop(type_store);
+
+		if let Some(converted) = self.coerce_any(&value, ty, expression) {
+			return Ok(converted);
+		}
+
+		// other mismatches are reported by the type checker
+		self.check_not_optional(&value, expression)?;
+
+		Ok((vec![], value))
+	}
+
+	/// Narrowed variables are read through their optional, so their value is unwrapped
+	pub(crate) fn unwrap_narrowed(
+		&mut self,
+		name: &str,
+		value: P<Value>,
+		expression: &P<Expression>,
+	) -> Result<(Vec<UnitHandle>, P<Value>)> {
+		let inner = self
+			.program
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.get_optional_inner_type(value.ty());
+
+		let Some(inner) = inner.filter(|_| self.narrowed.iter().any(|narrowed| narrowed == name))
+		else {
+			return Ok((vec![], value));
+		};
+
+		let result = self.value_store.new_temp(inner);
+		let unit = self.unit_store.new_unit(
+			vec![MIR::new(
+				MIRInfo::UnwrapOptional(result.clone(), value.clone()),
+				expression.span,
+			)],
+			Transition::Next,
+		);
+
+		Ok((vec![unit], result))
+	}
+}
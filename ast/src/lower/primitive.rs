@@ -24,7 +24,15 @@ impl AstLowerContext {
 						.context(format!("Could not parse f64 '{}'", value))?,
 				),
 			),
-			_ => todo!(),
+			eggscript_types::Primitive::String => self.value_store.new_primitive(
+				expression.ty.expect("Could not get type"),
+				PrimitiveValue::String(value.clone()),
+			),
+			eggscript_types::Primitive::Any => unreachable!("there are no literals of type any"),
+			eggscript_types::Primitive::Null => self.value_store.new_primitive(
+				expression.ty.expect("Could not get type"),
+				PrimitiveValue::Null,
+			),
 		};
 
 		Ok((vec![], Some(value)))
@@ -12,38 +12,44 @@ impl AstLowerContext {
 		&mut self,
 		expression: &P<Expression>,
 	) -> Result<(Vec<UnitHandle>, Option<P<Value>>)> {
-		let ExpressionInfo::Return(value) = &expression.info else {
+		let ExpressionInfo::Return(values) = &expression.info else {
 			unreachable!();
 		};
 
 		let mut units = vec![];
-		let value = if let Some(value) = value {
-			let (mut value_units, value) = self.lower_expression(value)?;
+		let mut return_values = vec![];
+		let return_type = self
+			.function
+			.as_ref()
+			.and_then(|function| function.return_ty);
+		for value_expression in values.iter() {
+			let (mut value_units, value) = self.lower_expression(value_expression)?;
 			units.append(&mut value_units);
 
-			match value
-				.as_ref()
-				.expect("Expected return value where there is none")
-				.deref()
+			let mut value = value.expect("Expected return value where there is none");
+
+			// tuples are returned as one value, so only single values are converted
+			if values.len() == 1
+				&& let Some(return_type) = return_type
 			{
+				let (mut coerce_units, coerced) =
+					self.coerce_value(value, return_type, value_expression)?;
+				units.append(&mut coerce_units);
+				value = coerced;
+			}
+
+			match value.deref() {
 				Value::Location { ty, .. } => {
 					let temp_value = self.value_store.new_temp(*ty);
-					units.push(
-						self.unit_store.new_unit(
-							vec![MIR::new(
-								MIRInfo::StoreValue(
-									temp_value.clone(),
-									value
-										.clone()
-										.expect("Expected return value where there is none"),
-								),
-								expression.span,
-							)],
-							Transition::Next,
-						),
-					);
+					units.push(self.unit_store.new_unit(
+						vec![MIR::new(
+							MIRInfo::StoreValue(temp_value.clone(), value.clone()),
+							expression.span,
+						)],
+						Transition::Next,
+					));
 
-					Some(temp_value)
+					return_values.push(temp_value);
 				}
 				Value::Primitive { ty, value, .. } => {
 					let temp_value = self.value_store.new_temp(*ty);
@@ -55,19 +61,17 @@ impl AstLowerContext {
 						Transition::Next,
 					));
 
-					Some(temp_value)
+					return_values.push(temp_value);
 				}
-				Value::Temp { .. } => value,
+				Value::Temp { .. } => return_values.push(value),
 			}
-		} else {
-			None
-		};
+		}
 
 		units.push(
 			self.unit_store
-				.new_unit(vec![], Transition::Return(value.clone())),
+				.new_unit(vec![], Transition::Return(return_values.clone())),
 		);
 
-		Ok((units, value))
+		Ok((units, return_values.first().cloned()))
 	}
 }
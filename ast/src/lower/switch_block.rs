@@ -0,0 +1,201 @@
+use anyhow::{bail, Context, Result};
+use eggscript_mir::{MIRInfo, PrimitiveValue, Transition, UnitHandle, Value, MIR};
+use eggscript_types::P;
+use std::ops::Deref;
+
+use crate::expressions::{Expression, ExpressionInfo};
+use crate::lower::AstLowerContext;
+use crate::pretty_error::print_warning_header;
+use crate::BinaryOperator;
+
+impl AstLowerContext {
+	pub(crate) fn lower_switch_block(
+		&mut self,
+		expression: &P<Expression>,
+	) -> Result<(Vec<UnitHandle>, Option<P<Value>>)> {
+		let ExpressionInfo::Switch(value, cases, default) = &expression.info else {
+			unreachable!();
+		};
+
+		let (mut units, switch_value) = self.lower_expression(value)?;
+		let switch_value = switch_value.context("Could not get switch value")?;
+
+		self.check_not_optional(&switch_value, value)?;
+		let (mut convert_units, switch_value) = self.convert_any_to_number(switch_value, value)?;
+		units.append(&mut convert_units);
+
+		if self.is_string(&switch_value) {
+			bail!("Switch values have to be numbers or enum values");
+		}
+
+		// every case compares against the value, so it is stored in a variable that user code
+		// cannot name
+		let (variable, is_new) = self.value_store.new_location(
+			&format!(
+				"switch:{}:{}",
+				expression.span.file(),
+				expression.span.start()
+			),
+			switch_value.ty(),
+		);
+
+		let mut mir = vec![];
+		if is_new {
+			mir.push(MIR::new(
+				MIRInfo::Allocate(variable.clone(), None),
+				&expression.span,
+			));
+		}
+
+		match switch_value.deref() {
+			Value::Primitive { value, .. } => mir.push(MIR::new(
+				MIRInfo::StoreLiteral(variable.clone(), value.clone()),
+				&expression.span,
+			)),
+			Value::Location { .. } | Value::Temp { .. } => mir.push(MIR::new(
+				MIRInfo::StoreValue(variable.clone(), switch_value.clone()),
+				&expression.span,
+			)),
+		}
+
+		units.push(self.unit_store.new_unit(mir, Transition::Next));
+
+		let number = self
+			.program
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.name_to_type_handle("number")
+			.context("Could not get 'number' type")?;
+
+		let unit_after = self.unit_store.new_unit(vec![], Transition::Next);
+
+		let mut handled_variants = vec![];
+		let mut case_units = vec![];
+		for (values, block) in cases.iter() {
+			let case_start = self.unit_store.new_unit(vec![], Transition::Next);
+
+			for value in values.iter() {
+				let (mut value_units, case_value) = self.lower_expression(value)?;
+				let case_value = case_value.context("Could not get case value")?;
+
+				if !self
+					.program
+					.type_store
+					.lock()
+					.expect("Could not lock type store")
+					.are_types_compatible(variable.ty(), case_value.ty())
+				{
+					bail!("Case value does not have the same type as the switch value");
+				}
+
+				if let Value::Primitive {
+					value: PrimitiveValue::Number(index),
+					..
+				} = case_value.deref()
+				{
+					handled_variants.push(*index as usize);
+				}
+
+				let comparison = self.value_store.new_temp(number);
+
+				units.append(&mut value_units);
+				units.push(self.unit_store.new_unit(
+					vec![MIR::new(
+						MIRInfo::BinaryOperation(
+							comparison.clone(),
+							variable.clone(),
+							case_value,
+							(&BinaryOperator::Equal).into(),
+						),
+						&value.span,
+					)],
+					Transition::Next,
+				));
+				units.push(
+					self.unit_store
+						.new_unit(vec![], Transition::GotoIfTrue(case_start, comparison)),
+				);
+			}
+
+			let (mut block_units, _) = self.lower_block(block)?;
+
+			case_units.push(case_start);
+			case_units.append(&mut block_units);
+			case_units.push(
+				self.unit_store
+					.new_unit(vec![], Transition::Goto(unit_after)),
+			);
+		}
+
+		if let Some(default) = default {
+			let (mut default_units, _) = self.lower_block(default)?;
+
+			// the default block directly follows the comparisons, so it runs when none match
+			units.append(&mut default_units);
+			units.push(
+				self.unit_store
+					.new_unit(vec![], Transition::Goto(unit_after)),
+			);
+		} else {
+			self.check_switch_exhaustive(expression, &variable, &handled_variants);
+
+			units.push(
+				self.unit_store
+					.new_unit(vec![], Transition::Goto(unit_after)),
+			);
+		}
+
+		units.append(&mut case_units);
+		units.push(unit_after);
+
+		Ok((units, None))
+	}
+
+	/// Warns about enum variants a switch without a default block does not handle
+	fn check_switch_exhaustive(
+		&self,
+		expression: &P<Expression>,
+		variable: &P<Value>,
+		handled_variants: &[usize],
+	) {
+		let type_store = self
+			.program
+			.type_store
+			.lock()
+			.expect("Could not lock type store");
+
+		let Some(variants) = type_store.get_enum_variants(variable.ty()) else {
+			return;
+		};
+
+		let missing = variants
+			.iter()
+			.enumerate()
+			.filter(|(index, _)| !handled_variants.contains(index))
+			.map(|(_, variant)| format!("'{}'", variant))
+			.collect::<Vec<String>>();
+
+		if missing.is_empty() {
+			return;
+		}
+
+		let (line, column) = self.program.line_and_column(&expression.span);
+		print_warning_header(
+			&format!(
+				"switch over enum '{}' does not handle {}",
+				type_store
+					.get_type(variable.ty())
+					.and_then(|ty| ty.get_name())
+					.unwrap_or_default(),
+				missing.join(", ")
+			),
+			&format!(
+				"{}:{}:{}",
+				self.program.files[expression.span.file() as usize].name,
+				line,
+				column
+			),
+		);
+	}
+}
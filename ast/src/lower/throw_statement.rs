@@ -0,0 +1,54 @@
+use anyhow::{bail, Result};
+use eggscript_mir::{MIRInfo, Transition, UnitHandle, Value, MIR};
+use eggscript_types::P;
+use std::ops::Deref;
+
+use crate::expressions::{Expression, ExpressionInfo};
+use crate::lower::AstLowerContext;
+
+impl AstLowerContext {
+	pub(crate) fn lower_throw_statement(
+		&mut self,
+		expression: &P<Expression>,
+	) -> Result<(Vec<UnitHandle>, Option<P<Value>>)> {
+		let ExpressionInfo::Throw(value) = &expression.info else {
+			unreachable!();
+		};
+
+		let (mut units, value) = self.lower_expression(value)?;
+		let mut value = value.expect("Expected thrown value where there is none");
+
+		if !self.is_string(&value) {
+			bail!("Only strings can be thrown");
+		}
+
+		// the thrown value is copied into a temp so the throw has an instruction of its own, which
+		// backtraces point at
+		let info = match value.deref() {
+			Value::Temp { .. } => None,
+			Value::Primitive {
+				value: primitive, ..
+			} => Some(MIRInfo::StoreLiteral(
+				self.value_store.new_temp(value.ty()),
+				primitive.clone(),
+			)),
+			Value::Location { .. } => Some(MIRInfo::StoreValue(
+				self.value_store.new_temp(value.ty()),
+				value.clone(),
+			)),
+		};
+
+		let mut mir = vec![];
+		if let Some(info) = info {
+			if let MIRInfo::StoreLiteral(temp, _) | MIRInfo::StoreValue(temp, _) = &info {
+				value = temp.clone();
+			}
+
+			mir.push(MIR::new(info, expression.span));
+		}
+
+		units.push(self.unit_store.new_unit(mir, Transition::Throw(value)));
+
+		Ok((units, None))
+	}
+}
@@ -0,0 +1,76 @@
+use anyhow::{bail, Context, Result};
+use eggscript_mir::{MIRInfo, Transition, UnitHandle, Value, MIR};
+use eggscript_types::P;
+
+use crate::expressions::{Expression, ExpressionInfo};
+use crate::lower::AstLowerContext;
+
+impl AstLowerContext {
+	pub(crate) fn lower_try_block(
+		&mut self,
+		expression: &P<Expression>,
+	) -> Result<(Vec<UnitHandle>, Option<P<Value>>)> {
+		let ExpressionInfo::Try(try_block, variable, catch_block) = &expression.info else {
+			unreachable!();
+		};
+
+		let string = self
+			.program
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.name_to_type_handle("string")
+			.context("Could not get 'string' type")?;
+
+		let (variable_value, is_new) = self.value_store.new_location(variable.name(), string);
+		if !self.is_string(&variable_value) {
+			bail!(
+				"Cannot catch exception into '{}', which is not a string",
+				variable.name()
+			);
+		}
+
+		// the catch unit is referenced by the units in the try block, so it has to exist first
+		let catch_unit = self.unit_store.new_unit(
+			vec![MIR::new(
+				MIRInfo::Catch(variable_value.clone()),
+				variable.span(),
+			)],
+			Transition::Next,
+		);
+
+		let mut mir = vec![];
+		if is_new {
+			mir.push(MIR::new(
+				MIRInfo::Allocate(variable_value.clone(), None),
+				variable.span(),
+			));
+		}
+
+		mir.push(MIR::new(MIRInfo::EnterTry(catch_unit), &expression.span));
+
+		let mut units = vec![self.unit_store.new_unit(mir, Transition::Next)];
+
+		self.try_depth += 1;
+		let try_result = self.lower_block(try_block);
+		self.try_depth -= 1;
+		let (mut try_units, _) = try_result?;
+		let unit_after = self.unit_store.new_unit(vec![], Transition::Next);
+
+		try_units.push(self.unit_store.new_unit(
+			vec![MIR::new(MIRInfo::ExitTry, &expression.span)],
+			Transition::Goto(unit_after),
+		));
+
+		self.unit_store.set_unwind(&try_units, catch_unit);
+
+		let (mut catch_units, _) = self.lower_block(catch_block)?;
+
+		units.append(&mut try_units);
+		units.push(catch_unit);
+		units.append(&mut catch_units);
+		units.push(unit_after);
+
+		Ok((units, None))
+	}
+}
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use eggscript_mir::{MIRInfo, Transition, UnitHandle, Value, MIR};
 use eggscript_types::P;
 
@@ -17,6 +17,18 @@ impl AstLowerContext {
 		let (mut value_units, value) = self.lower_expression(value)?;
 		let value = value.expect("Expected value where there is none");
 
+		self.check_not_optional(&value, expression)?;
+		let (mut convert_units, value) = self.convert_any_to_number(value, expression)?;
+		value_units.append(&mut convert_units);
+
+		if self.is_string(&value) {
+			bail!("Operator '{:?}' cannot be used on strings", operator);
+		}
+
+		if self.is_enum(&value) {
+			bail!("Operator '{:?}' cannot be used on enum values", operator);
+		}
+
 		let result = self.value_store.new_temp(value.ty());
 
 		let mut units = vec![];
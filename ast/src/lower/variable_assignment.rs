@@ -1,4 +1,4 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Result};
 use eggscript_mir::{MIRInfo, Transition, UnitHandle, Value, MIR};
 use eggscript_types::P;
 use std::ops::Deref;
@@ -16,18 +16,76 @@ impl AstLowerContext {
 			unreachable!();
 		};
 
-		let (variable_value, is_new) = self.value_store.new_location(
-			name.name(),
-			expression
-				.ty
-				.context("Variable assignment does not have type")?,
-		);
+		// map literals take the type of the variable, so empty maps can be assigned to it
+		let (mut rvalue_units, rvalue) = if let ExpressionInfo::MapLiteral(_) = &value.info {
+			let expected = expression.ty.or_else(|| {
+				self.value_store
+					.get_location(name.name())
+					.map(|location| location.ty())
+			});
 
-		let (mut rvalue_units, rvalue) = self.lower_expression(value)?;
+			self.lower_map_literal(value, expected)?
+		} else {
+			self.lower_expression(value)?
+		};
 		let Some(rvalue) = rvalue else {
 			unreachable!();
 		};
 
+		let (variable_value, is_new) = self
+			.value_store
+			.new_location(name.name(), expression.ty.unwrap_or(rvalue.ty()));
+
+		if self
+			.program
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.get_tuple_element_types(rvalue.ty())
+			.is_some()
+		{
+			bail!(
+				"Cannot assign a tuple to '{}', use destructuring instead",
+				name.name()
+			);
+		}
+
+		let (is_null, may_be_null) = {
+			let type_store = self
+				.program
+				.type_store
+				.lock()
+				.expect("Could not lock type store");
+
+			(
+				type_store.is_null(variable_value.ty()),
+				type_store.is_null(rvalue.ty())
+					|| type_store.get_optional_inner_type(rvalue.ty()).is_some(),
+			)
+		};
+
+		if is_new && is_null {
+			bail!(
+				"Cannot infer the type of '{}' from null, declare it with an optional type like 'number?'",
+				name.name()
+			);
+		}
+
+		if may_be_null && self.narrowed.iter().any(|narrowed| narrowed == name.name()) {
+			bail!(
+				"'{}' was compared to null, so it cannot be assigned a value that may be null here",
+				name.name()
+			);
+		}
+
+		if operator != &BinaryOperator::Equal && self.is_enum(&variable_value) {
+			bail!(
+				"Operator '{:?}' cannot be used on enum variable '{}'",
+				operator,
+				name.name()
+			);
+		}
+
 		// is_new is only allowed with equal operator. otherwise, panic
 		assert!(
 			(!is_new && operator != &BinaryOperator::Equal) || operator == &BinaryOperator::Equal
@@ -44,6 +102,10 @@ impl AstLowerContext {
 		let mut units = vec![];
 
 		if operator == &BinaryOperator::Equal {
+			let (mut coerce_units, rvalue) =
+				self.coerce_value(rvalue, variable_value.ty(), value)?;
+			rvalue_units.append(&mut coerce_units);
+
 			match rvalue.deref() {
 				Value::Location { .. } => {
 					mir.push(MIR::new(
@@ -68,24 +130,52 @@ impl AstLowerContext {
 			units.append(&mut rvalue_units);
 			units.push(self.unit_store.new_unit(mir, Transition::Next));
 		} else {
-			let result = self.value_store.new_temp(variable_value.ty());
+			// narrowed optionals are unwrapped before the operation and wrapped again after it
+			let (mut unwrap_units, current_value) =
+				self.unwrap_narrowed(name.name(), variable_value.clone(), expression)?;
+
+			if self
+				.program
+				.type_store
+				.lock()
+				.expect("Could not lock type store")
+				.get_optional_inner_type(current_value.ty())
+				.is_some()
+			{
+				bail!(
+					"'{}' may be null, compare it to null before using its value",
+					name.name()
+				);
+			}
+
+			self.check_not_optional(&rvalue, value)?;
+
+			let result = self.value_store.new_temp(current_value.ty());
 			mir.push(MIR::new(
 				MIRInfo::BinaryOperation(
 					result.clone(),
-					variable_value.clone(),
+					current_value,
 					rvalue.clone(),
 					operator.into(),
 				),
 				&expression.span,
 			));
 
-			mir.push(MIR::new(
-				MIRInfo::StoreValue(variable_value.clone(), result),
-				name.span(),
-			));
-
+			units.append(&mut unwrap_units);
 			units.append(&mut rvalue_units);
 			units.push(self.unit_store.new_unit(mir, Transition::Next));
+
+			let (mut coerce_units, result) =
+				self.coerce_value(result, variable_value.ty(), expression)?;
+			units.append(&mut coerce_units);
+
+			units.push(self.unit_store.new_unit(
+				vec![MIR::new(
+					MIRInfo::StoreValue(variable_value.clone(), result),
+					name.span(),
+				)],
+				Transition::Next,
+			));
 		}
 
 		Ok((units, Some(variable_value)))
@@ -15,23 +15,34 @@ impl AstLowerContext {
 		};
 
 		let (mut conditional_units, conditional_value) = self.lower_expression(conditional)?;
-
-		let (mut block_units, _) = self.lower_block(block)?;
-
+		let conditional_value =
+			conditional_value.expect("Expected conditional value where there is none");
+		self.check_not_optional(&conditional_value, conditional)?;
+		let (mut convert_units, conditional_value) =
+			self.convert_any_to_number(conditional_value, conditional)?;
+		conditional_units.append(&mut convert_units);
+
+		// the loop starts with an empty unit, since the condition might not need any units
+		let loop_start = self.unit_store.new_unit(vec![], Transition::Next);
 		let unit_after = self.unit_store.new_unit(vec![], Transition::Next);
 
-		let mut units = vec![];
+		let narrowed = self.narrow(AstLowerContext::get_non_null_variables(conditional, true));
+		let block_result = self.lower_loop_block(block, loop_start, unit_after);
+		self.narrowed.truncate(narrowed);
+		let (mut block_units, _) = block_result?;
+
+		let mut units = vec![loop_start];
 		units.append(&mut conditional_units);
 
 		units.push(self.unit_store.new_unit(
 			vec![],
-			Transition::GotoIfFalse(
-				unit_after,
-				conditional_value.expect("Expected conditional value where there is none"),
-			),
+			Transition::GotoIfFalse(unit_after, conditional_value),
 		));
 		units.append(&mut block_units);
-		units.push(self.unit_store.new_unit(vec![], Transition::Goto(units[0])));
+		units.push(
+			self.unit_store
+				.new_unit(vec![], Transition::Goto(loop_start)),
+		);
 		units.push(unit_after);
 
 		Ok((units, None))
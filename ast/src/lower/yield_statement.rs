@@ -0,0 +1,34 @@
+use anyhow::{Context, Result};
+use eggscript_mir::{MIRInfo, Transition, UnitHandle, Value, MIR};
+use eggscript_types::P;
+
+use crate::expressions::{Expression, ExpressionInfo};
+use crate::lower::AstLowerContext;
+
+impl AstLowerContext {
+	pub(crate) fn lower_yield_statement(
+		&mut self,
+		expression: &P<Expression>,
+	) -> Result<(Vec<UnitHandle>, Option<P<Value>>)> {
+		let ExpressionInfo::Yield(value) = &expression.info else {
+			unreachable!();
+		};
+
+		let mut units = vec![];
+		let value = match value {
+			Some(value) => {
+				let (mut value_units, value) = self.lower_expression(value)?;
+				units.append(&mut value_units);
+				Some(value.context("Expected yielded value where there is none")?)
+			}
+			None => None,
+		};
+
+		units.push(self.unit_store.new_unit(
+			vec![MIR::new(MIRInfo::Yield(value), expression.span)],
+			Transition::Next,
+		));
+
+		Ok((units, None))
+	}
+}
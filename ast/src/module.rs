@@ -0,0 +1,250 @@
+use anyhow::{bail, Context, Result};
+use eggscript_types::{TypeStore, P};
+use pest::iterators::Pair;
+use pest::Parser;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::expressions::Expression;
+use crate::parser::{attempt_print_pest_error, PestParser, Rule};
+use crate::{AstContext, Function, Package, Program};
+
+#[derive(Clone)]
+pub struct SourceFile {
+	pub contents: String,
+	/// Namespaces of the modules the file imports
+	pub imports: Vec<String>,
+	pub name: String,
+	/// Namespace the file's functions are declared in, `None` for the main file
+	pub namespace: Option<String>,
+}
+
+/// Parses a file and every file it imports into one set of functions that share a type store
+pub(crate) struct ModuleLoader {
+	pub files: Vec<SourceFile>,
+	pub function_name_to_function: HashMap<String, P<Function>>,
+	pub functions: Vec<P<Function>>,
+	/// Maps the canonical path of every loaded file to its index in `files`
+	loaded: HashMap<PathBuf, usize>,
+	/// Canonical paths of the files that are currently being loaded, used to detect import cycles
+	loading: Vec<PathBuf>,
+	pub packages: Vec<Package>,
+	pub type_store: Arc<Mutex<TypeStore>>,
+}
+
+impl ModuleLoader {
+	pub fn new(type_store: Arc<Mutex<TypeStore>>) -> ModuleLoader {
+		ModuleLoader {
+			files: vec![],
+			function_name_to_function: HashMap::new(),
+			functions: vec![],
+			loaded: HashMap::new(),
+			loading: vec![],
+			packages: vec![],
+			type_store,
+		}
+	}
+
+	/// Continues loading files into an existing program
+	pub fn from_program(program: &Program) -> ModuleLoader {
+		let loaded = program
+			.files
+			.iter()
+			.enumerate()
+			.filter_map(|(file, source_file)| {
+				Some((Path::new(&source_file.name).canonicalize().ok()?, file))
+			})
+			.collect::<HashMap<PathBuf, usize>>();
+
+		ModuleLoader {
+			files: program.files.clone(),
+			function_name_to_function: program.function_name_to_function.clone(),
+			functions: program.functions.clone(),
+			loaded,
+			loading: vec![],
+			packages: program.packages.clone(),
+			type_store: program.type_store.clone(),
+		}
+	}
+
+	/// Returns the expressions in the global scope of the file
+	pub fn load_file(
+		&mut self,
+		contents: &str,
+		file_name: &str,
+		namespace: Option<String>,
+	) -> Result<Vec<Result<P<Expression>>>> {
+		let pairs = match PestParser::parse(Rule::program, &contents) {
+			Ok(pairs) => pairs,
+			Err(error) => {
+				attempt_print_pest_error(error, contents, file_name);
+				bail!("Could not parse string")
+			}
+		};
+
+		let file = self.files.len();
+		self.files.push(SourceFile {
+			contents: contents.into(),
+			imports: vec![],
+			name: file_name.into(),
+			namespace: namespace.clone(),
+		});
+
+		// files passed in as a string do not exist on disk, so they cannot take part in cycles
+		let path = Path::new(file_name).canonicalize().ok();
+		if let Some(path) = path.as_ref() {
+			self.loaded.insert(path.clone(), file);
+			self.loading.push(path.clone());
+		}
+
+		// calls can refer to functions declared further down the file, so collect their names
+		// before parsing anything
+		let local_functions = pairs
+			.clone()
+			.filter(|pair| pair.as_rule() == Rule::function_declaration)
+			.filter_map(|pair| {
+				pair.into_inner()
+					.find(|pair| pair.as_rule() == Rule::function_name)
+					.map(|pair| pair.as_str().to_string())
+			})
+			.collect::<HashSet<String>>();
+
+		let mut context = AstContext::new(
+			self.type_store.clone(),
+			file as u32,
+			namespace.clone(),
+			local_functions,
+		);
+
+		for pair in pairs.clone() {
+			if let Rule::enum_declaration = pair.as_rule() {
+				Expression::parse_enum_declaration(&mut context, pair)
+					.context("Could not parse enum declaration")?;
+			}
+		}
+
+		let mut global_scope = vec![];
+		for pair in pairs.into_iter() {
+			match pair.as_rule() {
+				Rule::import_statement => {
+					let imported_namespace = self.load_import(file, pair)?;
+					self.files[file].imports.push(imported_namespace);
+				}
+				Rule::function_declaration => {
+					let id = self.functions.len();
+					let function = Expression::parse_function_declaration(&mut context, pair, id)
+						.context("Could not parse function declaration")?;
+
+					self.add_function(function)?;
+				}
+				Rule::package_declaration => {
+					if namespace.is_some() {
+						bail!("Packages cannot be declared in module '{}'", file_name);
+					}
+
+					let id = self.functions.len();
+					let package = Expression::parse_package_declaration(&mut context, pair, id)
+						.context("Could not parse package declaration")?;
+
+					for function in package.functions.iter() {
+						self.add_function(function.clone())?;
+					}
+
+					self.packages.push(package);
+				}
+				Rule::enum_declaration | Rule::EOI => {}
+				_ => {
+					if namespace.is_some() {
+						bail!(
+							"Only imports and functions can be declared at the top level of module '{}'",
+							file_name
+						);
+					}
+
+					if let Some(parsed) = Expression::parse_pair(&mut context, pair) {
+						global_scope.push(parsed);
+					}
+				}
+			}
+		}
+
+		if path.is_some() {
+			self.loading.pop();
+		}
+
+		Ok(global_scope)
+	}
+
+	fn add_function(&mut self, function: P<Function>) -> Result<()> {
+		if self.function_name_to_function.contains_key(&function.name) {
+			bail!("Function '{}' is declared more than once", function.name);
+		}
+
+		self.functions.push(function.clone());
+		self.function_name_to_function
+			.insert(function.name.clone(), function);
+
+		Ok(())
+	}
+
+	/// Loads the file named by an import statement if it has not been loaded yet, and returns its
+	/// namespace
+	fn load_import(&mut self, importer: usize, pair: Pair<Rule>) -> Result<String> {
+		let literal = pair
+			.into_inner()
+			.next()
+			.context("Could not get import path")?
+			.as_str();
+
+		let relative_path = &literal[1..literal.len() - 1];
+		let path = Path::new(&self.files[importer].name)
+			.parent()
+			.unwrap_or(Path::new(""))
+			.join(relative_path);
+
+		let canonical_path = path
+			.canonicalize()
+			.context(format!("Could not find imported file '{}'", path.display()))?;
+
+		if let Some(position) = self.loading.iter().position(|path| path == &canonical_path) {
+			let cycle = self.loading[position..]
+				.iter()
+				.chain(std::iter::once(&canonical_path))
+				.map(|path| path.display().to_string())
+				.collect::<Vec<String>>();
+
+			bail!("Import cycle detected: {}", cycle.join(" -> "));
+		}
+
+		if let Some(file) = self.loaded.get(&canonical_path) {
+			return self.files[*file]
+				.namespace
+				.clone()
+				.context("Could not import the main file");
+		}
+
+		let namespace = canonical_path
+			.file_stem()
+			.context("Could not get imported file name")?
+			.to_string_lossy()
+			.to_string();
+
+		if let Some(file) = self
+			.files
+			.iter()
+			.find(|file| file.namespace.as_ref() == Some(&namespace))
+		{
+			bail!(
+				"Module '{}' has the same namespace as already imported module '{}'",
+				path.display(),
+				file.name
+			);
+		}
+
+		let contents = std::fs::read_to_string(&path).context("Could not read file")?;
+		self.load_file(&contents, &path.to_string_lossy(), Some(namespace.clone()))?;
+
+		Ok(namespace)
+	}
+}
@@ -36,6 +36,41 @@ impl BinaryOperator {
 		})
 	}
 
+	/// Comparisons result in a number no matter what types are being compared
+	pub(crate) fn is_comparison(&self) -> bool {
+		match self {
+			BinaryOperator::Equal
+			| BinaryOperator::NotEqual
+			| BinaryOperator::LessThan
+			| BinaryOperator::GreaterThan
+			| BinaryOperator::LessThanEqualTo
+			| BinaryOperator::GreaterThanEqualTo => true,
+			_ => false,
+		}
+	}
+
+	/// Applies the operator to numbers the same way the interpreter does, used for constants
+	pub(crate) fn evaluate(&self, left: f64, right: f64) -> f64 {
+		match self {
+			BinaryOperator::Plus => left + right,
+			BinaryOperator::Minus => left - right,
+			BinaryOperator::Multiply => left * right,
+			BinaryOperator::Divide => left / right,
+			BinaryOperator::Modulus => left % right,
+			BinaryOperator::BitwiseAnd => (left as i64 & right as i64) as f64,
+			BinaryOperator::BitwiseOr => (left as i64 | right as i64) as f64,
+			BinaryOperator::BitwiseXor => (left as i64 ^ right as i64) as f64,
+			BinaryOperator::ShiftLeft => ((left as i64) << (right as i64)) as f64,
+			BinaryOperator::ShiftRight => (left as i64 >> right as i64) as f64,
+			BinaryOperator::Equal => (left == right) as u8 as f64,
+			BinaryOperator::NotEqual => (left != right) as u8 as f64,
+			BinaryOperator::LessThan => (left < right) as u8 as f64,
+			BinaryOperator::GreaterThan => (left > right) as u8 as f64,
+			BinaryOperator::LessThanEqualTo => (left <= right) as u8 as f64,
+			BinaryOperator::GreaterThanEqualTo => (left >= right) as u8 as f64,
+		}
+	}
+
 	pub(crate) fn parse_binary(operator: &str) -> Option<BinaryOperator> {
 		Some(match operator {
 			"+" => BinaryOperator::Plus,
@@ -90,6 +125,15 @@ pub enum UnaryOperator {
 }
 
 impl UnaryOperator {
+	/// Applies the operator to a number the same way the interpreter does, used for constants
+	pub(crate) fn evaluate(&self, value: f64) -> f64 {
+		match self {
+			UnaryOperator::BitwiseNot => !(value as i64) as f64,
+			UnaryOperator::Minus => -value,
+			UnaryOperator::Not => (value == 0.0) as u8 as f64,
+		}
+	}
+
 	pub(crate) fn parse_unary(operator: &str) -> Option<UnaryOperator> {
 		Some(match operator {
 			"-" => UnaryOperator::Minus,
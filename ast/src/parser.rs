@@ -1,19 +1,20 @@
 use anyhow::{bail, Context, Result};
+use eggscript_mir::{RuntimeError, SandboxConfig, SourceSpan, StackFrame};
 use eggscript_types::{TypeHandle, TypeStore, P};
 use pest::error::{Error, ErrorVariant, LineColLocation};
 use pest::iterators::Pairs;
 use pest::pratt_parser::{Assoc, Op, PrattParser};
-use pest::Parser;
 use pest_derive::Parser;
 use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, Mutex};
 
 use crate::expressions::Expression;
+use crate::module::{ModuleLoader, SourceFile};
 use crate::pretty_error::{
 	print_blank, print_dots, print_error_header, print_line_with_correction,
 	print_line_with_squiggle,
 };
-use crate::{Function, FunctionArgument, Span};
+use crate::{Function, FunctionArgument, Package, Span};
 
 pub fn configure_pratt() -> PrattParser<Rule> {
 	PrattParser::new()
@@ -44,39 +45,306 @@ pub(crate) struct PestParser<'a> {
 }
 
 pub struct Program {
-	pub file_name: String,
+	/// Every file that makes up the program, the main file comes first
+	pub files: Vec<SourceFile>,
 	pub function_name_to_function: HashMap<String, P<Function>>,
 	pub functions: Vec<P<Function>>,
 	pub global_scope: P<Expression>,
+	pub options: CompileOptions,
+	pub packages: Vec<Package>,
 	pub type_store: Arc<Mutex<TypeStore>>,
 }
 
+/// Settings that change how a program is compiled
+#[derive(Clone, Debug, Default)]
+pub struct CompileOptions {
+	/// Sandbox the program is going to run in. Calls to natives it does not allow fail to compile
+	pub sandbox: Option<SandboxConfig>,
+	/// Leaves `assert` calls out of the compiled program
+	pub strip_asserts: bool,
+}
+
+impl CompileOptions {
+	/// Only lets the program call the natives the sandbox allows
+	pub fn with_sandbox(mut self, sandbox: &SandboxConfig) -> CompileOptions {
+		self.sandbox = Some(sandbox.clone());
+		self
+	}
+
+	pub fn allows_native(&self, name: &str) -> bool {
+		self.sandbox
+			.as_ref()
+			.is_none_or(|sandbox| sandbox.allows_native(name))
+	}
+}
+
 impl Program {
+	pub fn file_names(&self) -> Vec<String> {
+		self.files.iter().map(|file| file.name.clone()).collect()
+	}
+
+	/// Source code the span covers
+	pub fn source_text(&self, span: &Span) -> &str {
+		let contents = &self.files[span.file() as usize].contents;
+		return &contents[span.start() as usize..span.end() as usize];
+	}
+
+	/// One-based line and column the span starts at
+	pub fn line_and_column(&self, span: &Span) -> (usize, usize) {
+		let contents = &self.files[span.file() as usize].contents;
+		let before = &contents[..span.start() as usize];
+
+		let line = before.matches('\n').count() + 1;
+		let column = before.len() - before.rfind('\n').map_or(0, |index| index + 1) + 1;
+
+		return (line, column);
+	}
+
+	/// File name, one-based line and column, and the source line a span of compiled code starts
+	/// at. Spans of files the program does not know about have no location
+	pub fn source_location(&self, span: SourceSpan) -> Option<(&str, usize, usize, &str)> {
+		let file = self.files.get(span.file as usize)?;
+		let (line, column) = self.line_and_column(&Span::new(span.start, span.end, span.file));
+		let source_line = file.contents.lines().nth(line - 1).unwrap_or("");
+
+		return Some((&file.name, line, column, source_line));
+	}
+
+	/// Formats every frame of a backtrace on its own line, followed by its source line
+	pub fn format_backtrace(&self, backtrace: &[StackFrame]) -> String {
+		let mut result = String::new();
+		for frame in backtrace.iter() {
+			let function = frame.function.as_deref().unwrap_or("<global scope>");
+
+			let Some((file_name, line, column, source_line)) =
+				frame.span.and_then(|span| self.source_location(span))
+			else {
+				result.push_str(&format!("\n    at {}", function));
+				continue;
+			};
+
+			result.push_str(&format!(
+				"\n    at {} ({}:{}:{})\n        {}",
+				function,
+				file_name,
+				line,
+				column,
+				source_line.trim()
+			));
+		}
+
+		return result;
+	}
+
+	/// Adds the backtrace of a runtime error to it, with the source line of every frame
+	pub fn runtime_error_with_backtrace(&self, error: RuntimeError) -> anyhow::Error {
+		let backtrace = self.format_backtrace(&error.backtrace);
+		return anyhow::anyhow!("{}{}", error, backtrace);
+	}
+
+	/// Declares the natives every program has access to
+	pub(crate) fn add_builtin_functions(&mut self) -> Result<()> {
+		let type_store = self.type_store.lock().expect("Could not lock type store");
+
+		let number = type_store
+			.name_to_type_handle("number")
+			.context("Could not get 'number' type")?;
+
+		let string = type_store
+			.name_to_type_handle("string")
+			.context("Could not get 'string' type")?;
+
+		let any = type_store
+			.name_to_type_handle("any")
+			.context("Could not get 'any' type")?;
+
+		drop(type_store);
+
+		self.add_native_function(
+			vec![FunctionArgument {
+				default: None,
+				name: "value".into(),
+				span: Span::new(0, 0, 0),
+				ty: number,
+			}],
+			"printNumber",
+			number,
+			None,
+		);
+
+		self.add_native_function(
+			vec![FunctionArgument {
+				default: None,
+				name: "value".into(),
+				span: Span::new(0, 0, 0),
+				ty: string,
+			}],
+			"printString",
+			number,
+			None,
+		);
+
+		self.add_native_function(
+			vec![FunctionArgument {
+				default: None,
+				name: "value".into(),
+				span: Span::new(0, 0, 0),
+				ty: string,
+			}],
+			"parseNumber",
+			number,
+			None,
+		);
+
+		self.add_native_function(vec![], "echo", number, Some(number));
+
+		self.add_native_function(
+			vec![FunctionArgument {
+				default: None,
+				name: "file_name".into(),
+				span: Span::new(0, 0, 0),
+				ty: string,
+			}],
+			"exec",
+			number,
+			None,
+		);
+
+		for name in ["activatePackage", "deactivatePackage"] {
+			self.add_native_function(
+				vec![FunctionArgument {
+					default: None,
+					name: "name".into(),
+					span: Span::new(0, 0, 0),
+					ty: string,
+				}],
+				name,
+				number,
+				None,
+			);
+		}
+
+		// arguments after the function name are passed to the scheduled function
+		self.add_native_function(
+			vec![
+				FunctionArgument {
+					default: None,
+					name: "delay".into(),
+					span: Span::new(0, 0, 0),
+					ty: number,
+				},
+				FunctionArgument {
+					default: None,
+					name: "function".into(),
+					span: Span::new(0, 0, 0),
+					ty: string,
+				},
+			],
+			"schedule",
+			number,
+			Some(any),
+		);
+
+		for name in ["cancel", "isEventPending"] {
+			self.add_native_function(
+				vec![FunctionArgument {
+					default: None,
+					name: "id".into(),
+					span: Span::new(0, 0, 0),
+					ty: number,
+				}],
+				name,
+				number,
+				None,
+			);
+		}
+
+		self.add_native_function(vec![], "getSimTime", number, None);
+
+		Ok(())
+	}
+
+	/// Package functions have to match the signature of the function they override, since calls to
+	/// either one go through the same call sites
+	pub(crate) fn check_packages(&self) -> Result<()> {
+		for package in self.packages.iter() {
+			for function in package.functions.iter() {
+				let overridden_name = function
+					.overrides
+					.as_ref()
+					.context("Could not get overridden function name")?;
+
+				let Some(overridden) = self.function_name_to_function.get(overridden_name) else {
+					bail!(
+						"Package '{}' overrides function '{}' which is not declared",
+						package.name,
+						overridden_name
+					);
+				};
+
+				let argument_types = |function: &Function| {
+					function
+						.arguments
+						.iter()
+						.map(|argument| argument.ty)
+						.collect::<Vec<TypeHandle>>()
+				};
+
+				if argument_types(overridden) != argument_types(function)
+					|| overridden.return_ty != function.return_ty
+					|| overridden.ty.variadic_type.is_some()
+				{
+					bail!(
+						"Function '{}' in package '{}' does not match the signature of the function it overrides",
+						overridden_name,
+						package.name
+					);
+				}
+			}
+		}
+
+		Ok(())
+	}
+
 	pub fn add_native_function(
 		&mut self,
 		arguments: Vec<FunctionArgument>,
 		name: &str,
 		return_type: TypeHandle,
+		variadic_type: Option<TypeHandle>,
 	) {
 		let argument_types = arguments
 			.iter()
 			.map(|argument| argument.ty)
 			.collect::<Vec<TypeHandle>>();
 
-		let ty = self.type_store.lock().expect("Could not lock type store").create_function_type(
-			name,
-			argument_types,
-			Some(return_type),
-		);
+		let required_argument_count = arguments
+			.iter()
+			.filter(|argument| argument.default.is_none())
+			.count();
+
+		let ty = self
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.create_function_type(
+				name,
+				argument_types,
+				required_argument_count,
+				Some(return_type),
+				variadic_type,
+			);
 
 		let id = self.functions.len();
 		let function = P::new(Function {
 			arguments,
+			exported: true,
 			id,
 			name: name.to_string(),
+			overrides: None,
 			return_ty: Some(return_type),
 			scope: None,
-			span: Span::new(0, 0),
+			span: Span::new(0, 0, 0),
 			ty,
 		});
 
@@ -87,17 +355,39 @@ impl Program {
 }
 
 pub fn parse_string(contents: &str, file_name: &str) -> Result<P<Program>> {
-	match PestParser::parse(Rule::program, &contents) {
-		Ok(pairs) => Ok(P::new(Expression::parse_program(
-			file_name,
-			Arc::new(Mutex::new(TypeStore::new())),
-			pairs,
-		)?)),
-		Err(error) => {
-			attempt_print_pest_error(error, contents, file_name);
-			bail!("Could not parse string")
-		}
-	}
+	return parse_string_with_options(contents, file_name, CompileOptions::default());
+}
+
+pub fn parse_string_with_options(
+	contents: &str,
+	file_name: &str,
+	options: CompileOptions,
+) -> Result<P<Program>> {
+	let mut loader = ModuleLoader::new(Arc::new(Mutex::new(TypeStore::new())));
+	let global_scope = loader.load_file(contents, file_name, None)?;
+
+	let mut program = Expression::parse_program(loader, 0, global_scope, options)?;
+	program.add_builtin_functions()?;
+	program.check_packages()?;
+
+	Ok(P::new(program))
+}
+
+/// Parses code that is loaded while `program` is running. The returned program contains the
+/// functions of `program` followed by the functions declared by the new code
+pub fn parse_additional_string(
+	program: &Program,
+	contents: &str,
+	file_name: &str,
+) -> Result<P<Program>> {
+	let mut loader = ModuleLoader::from_program(program);
+	let file = loader.files.len() as u32;
+	let global_scope = loader.load_file(contents, file_name, None)?;
+
+	let program = Expression::parse_program(loader, file, global_scope, program.options.clone())?;
+	program.check_packages()?;
+
+	Ok(P::new(program))
 }
 
 pub fn parse_file(file_name: &str) -> Result<P<Program>> {
@@ -132,7 +422,7 @@ fn get_line_number(location: &LineColLocation) -> usize {
 	}
 }
 
-fn attempt_print_pest_error(error: Error<Rule>, contents: &str, file_name: &str) {
+pub(crate) fn attempt_print_pest_error(error: Error<Rule>, contents: &str, file_name: &str) {
 	let ErrorVariant::ParsingError {
 		positives,
 		negatives: _,
@@ -8,6 +8,11 @@ pub fn print_error_header(name: &str, file_name: &str) {
 	println!("  {} {}", "-->".blue(), file_name);
 }
 
+pub fn print_warning_header(name: &str, location: &str) {
+	println!("{} {}", "warning:".yellow(), name);
+	println!("  {} {}", "-->".blue(), location);
+}
+
 fn reformat_line(line: &str) -> (String, isize) {
 	let mut offset = 0;
 	for character in line.chars() {
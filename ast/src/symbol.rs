@@ -4,11 +4,12 @@ pub type Symbol = String;
 pub struct Span {
 	start: u32,
 	end: u32,
+	file: u32,
 }
 
 impl Span {
-	pub fn new(start: u32, end: u32) -> Span {
-		Span { start, end }
+	pub fn new(start: u32, end: u32, file: u32) -> Span {
+		Span { start, end, file }
 	}
 
 	pub fn start(&self) -> u32 {
@@ -19,32 +20,29 @@ impl Span {
 		self.end
 	}
 
+	/// Index of the file the span points into, see `Program::files`
+	pub fn file(&self) -> u32 {
+		self.file
+	}
+
 	pub fn combine(&self, other: &Span) -> Span {
 		Span {
 			start: self.start,
 			end: other.end,
-		}
-	}
-}
-
-impl From<pest::Span<'_>> for Span {
-	fn from(value: pest::Span) -> Self {
-		Span {
-			start: value.start() as u32,
-			end: value.end() as u32,
+			file: self.file,
 		}
 	}
 }
 
 impl Into<eggscript_mir::Span> for Span {
 	fn into(self) -> eggscript_mir::Span {
-		eggscript_mir::Span::new(self.start(), self.end())
+		eggscript_mir::Span::new(self.start(), self.end(), self.file())
 	}
 }
 
 impl Into<eggscript_mir::Span> for &Span {
 	fn into(self) -> eggscript_mir::Span {
-		eggscript_mir::Span::new(self.start(), self.end())
+		eggscript_mir::Span::new(self.start(), self.end(), self.file())
 	}
 }
 
@@ -0,0 +1,419 @@
+use anyhow::{Context, Result};
+use eggscript_ast::{parse_string_with_options, CompileOptions, Program};
+use eggscript_interpreter::{
+	get_native_function_mapping_for_interpreter, Interpreter, RunStatus, Value,
+};
+use eggscript_types::P;
+use std::collections::BTreeSet;
+use std::io::{BufRead, Write};
+
+use crate::eggscript::{self, LocalSlots};
+
+const HELP: &str = "Commands:
+  break [file:]<line>   stop when a line is reached
+  delete [file:]<line>  remove a breakpoint
+  continue              run until a breakpoint is reached
+  step                  run until the next line, entering calls
+  next                  run until the next line, stepping over calls
+  out                   run until the current function returns
+  print <name>          show the value of a variable
+  locals                show every variable of the current function
+  backtrace             show the call stack
+  quit                  stop debugging";
+
+/// How far the debugger lets the interpreter run before it stops again
+#[derive(Clone, Copy, PartialEq)]
+enum StepMode {
+	Continue,
+	Into,
+	Over,
+	Out,
+}
+
+/// Line the interpreter is on, and how many frames deep it is
+#[derive(Clone, Copy, PartialEq)]
+struct Location {
+	depth: usize,
+	file: u32,
+	line: usize,
+}
+
+/// Runs a program in the interpreter one instruction at a time, stopping at breakpoints and after
+/// steps so the state of the program can be looked at
+pub struct Debugger {
+	/// Files and lines the debugger stops at
+	breakpoints: BTreeSet<(u32, usize)>,
+	interpreter: Interpreter,
+	/// Byte offsets at which the lines of every file start
+	line_starts: Vec<Vec<u32>>,
+	local_slots: LocalSlots,
+	program: P<Program>,
+	/// Whether the program ran, before that the line it starts on counts as reached
+	started: bool,
+}
+
+impl Debugger {
+	pub fn new(program: P<Program>, interpreter: Interpreter, local_slots: LocalSlots) -> Debugger {
+		let line_starts = program
+			.files
+			.iter()
+			.map(|file| {
+				let mut starts = vec![0];
+				starts.extend(
+					file.contents
+						.match_indices('\n')
+						.map(|(index, _)| index as u32 + 1),
+				);
+
+				starts
+			})
+			.collect();
+
+		Debugger {
+			breakpoints: BTreeSet::new(),
+			interpreter,
+			line_starts,
+			local_slots,
+			program,
+			started: false,
+		}
+	}
+
+	/// Reads commands until the input ends or the user quits
+	pub fn run(&mut self, input: impl BufRead, output: &mut impl Write) -> Result<()> {
+		writeln!(output, "Type 'help' for a list of commands")?;
+
+		let mut lines = input.lines();
+		loop {
+			write!(output, "(egg) ")?;
+			output.flush().context("Could not flush output")?;
+
+			let Some(line) = lines.next() else {
+				writeln!(output)?;
+				return Ok(());
+			};
+
+			let line = line.context("Could not read command")?;
+			let mut words = line.split_whitespace();
+			let Some(command) = words.next() else {
+				continue;
+			};
+
+			let argument = words.next();
+
+			match command {
+				"b" | "break" => self.set_breakpoint(argument, true, output)?,
+				"d" | "delete" => self.set_breakpoint(argument, false, output)?,
+				"c" | "continue" => self.resume(StepMode::Continue, output)?,
+				"s" | "step" => self.resume(StepMode::Into, output)?,
+				"n" | "next" => self.resume(StepMode::Over, output)?,
+				"o" | "out" | "finish" => self.resume(StepMode::Out, output)?,
+				"p" | "print" => self.print_local(argument, output)?,
+				"l" | "locals" => self.print_locals(output)?,
+				"bt" | "backtrace" => writeln!(
+					output,
+					"Call stack:{}",
+					self.program.format_backtrace(&self.interpreter.backtrace())
+				)?,
+				"h" | "help" => writeln!(output, "{}", HELP)?,
+				"q" | "quit" => return Ok(()),
+				_ => writeln!(
+					output,
+					"Unknown command '{}', type 'help' for a list of commands",
+					command
+				)?,
+			}
+		}
+	}
+
+	/// Lets the interpreter run until the step is over, a breakpoint is reached or the program ends
+	fn resume(&mut self, mode: StepMode, output: &mut impl Write) -> Result<()> {
+		if self.interpreter.is_finished() {
+			writeln!(output, "The program has finished")?;
+			return Ok(());
+		}
+
+		let start_depth = self.interpreter.call_depth();
+		let mut previous = self.location();
+
+		// steps only reach lines the program was not on already, so a breakpoint on the line the
+		// program starts on is checked before it runs
+		if !self.started {
+			self.started = true;
+			if let Some(location) = previous
+				&& self.breakpoints.contains(&(location.file, location.line))
+			{
+				return self.print_location(output, true);
+			}
+		}
+
+		// line of the function that is stepped over, calls made from it do not change it
+		let mut previous_line = previous.map(|location| location.line);
+
+		loop {
+			// pauses are where the host would get control back, so the debugger stops there too
+			let status = match self.interpreter.step() {
+				Ok(status) => status,
+				Err(error) => {
+					writeln!(
+						output,
+						"{}",
+						self.program.runtime_error_with_backtrace(error)
+					)?;
+					return Ok(());
+				}
+			};
+
+			if self.interpreter.is_finished() {
+				match self.interpreter.run() {
+					Ok(_) => writeln!(output, "The program has finished")?,
+					Err(error) => writeln!(
+						output,
+						"{}",
+						self.program.runtime_error_with_backtrace(error)
+					)?,
+				}
+
+				return Ok(());
+			}
+
+			match status {
+				Some(RunStatus::Yielded(value)) => {
+					writeln!(output, "Yielded {}", format_value(&value))?;
+					return self.print_location(output, false);
+				}
+				Some(RunStatus::Waiting(_)) => {
+					writeln!(output, "Waiting for an async native")?;
+					return self.print_location(output, false);
+				}
+				Some(_) => {
+					writeln!(output, "Suspended")?;
+					return self.print_location(output, false);
+				}
+				None => {}
+			}
+
+			let depth = self.interpreter.call_depth();
+			if mode == StepMode::Out && depth < start_depth {
+				return self.print_location(output, false);
+			}
+
+			let Some(location) = self.location() else {
+				continue;
+			};
+
+			// returning to the line of a call is not reaching that line again
+			let entered_line = previous
+				.is_none_or(|previous| previous != location && previous.depth <= location.depth);
+			previous = Some(location);
+
+			if entered_line && self.breakpoints.contains(&(location.file, location.line)) {
+				return self.print_location(output, true);
+			}
+
+			let stop = match mode {
+				StepMode::Continue | StepMode::Out => false,
+				StepMode::Into => entered_line,
+				StepMode::Over if depth < start_depth => true,
+				StepMode::Over if depth == start_depth => {
+					let stop = previous_line != Some(location.line);
+					previous_line = Some(location.line);
+					stop
+				}
+				StepMode::Over => false,
+			};
+
+			if stop {
+				return self.print_location(output, false);
+			}
+		}
+	}
+
+	fn location(&self) -> Option<Location> {
+		let span = self.interpreter.current_span()?;
+		let line_starts = self.line_starts.get(span.file as usize)?;
+
+		Some(Location {
+			depth: self.interpreter.call_depth(),
+			file: span.file,
+			line: line_starts.partition_point(|start| *start <= span.start),
+		})
+	}
+
+	fn print_location(&self, output: &mut impl Write, breakpoint: bool) -> Result<()> {
+		let function = self
+			.interpreter
+			.backtrace()
+			.first()
+			.and_then(|frame| frame.function.clone())
+			.unwrap_or("<global scope>".to_string());
+
+		let Some((file_name, line, _, source_line)) = self
+			.interpreter
+			.current_span()
+			.and_then(|span| self.program.source_location(span))
+		else {
+			writeln!(output, "Stopped in {}", function)?;
+			return Ok(());
+		};
+
+		writeln!(
+			output,
+			"{} {} at {}:{}",
+			if breakpoint {
+				"Breakpoint in"
+			} else {
+				"Stopped in"
+			},
+			function,
+			file_name,
+			line
+		)?;
+		writeln!(output, "{:>4} | {}", line, source_line.trim())?;
+
+		Ok(())
+	}
+
+	/// Adds or removes the breakpoint at `[file:]line`, lines without a file are in the main file
+	fn set_breakpoint(
+		&mut self,
+		argument: Option<&str>,
+		add: bool,
+		output: &mut impl Write,
+	) -> Result<()> {
+		let Some(argument) = argument else {
+			writeln!(output, "Expected a line like '12' or 'file.egg:12'")?;
+			return Ok(());
+		};
+
+		let (file_name, line) = match argument.rsplit_once(':') {
+			Some((file_name, line)) => (Some(file_name), line),
+			None => (None, argument),
+		};
+
+		let Ok(line) = line.parse::<usize>() else {
+			writeln!(output, "'{}' is not a line number", line)?;
+			return Ok(());
+		};
+
+		let file = match file_name {
+			Some(file_name) => self
+				.program
+				.files
+				.iter()
+				.position(|file| file.name == file_name || file.name.ends_with(file_name)),
+			None => Some(0),
+		};
+
+		let Some(file) = file else {
+			writeln!(output, "Unknown file '{}'", file_name.unwrap_or(""))?;
+			return Ok(());
+		};
+
+		if line == 0 || line > self.line_starts[file].len() {
+			writeln!(
+				output,
+				"{} does not have a line {}",
+				self.program.files[file].name, line
+			)?;
+			return Ok(());
+		}
+
+		let key = (file as u32, line);
+		if add {
+			self.breakpoints.insert(key);
+			writeln!(
+				output,
+				"Breakpoint set at {}:{}",
+				self.program.files[file].name, line
+			)?;
+		} else if self.breakpoints.remove(&key) {
+			writeln!(
+				output,
+				"Breakpoint removed from {}:{}",
+				self.program.files[file].name, line
+			)?;
+		} else {
+			writeln!(
+				output,
+				"There is no breakpoint at {}:{}",
+				self.program.files[file].name, line
+			)?;
+		}
+
+		Ok(())
+	}
+
+	/// Variables of the function that is running, with the values in their stack slots. Functions
+	/// loaded with `exec` do not have any
+	fn current_locals(&self) -> Vec<(&str, &Value)> {
+		let Some(slots) = self.local_slots.get(&self.interpreter.current_function()) else {
+			return vec![];
+		};
+
+		slots
+			.iter()
+			.filter_map(|(name, slot)| {
+				self.interpreter
+					.local(*slot)
+					.map(|value| (name.as_str(), value))
+			})
+			.collect()
+	}
+
+	fn print_local(&self, name: Option<&str>, output: &mut impl Write) -> Result<()> {
+		let Some(name) = name else {
+			writeln!(output, "Expected the name of a variable")?;
+			return Ok(());
+		};
+
+		// the sigil of local variables can be left out
+		let local = self.current_locals().into_iter().find(|(local, _)| {
+			*local == name || local.strip_prefix('%').is_some_and(|local| local == name)
+		});
+
+		match local {
+			Some((local, value)) => writeln!(output, "{} = {}", local, format_value(value))?,
+			None => writeln!(output, "No variable named '{}' in this function", name)?,
+		}
+
+		Ok(())
+	}
+
+	fn print_locals(&self, output: &mut impl Write) -> Result<()> {
+		let locals = self.current_locals();
+		if locals.len() == 0 {
+			writeln!(output, "No variables")?;
+		}
+
+		for (name, value) in locals.into_iter() {
+			writeln!(output, "{} = {}", name, format_value(value))?;
+		}
+
+		Ok(())
+	}
+}
+
+fn format_value(value: &Value) -> String {
+	match value {
+		Value::String(_) => format!("\"{}\"", value),
+		_ => value.to_string(),
+	}
+}
+
+/// Compiles a program for the interpreter and debugs it with commands read from stdin
+pub fn debug_eggscript_program(
+	contents: &str,
+	file_name: &str,
+	options: CompileOptions,
+) -> Result<()> {
+	let program = parse_string_with_options(contents, file_name, options)?;
+	let (interpreter, local_slots) = eggscript::create_interpreter(
+		program.clone(),
+		get_native_function_mapping_for_interpreter(),
+		false,
+	)?;
+
+	let mut debugger = Debugger::new(program, interpreter, local_slots);
+	debugger.run(std::io::stdin().lock(), &mut std::io::stdout())
+}
@@ -1,13 +1,18 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use colored::Colorize;
-use eggscript_ast::{compile_expression, compile_function, parse_string, Function, Program};
+use eggscript_ast::{
+	compile_expression, compile_function, parse_additional_string, parse_string_with_options,
+	CompileOptions, Function, Package, Program,
+};
 use eggscript_interpreter::{
-	get_native_function_mapping_for_interpreter, Instruction, Interpreter,
+	get_native_function_mapping_for_interpreter, CompiledSource, FunctionHandle, Instruction,
+	Interpreter, NativeFunction, RunStatus, SourceCompiler, SourceSpan, ValueType,
 };
 use eggscript_mir::{EggscriptLowerContext, Unit, UnitHandle};
-use eggscript_types::P;
+use eggscript_types::{KnownTypeInfo, Primitive, TypeHandle, TypeStore, P};
 use indexmap::IndexMap;
 use serde::Serialize;
+use std::collections::HashMap;
 use std::ops::Deref;
 
 pub fn instructions_to_vector_string(instructions: &Vec<Instruction>) -> Vec<String> {
@@ -37,7 +42,12 @@ pub fn lower_function(
 	program: P<Program>,
 	function: &P<Function>,
 	debug: bool,
-) -> Result<(IndexMap<UnitHandle, Unit>, Vec<Instruction>)> {
+) -> Result<(
+	IndexMap<UnitHandle, Unit>,
+	Vec<Instruction>,
+	Vec<Option<SourceSpan>>,
+	Vec<(String, usize)>,
+)> {
 	let (ast_context, units) = compile_function(
 		function.clone(),
 		program,
@@ -62,9 +72,80 @@ pub fn lower_function(
 	}
 
 	let mut eggscript_context: EggscriptLowerContext = ast_context.into();
-	let instructions = eggscript_context.compile_to_eggscript(&units, Some(function.ty.clone()))?;
+	let (instructions, spans) =
+		eggscript_context.compile_to_eggscript(&units, Some(function.ty.clone()))?;
+
+	Ok((units, instructions, spans, eggscript_context.local_slots()))
+}
+
+pub fn lower_package(program: &Program, package: &Package) -> eggscript_interpreter::Package {
+	eggscript_interpreter::Package {
+		name: package.name.clone(),
+		overrides: package
+			.functions
+			.iter()
+			.map(|function| {
+				let overridden = function
+					.overrides
+					.as_ref()
+					.and_then(|name| program.function_name_to_function.get(name))
+					.expect("Could not get overridden function");
+
+				(overridden.id, function.id)
+			})
+			.collect(),
+	}
+}
+
+/// Compiles files loaded with `exec` against the functions of the running program
+pub struct ProgramCompiler {
+	program: P<Program>,
+}
+
+impl ProgramCompiler {
+	pub fn new(program: P<Program>) -> ProgramCompiler {
+		ProgramCompiler { program }
+	}
+}
+
+impl SourceCompiler for ProgramCompiler {
+	fn compile(&mut self, contents: &str, file_name: &str) -> Result<CompiledSource> {
+		let first_new_function = self.program.functions.len();
+		let first_new_package = self.program.packages.len();
+		let program = parse_additional_string(&self.program, contents, file_name)?;
+
+		let (ast_context, units) =
+			compile_expression(program.clone(), program.global_scope.clone())?;
+
+		let mut eggscript_context: EggscriptLowerContext = ast_context.into();
+		let (instructions, spans) = eggscript_context.compile_to_eggscript(&units, None)?;
+
+		let mut functions = vec![];
+		for function in program.functions[first_new_function..].iter() {
+			let (_, instructions, spans, _) = lower_function(program.clone(), function, false)?;
+			functions.push(eggscript_interpreter::Function::new_eggscript_function(
+				function.id,
+				function.arguments.len(),
+				instructions,
+				spans,
+				&function.name,
+			));
+		}
+
+		let packages = program.packages[first_new_package..]
+			.iter()
+			.map(|package| lower_package(&program, package))
+			.collect();
 
-	Ok((units, instructions))
+		self.program = program;
+
+		Ok(CompiledSource {
+			functions,
+			instructions,
+			packages,
+			spans,
+		})
+	}
 }
 
 #[derive(Debug, Serialize)]
@@ -85,8 +166,9 @@ pub struct InterpreterCompilationResult {
 pub fn compile_eggscript_program(
 	contents: &str,
 	file_name: &str,
+	options: CompileOptions,
 ) -> Result<InterpreterCompilationResult> {
-	let program = parse_string(contents, file_name)?;
+	let program = parse_string_with_options(contents, file_name, options)?;
 
 	let mut result = InterpreterCompilationResult {
 		functions: Vec::new(),
@@ -95,7 +177,7 @@ pub fn compile_eggscript_program(
 	let (ast_context, units) = compile_expression(program.clone(), program.global_scope.clone())?;
 
 	let mut eggscript_context: EggscriptLowerContext = ast_context.into();
-	let instructions = eggscript_context.compile_to_eggscript(&units, None)?;
+	let (instructions, _) = eggscript_context.compile_to_eggscript(&units, None)?;
 
 	result.functions.push(InterpreterFunctionResult {
 		arguments: Vec::new(),
@@ -108,7 +190,7 @@ pub fn compile_eggscript_program(
 
 	for function in program.functions.iter() {
 		if function.scope.is_some() {
-			let (units, instructions) = lower_function(program.clone(), function, false)?;
+			let (units, instructions, _, _) = lower_function(program.clone(), function, false)?;
 			let type_store = program
 				.type_store
 				.lock()
@@ -151,20 +233,57 @@ pub fn compile_eggscript_program(
 	Ok(result)
 }
 
-#[allow(dead_code)]
-pub fn run_eggscript_program(contents: &str, file_name: &str, debug: bool) -> Result<()> {
-	let program = parse_string(contents, file_name)?;
-
-	if debug {
-		println!("{}", program.global_scope.deref());
+/// Type the host passes values of a script type as. Enums are passed as the index of their variant
+pub fn value_type(type_store: &TypeStore, ty: TypeHandle) -> ValueType {
+	let Some(ty) = type_store
+		.resolve_type(ty)
+		.and_then(|ty| type_store.get_type(ty))
+	else {
+		return ValueType::Unsupported("unknown".into());
+	};
 
-		for function in program.functions.iter() {
-			println!("{}", function.deref());
+	match ty.get_info() {
+		Some(KnownTypeInfo::Enum(_)) => ValueType::Number,
+		Some(KnownTypeInfo::Map(key, value)) => ValueType::Map(
+			Box::new(value_type(type_store, *key)),
+			Box::new(value_type(type_store, *value)),
+		),
+		Some(KnownTypeInfo::Optional(inner)) => {
+			ValueType::Optional(Box::new(value_type(type_store, *inner)))
 		}
-
-		println!("{}", "Global program".yellow());
+		Some(KnownTypeInfo::Primitive(Primitive::Any)) => ValueType::Any,
+		Some(KnownTypeInfo::Primitive(Primitive::Number)) => ValueType::Number,
+		Some(KnownTypeInfo::Primitive(Primitive::String)) => ValueType::String,
+		_ => ValueType::Unsupported(ty.get_name().unwrap_or("unknown").into()),
 	}
+}
+
+/// Types the host has to pass the arguments of a function as
+pub fn argument_types(program: &Program, function: &Function) -> Vec<ValueType> {
+	let type_store = program
+		.type_store
+		.lock()
+		.expect("Could not lock type store");
 
+	function
+		.ty
+		.argument_types
+		.iter()
+		.map(|ty| value_type(&type_store, *ty))
+		.collect()
+}
+
+/// Stack slots of the variables of every function, by the function they belong to. The global
+/// scope is `None`
+pub type LocalSlots = HashMap<Option<FunctionHandle>, Vec<(String, usize)>>;
+
+/// Lowers the program and adds its functions and packages to a new interpreter, which is ready to
+/// run the global scope
+pub fn create_interpreter(
+	program: P<Program>,
+	native_function_mapping: HashMap<String, NativeFunction>,
+	debug: bool,
+) -> Result<(Interpreter, LocalSlots)> {
 	let (ast_context, units) = compile_expression(program.clone(), program.global_scope.clone())?;
 
 	if debug {
@@ -174,7 +293,7 @@ pub fn run_eggscript_program(contents: &str, file_name: &str, debug: bool) -> Re
 	}
 
 	let mut eggscript_context: EggscriptLowerContext = ast_context.into();
-	let instructions = eggscript_context.compile_to_eggscript(&units, None)?;
+	let (instructions, spans) = eggscript_context.compile_to_eggscript(&units, None)?;
 
 	if debug {
 		for instruction in instructions.iter() {
@@ -184,13 +303,17 @@ pub fn run_eggscript_program(contents: &str, file_name: &str, debug: bool) -> Re
 		println!("");
 	}
 
-	let mut interpreter = Interpreter::new(instructions);
+	let mut local_slots = LocalSlots::new();
+	local_slots.insert(None, eggscript_context.local_slots());
 
-	let native_function_mapping = get_native_function_mapping_for_interpreter();
+	let mut interpreter = Interpreter::new(instructions);
+	interpreter.set_spans(spans);
 
 	for function in program.functions.iter() {
 		if function.scope.is_some() {
-			let (_, instructions) = lower_function(program.clone(), function, debug)?;
+			let (_, instructions, spans, slots) = lower_function(program.clone(), function, debug)?;
+			local_slots.insert(Some(function.id), slots);
+
 			if debug {
 				for instruction in instructions.iter() {
 					println!("{:?}", instruction);
@@ -202,6 +325,7 @@ pub fn run_eggscript_program(contents: &str, file_name: &str, debug: bool) -> Re
 				function.id,
 				function.arguments.len(),
 				instructions,
+				spans,
 				&function.name,
 			));
 		} else {
@@ -217,7 +341,57 @@ pub fn run_eggscript_program(contents: &str, file_name: &str, debug: bool) -> Re
 		}
 	}
 
-	interpreter.run();
+	for function in program.functions.iter() {
+		interpreter.set_argument_types(function.id, argument_types(&program, function));
+	}
+
+	for package in program.packages.iter() {
+		interpreter.add_package(lower_package(&program, package));
+	}
+
+	interpreter.set_compiler(ProgramCompiler::new(program.clone()));
+
+	Ok((interpreter, local_slots))
+}
+
+#[allow(dead_code)]
+pub fn run_eggscript_program(
+	contents: &str,
+	file_name: &str,
+	debug: bool,
+	options: CompileOptions,
+) -> Result<()> {
+	let program = parse_string_with_options(contents, file_name, options)?;
+
+	if debug {
+		println!("{}", program.global_scope.deref());
+
+		for function in program.functions.iter() {
+			println!("{}", function.deref());
+		}
+
+		println!("{}", "Global program".yellow());
+	}
+
+	let (mut interpreter, _) = create_interpreter(
+		program.clone(),
+		get_native_function_mapping_for_interpreter(),
+		debug,
+	)?;
+
+	// the command line has nothing to do while a script is paused, so it continues right away
+	loop {
+		let status = interpreter
+			.run()
+			.map_err(|error| program.runtime_error_with_backtrace(error))?;
+
+		match status {
+			RunStatus::Finished => break,
+			// none of the command line's natives are async, so nothing would fulfill the token
+			RunStatus::Waiting(_) => bail!("The program waits for an async native"),
+			_ => {}
+		}
+	}
 
 	if debug {
 		println!("Results:");
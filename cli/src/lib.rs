@@ -0,0 +1,6 @@
+pub mod debugger;
+pub mod eggscript;
+pub mod llvm;
+
+#[cfg(test)]
+mod tests;
@@ -1,20 +1,38 @@
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context as _, Result};
 use colored::Colorize;
-use eggscript_ast::{compile_expression, compile_function, parse_string, Function, Program};
-use eggscript_interpreter::get_native_function_mapping_for_jit;
-use eggscript_mir::{Unit, UnitHandle};
+use eggscript_ast::{
+	compile_expression, compile_function, parse_additional_string, parse_string_with_options,
+	CompileOptions, Function, Program,
+};
+use eggscript_interpreter::runtime::{any, exception, map, sandbox, stack};
+use eggscript_interpreter::{
+	check_argument_types, get_native_function_mapping_for_jit, FunctionReference, InterruptHandle,
+	PackageTable, SandboxConfig, Scheduler, Value, ValueType,
+};
+use eggscript_mir::{
+	Dispatch, Unit, UnitHandle, ANY_TO_NUMBER_FUNCTION, ANY_TO_STRING_FUNCTION, CALL_DEPTH_GLOBAL,
+	CATCH_FUNCTION, EXCEPTION_PENDING_GLOBAL, INTERRUPT_GLOBAL, MAX_CALL_DEPTH_GLOBAL,
+	POLL_COUNTDOWN_GLOBAL, POLL_FUNCTION, THROW_FUNCTION,
+};
 use eggscript_types::P;
 use indexmap::IndexMap;
 use inkwell::{
 	builder::Builder,
 	context::Context,
-	execution_engine::JitFunction,
+	execution_engine::{ExecutionEngine, JitFunction},
 	module::Module,
-	values::{AnyValue, FunctionValue},
-	OptimizationLevel,
+	types::{BasicMetadataTypeEnum, BasicType, BasicTypeEnum},
+	values::{AnyValue, BasicMetadataValueEnum, BasicValue, FunctionValue},
+	AddressSpace, OptimizationLevel,
 };
 use serde::Serialize;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap};
+use std::ffi::{c_char, c_void, CStr, CString};
 use std::ops::Deref;
+use std::rc::Rc;
+
+use crate::eggscript;
 
 pub fn llvm_to_vector_string(function: &FunctionValue<'_>) -> Vec<String> {
 	let mut result = Vec::new();
@@ -95,8 +113,12 @@ pub struct LLVMCompilationResult {
 	functions: Vec<LLVMFunctionResult>,
 }
 
-pub fn compile_llvm_program(contents: &str, file_name: &str) -> Result<LLVMCompilationResult> {
-	let program = parse_string(contents, file_name)?;
+pub fn compile_llvm_program(
+	contents: &str,
+	file_name: &str,
+	options: CompileOptions,
+) -> Result<LLVMCompilationResult> {
+	let program = parse_string_with_options(contents, file_name, options)?;
 
 	let mut result = LLVMCompilationResult {
 		functions: Vec::new(),
@@ -180,11 +202,854 @@ pub fn compile_llvm_program(contents: &str, file_name: &str) -> Result<LLVMCompi
 	Ok(result)
 }
 
-type EntryFunction = unsafe extern "C" fn();
+pub type EntryFunction = unsafe extern "C" fn();
+
+/// Calls the function at the first argument with arguments read from an array of 8 byte slots,
+/// and writes its result to the slot at the last argument
+type HostCallFunction = unsafe extern "C" fn(usize, *const u64, *mut u64);
+
+thread_local! {
+	/// JIT compiled code calls natives without any context, so the running program is found here
+	static JIT_RUNTIME: Cell<*const c_void> = Cell::new(std::ptr::null());
+}
+
+/// Runtime support for JIT compiled code. Files loaded with `exec` are compiled into new modules
+/// in the execution engine, which resolves calls to functions in previously added modules.
+/// Script functions are called through slots, which are rebound when packages are activated or
+/// deactivated
+pub struct JitRuntime<'a, 'ctx> {
+	/// Natives the compiled code calls. JIT code calls them directly, so a sandbox has to allow
+	/// every one of them
+	called_natives: RefCell<BTreeSet<String>>,
+	context: &'ctx Context,
+	engine: &'a ExecutionEngine<'ctx>,
+	exec_count: Cell<usize>,
+	/// Addresses of the functions that call script functions for the host, by the function they
+	/// call
+	host_calls: RefCell<HashMap<String, usize>>,
+	/// JIT code reads the flag of the handle at the back-edges of loops and in function prologues
+	interrupt: InterruptHandle,
+	modules: RefCell<Vec<Module<'ctx>>>,
+	native_function_mapping: HashMap<String, usize>,
+	packages: RefCell<PackageTable>,
+	program: RefCell<P<Program>>,
+	/// Sandbox the program runs in, `None` if it is not sandboxed
+	sandbox: RefCell<Option<SandboxConfig>>,
+	scheduler: RefCell<Scheduler>,
+	/// Memory behind the slot globals, boxed so the addresses handed to the engine stay valid
+	slots: RefCell<HashMap<String, Box<Cell<usize>>>>,
+}
+
+impl<'a, 'ctx> JitRuntime<'a, 'ctx> {
+	/// Natives have to be mapped into `module` before creating the runtime
+	pub fn new(
+		context: &'ctx Context,
+		engine: &'a ExecutionEngine<'ctx>,
+		module: &Module<'ctx>,
+		program: P<Program>,
+		native_function_mapping: HashMap<String, usize>,
+	) -> Result<Self> {
+		let runtime = JitRuntime {
+			called_natives: RefCell::new(called_natives(module, &program)),
+			context,
+			engine,
+			exec_count: Cell::new(0),
+			host_calls: RefCell::new(HashMap::new()),
+			interrupt: InterruptHandle::default(),
+			modules: RefCell::new(vec![]),
+			native_function_mapping,
+			packages: RefCell::new(PackageTable::default()),
+			program: RefCell::new(program.clone()),
+			sandbox: RefCell::new(None),
+			scheduler: RefCell::new(Scheduler::default()),
+			slots: RefCell::new(HashMap::new()),
+		};
+
+		runtime.map_exception_runtime(module);
+		runtime.map_any_runtime(module);
+		runtime.map_map_runtime(module);
+		runtime.map_stack_runtime(module);
+		runtime.map_sandbox_runtime(module);
+		runtime.add_packages(module, &program, 0)?;
+
+		Ok(runtime)
+	}
+
+	/// Calls the function like `call`, and reports exceptions that unwound out of it
+	pub fn run(&self, function: &JitFunction<'ctx, EntryFunction>) {
+		self.call(function);
+
+		if let Some(exception) = exception::take_uncaught_exception_for_jit() {
+			eprintln!("Uncaught exception: {}", exception);
+		}
+	}
+
+	/// Maps the symbols JIT code uses to raise and handle exceptions onto the runtime
+	fn map_exception_runtime(&self, module: &Module<'ctx>) {
+		if let Some(global) = module.get_global(EXCEPTION_PENDING_GLOBAL) {
+			self.engine
+				.add_global_mapping(&global, exception::exception_pending_address());
+		}
+
+		if let Some(function) = module.get_function(THROW_FUNCTION) {
+			self.engine
+				.add_global_mapping(&function, exception::throw_for_jit as usize);
+		}
+
+		if let Some(function) = module.get_function(CATCH_FUNCTION) {
+			self.engine
+				.add_global_mapping(&function, exception::catch_for_jit as usize);
+		}
+	}
+
+	/// Maps the symbols JIT code uses to convert `any` values onto the runtime
+	fn map_any_runtime(&self, module: &Module<'ctx>) {
+		if let Some(function) = module.get_function(ANY_TO_NUMBER_FUNCTION) {
+			self.engine
+				.add_global_mapping(&function, any::any_to_number_for_jit as usize);
+		}
+
+		if let Some(function) = module.get_function(ANY_TO_STRING_FUNCTION) {
+			self.engine
+				.add_global_mapping(&function, any::any_to_string_for_jit as usize);
+		}
+	}
+
+	/// Maps the symbols JIT code uses to work with maps onto the runtime
+	fn map_map_runtime(&self, module: &Module<'ctx>) {
+		for (name, address) in map::get_map_function_mapping_for_jit() {
+			if let Some(function) = module.get_function(name) {
+				self.engine.add_global_mapping(&function, address);
+			}
+		}
+	}
+
+	/// Maps the counters JIT code keeps the call depth in onto the runtime
+	fn map_stack_runtime(&self, module: &Module<'ctx>) {
+		if let Some(global) = module.get_global(CALL_DEPTH_GLOBAL) {
+			self.engine
+				.add_global_mapping(&global, stack::call_depth_address());
+		}
+
+		if let Some(global) = module.get_global(MAX_CALL_DEPTH_GLOBAL) {
+			self.engine
+				.add_global_mapping(&global, stack::max_call_depth_address());
+		}
+	}
+
+	/// Sets how many script functions can run at once before calls raise a stack overflow
+	pub fn set_max_call_depth(&self, depth: usize) {
+		stack::set_max_call_depth_for_jit(depth);
+	}
+
+	/// Maps the symbols JIT code polls the sandbox and the interrupt flag with onto the runtime
+	fn map_sandbox_runtime(&self, module: &Module<'ctx>) {
+		if let Some(global) = module.get_global(INTERRUPT_GLOBAL) {
+			self.engine
+				.add_global_mapping(&global, self.interrupt.flag_address());
+		}
+
+		if let Some(global) = module.get_global(POLL_COUNTDOWN_GLOBAL) {
+			self.engine
+				.add_global_mapping(&global, sandbox::poll_countdown_address());
+		}
+
+		if let Some(function) = module.get_function(POLL_FUNCTION) {
+			self.engine
+				.add_global_mapping(&function, sandbox::poll_for_jit as usize);
+		}
+	}
+
+	/// Handle that stops the program from another thread, at its next loop iteration or call
+	pub fn interrupt_handle(&self) -> InterruptHandle {
+		self.interrupt.clone()
+	}
+
+	/// Runs the program with the limits of the sandbox from now on. Fails if the program calls a
+	/// native the sandbox does not allow, and files loaded with `exec` fail to load if they do
+	pub fn set_sandbox(&self, config: SandboxConfig) -> Result<()> {
+		check_allowed_natives(&config, self.called_natives.borrow().iter())?;
+
+		sandbox::set_sandbox_for_jit(&config);
+		*self.sandbox.borrow_mut() = Some(config);
+
+		Ok(())
+	}
+
+	/// Calls a JIT compiled function with the runtime's natives available to it
+	pub fn call(&self, function: &JitFunction<'ctx, EntryFunction>) {
+		self.with_natives(|| unsafe { function.call() });
+	}
+
+	fn with_natives<T>(&self, action: impl FnOnce() -> T) -> T {
+		let previous = JIT_RUNTIME
+			.with(|jit_runtime| jit_runtime.replace(self as *const Self as *const c_void));
+
+		let result = action();
+
+		JIT_RUNTIME.with(|jit_runtime| jit_runtime.set(previous));
+
+		// files loaded with `exec` run while the calling JIT code still holds its strings and maps,
+		// so they are only freed once the outermost call returns
+		if previous.is_null() {
+			any::free_strings_for_jit();
+			exception::free_caught_exceptions_for_jit();
+			map::free_maps_for_jit();
+		}
+
+		return result;
+	}
+
+	/// Calls a function from the host, like a callback such as `onTick(%dt)` would be. Calls go to
+	/// the function packages bind the name to. Only numbers, enums and strings can be passed to and
+	/// returned from JIT code, and exceptions the function does not catch are returned as errors
+	pub fn call_function<'r>(
+		&self,
+		function: impl Into<FunctionReference<'r>>,
+		arguments: &[Value],
+	) -> Result<Value> {
+		let program = self.program.borrow().clone();
+		let function = match function.into() {
+			FunctionReference::Handle(handle) => program.functions.get(handle),
+			FunctionReference::Name(name) => program.function_name_to_function.get(name),
+		}
+		.context("Invalid call: Unknown function")?;
+
+		if function.ty.variadic_type.is_some() {
+			bail!(
+				"Invalid call: '{}' is variadic, which the host cannot call in JIT code",
+				function.name
+			);
+		}
+
+		if function.scope.is_none()
+			&& let Some(sandbox) = self.sandbox.borrow().as_ref()
+		{
+			check_allowed_natives(sandbox, [&function.name])?;
+		}
+
+		if function.ty.argument_types.len() != arguments.len() {
+			bail!(
+				"Invalid call: '{}' takes {} arguments, got {}",
+				function.name,
+				function.ty.argument_types.len(),
+				arguments.len()
+			);
+		}
+
+		let argument_types = eggscript::argument_types(&program, function);
+		check_argument_types(&function.name, &argument_types, arguments)
+			.map_err(|message| anyhow!("Invalid call: {}", message))?;
+
+		let return_type = function.ty.return_type.map(|return_type| {
+			let type_store = program
+				.type_store
+				.lock()
+				.expect("Could not lock type store");
+
+			eggscript::value_type(&type_store, return_type)
+		});
+
+		let host_call = self.host_call_address(&function.name, &argument_types, &return_type)?;
+
+		// functions are called through their slot, which points at the function packages bind it to
+		let slot = self
+			.slots
+			.borrow()
+			.get(&Dispatch::function_slot_name(function.id))
+			.map(|slot| slot.get());
+
+		let address = match slot {
+			Some(address) => address,
+			None => self.function_address(function)?,
+		};
+
+		// JIT code reads strings up to their first NUL byte, so it would see a different string
+		let strings = arguments
+			.iter()
+			.enumerate()
+			.filter_map(|(index, argument)| match argument {
+				Value::String(string) => Some(CString::new(string.as_str()).map_err(|_| {
+					anyhow!(
+						"Invalid call: Argument {} of '{}' contains a NUL byte",
+						index + 1,
+						function.name
+					)
+				})),
+				_ => None,
+			})
+			.collect::<Result<Vec<CString>>>()?;
+
+		let mut strings_iter = strings.iter();
+		let slots = arguments
+			.iter()
+			.map(|argument| match argument {
+				Value::Number(number) => number.to_bits(),
+				_ => strings_iter
+					.next()
+					.expect("Could not get string argument")
+					.as_ptr() as u64,
+			})
+			.collect::<Vec<u64>>();
+
+		let host_call: HostCallFunction = unsafe { std::mem::transmute(host_call) };
+
+		// returned strings can belong to the runtime, which frees them once the call returns
+		self.with_natives(|| {
+			let mut result = 0u64;
+			unsafe { host_call(address, slots.as_ptr(), &mut result) };
+
+			if let Some(exception) = exception::take_uncaught_exception_for_jit() {
+				bail!("Uncaught exception: {}", exception);
+			}
+
+			match return_type {
+				Some(ValueType::Number) => Ok(Value::Number(f64::from_bits(result))),
+				Some(ValueType::String) => Ok(Value::String(Rc::new(unsafe {
+					string_argument(result as *const c_char)
+				}))),
+				_ => Ok(Value::Null),
+			}
+		})
+	}
+
+	/// Moves the simulated time forward, and calls every scheduled function that is due in the
+	/// order they are due in. Stops at the first call that fails
+	pub fn advance_time(&self, ms: u64) -> Result<()> {
+		let until = self.scheduler.borrow().time().saturating_add(ms);
+
+		// scheduled functions can schedule calls themselves, so the scheduler is not borrowed
+		// while they run
+		loop {
+			let Some(scheduled_call) = self.scheduler.borrow_mut().next_due(until) else {
+				break;
+			};
+
+			self.call_function(scheduled_call.function.as_str(), &scheduled_call.arguments)?;
+		}
+
+		self.scheduler.borrow_mut().set_time(until);
+
+		Ok(())
+	}
+
+	fn schedule(&self, delay: f64, function: &str, arguments: Vec<Value>) -> Result<f64> {
+		if !self
+			.program
+			.borrow()
+			.function_name_to_function
+			.contains_key(function)
+		{
+			bail!("Cannot schedule unknown function '{}'", function);
+		}
+
+		let is_native = self.program.borrow().function_name_to_function[function]
+			.scope
+			.is_none();
+		if is_native
+			&& let Some(sandbox) = self.sandbox.borrow().as_ref()
+			&& !sandbox.allows_native(function)
+		{
+			bail!(
+				"Cannot schedule '{}', the sandbox does not allow it",
+				function
+			);
+		}
+
+		Ok(self
+			.scheduler
+			.borrow_mut()
+			.schedule(delay, function, arguments) as f64)
+	}
+
+	/// Address of a function that calls functions with the given signature for `call_function`,
+	/// compiled the first time a function is called from the host
+	fn host_call_address(
+		&self,
+		function_name: &str,
+		argument_types: &[ValueType],
+		return_type: &Option<ValueType>,
+	) -> Result<usize> {
+		if let Some(address) = self.host_calls.borrow().get(function_name) {
+			return Ok(*address);
+		}
+
+		let llvm_type = |ty: &ValueType| -> Result<BasicTypeEnum<'ctx>> {
+			match ty {
+				ValueType::Number => Ok(self.context.f64_type().into()),
+				ValueType::String => Ok(self.context.ptr_type(AddressSpace::default()).into()),
+				_ => bail!(
+					"Invalid call: {} values cannot be passed to JIT code by the host",
+					ty
+				),
+			}
+		};
+
+		let called_argument_types = argument_types
+			.iter()
+			.map(|ty| llvm_type(ty).map(|ty| ty.into()))
+			.collect::<Result<Vec<BasicMetadataTypeEnum>>>()?;
+
+		let called_type = match return_type {
+			Some(return_type) => llvm_type(return_type)?.fn_type(&called_argument_types, false),
+			None => self
+				.context
+				.void_type()
+				.fn_type(&called_argument_types, false),
+		};
+
+		let name = format!("host_call_{}", function_name);
+		let module = self.context.create_module(&name);
+		let builder = self.context.create_builder();
+
+		let ptr_type = self.context.ptr_type(AddressSpace::default());
+		let i64_type = self.context.i64_type();
+		let host_call = module.add_function(
+			&name,
+			self.context
+				.void_type()
+				.fn_type(&[i64_type.into(), ptr_type.into(), ptr_type.into()], false),
+			None,
+		);
+
+		builder.position_at_end(self.context.append_basic_block(host_call, "entry"));
+
+		let address = host_call
+			.get_nth_param(0)
+			.context("Could not get function address")?
+			.into_int_value();
+		let slots = host_call
+			.get_nth_param(1)
+			.context("Could not get argument slots")?
+			.into_pointer_value();
+		let result = host_call
+			.get_nth_param(2)
+			.context("Could not get result slot")?
+			.into_pointer_value();
+
+		let mut arguments = vec![];
+		for (index, ty) in argument_types.iter().enumerate() {
+			let slot = unsafe {
+				builder.build_gep(
+					i64_type,
+					slots,
+					&[i64_type.const_int(index as u64, false)],
+					"slot",
+				)?
+			};
+
+			let argument: BasicMetadataValueEnum =
+				builder.build_load(llvm_type(ty)?, slot, "argument")?.into();
+			arguments.push(argument);
+		}
+
+		let called = builder.build_int_to_ptr(address, ptr_type, "function")?;
+		let value = builder
+			.build_indirect_call(called_type, called, &arguments, "result")?
+			.try_as_basic_value()
+			.left();
+
+		if let Some(value) = value {
+			builder.build_store(result, value)?;
+		}
+
+		builder.build_return(None)?;
+
+		self.engine
+			.add_module(&module)
+			.map_err(|_| anyhow!("Could not add module to execution engine"))?;
+
+		let address = self
+			.engine
+			.get_function_address(&name)
+			.map_err(|_| anyhow!("Could not get address of '{}'", name))?;
+
+		self.modules.borrow_mut().push(module);
+		self.host_calls
+			.borrow_mut()
+			.insert(function_name.to_string(), address);
+
+		Ok(address)
+	}
+
+	fn activate_package(&self, name: &str) -> Result<()> {
+		self.packages.borrow_mut().activate(name)?;
+		self.rebind_functions()
+	}
+
+	fn deactivate_package(&self, name: &str) -> Result<()> {
+		self.packages.borrow_mut().deactivate(name)?;
+		self.rebind_functions()
+	}
+
+	/// Registers the packages of `program` starting at `first_package`, and maps the slots `module`
+	/// declares onto the runtime's memory
+	fn add_packages(
+		&self,
+		module: &Module<'ctx>,
+		program: &Program,
+		first_package: usize,
+	) -> Result<()> {
+		for package in program.packages[first_package..].iter() {
+			self.packages
+				.borrow_mut()
+				.add_package(eggscript::lower_package(program, package));
+		}
+
+		let function_slot_names = program
+			.functions
+			.iter()
+			.filter(|function| function.scope.is_some())
+			.map(|function| Dispatch::function_slot_name(function.id));
+
+		let parent_slot_names = self
+			.packages
+			.borrow()
+			.packages()
+			.iter()
+			.flat_map(|package| package.overrides.iter())
+			.map(|(_, function)| Dispatch::parent_slot_name(*function))
+			.collect::<Vec<String>>();
+
+		let mut slots = self.slots.borrow_mut();
+		for slot_name in function_slot_names.chain(parent_slot_names) {
+			let slot = slots
+				.entry(slot_name.clone())
+				.or_insert_with(|| Box::new(Cell::new(0)));
+
+			if let Some(global) = module.get_global(&slot_name) {
+				self.engine
+					.add_global_mapping(&global, slot.as_ptr() as usize);
+			}
+		}
+
+		drop(slots);
+
+		self.rebind_functions()
+	}
+
+	fn rebind_functions(&self) -> Result<()> {
+		let bindings = self.packages.borrow().bindings();
+
+		// calls to every script function go through its slot, so packages loaded later can
+		// override functions that were called before
+		let program = self.program.borrow().clone();
+		for function in program.functions.iter() {
+			if function.scope.is_some() && !bindings.current.contains_key(&function.id) {
+				self.set_slot(&Dispatch::function_slot_name(function.id), function.id)?;
+			}
+		}
+
+		for (overridden, function) in bindings.current.into_iter() {
+			self.set_slot(&Dispatch::function_slot_name(overridden), function)?;
+		}
+
+		for (function, parent) in bindings.parent.into_iter() {
+			self.set_slot(&Dispatch::parent_slot_name(function), parent)?;
+		}
+
+		Ok(())
+	}
+
+	fn set_slot(&self, slot_name: &str, function_handle: usize) -> Result<()> {
+		let program = self.program.borrow();
+		let function = program
+			.functions
+			.get(function_handle)
+			.context("Could not get function")?;
+
+		let address = self.function_address(function)?;
+
+		self.slots
+			.borrow()
+			.get(slot_name)
+			.context("Could not get function slot")?
+			.set(address);
+
+		Ok(())
+	}
+
+	fn function_address(&self, function: &Function) -> Result<usize> {
+		if function.scope.is_some() {
+			return self
+				.engine
+				.get_function_address(&function.name)
+				.map_err(|_| anyhow!("Could not get address of function '{}'", function.name));
+		}
+
+		self.native_function_mapping
+			.get(&function.name)
+			.copied()
+			.context("Could not get native function from map")
+	}
+
+	fn exec(&self, file_name: &str) -> Result<()> {
+		let contents = std::fs::read_to_string(file_name).context("Could not read file")?;
+
+		let first_new_function = self.program.borrow().functions.len();
+		let first_new_package = self.program.borrow().packages.len();
+		let program = parse_additional_string(&self.program.borrow(), &contents, file_name)?;
+
+		let entry_name = format!("exec_entry{}", self.exec_count.get());
+		self.exec_count.set(self.exec_count.get() + 1);
+
+		let (ast_context, units) =
+			compile_expression(program.clone(), program.global_scope.clone())?;
+
+		let builder = self.context.create_builder();
+		let module = self.context.create_module(file_name);
+		let mut llvm_context = ast_context.into_llvm_lower_context(self.context, &builder, &module);
+		llvm_context.set_entry_name(&entry_name);
+
+		for function in program.functions.iter() {
+			llvm_context.pre_define_function(&function.ty)?;
+		}
+
+		llvm_context.compile_to_ir(&units, None)?;
+		llvm_context.optimize_ir();
+
+		for function in program.functions[first_new_function..].iter() {
+			lower_function(
+				self.context,
+				&builder,
+				&module,
+				program.clone(),
+				function,
+				false,
+			)?;
+		}
+
+		drop(llvm_context);
+
+		let natives = called_natives(&module, &program);
+		if let Some(sandbox) = self.sandbox.borrow().as_ref() {
+			check_allowed_natives(sandbox, &natives)?;
+		}
+
+		self.called_natives.borrow_mut().extend(natives);
+
+		self.engine
+			.add_module(&module)
+			.map_err(|_| anyhow!("Could not add module to execution engine"))?;
+
+		for function in program.functions.iter() {
+			if function.scope.is_none() {
+				let function_declaration = module
+					.get_function(&function.name)
+					.expect("Could not find function in module");
+
+				self.engine.add_global_mapping(
+					&function_declaration,
+					*self
+						.native_function_mapping
+						.get(&function.name)
+						.expect("Could not get native function from map"),
+				);
+			}
+		}
+
+		*self.program.borrow_mut() = program.clone();
+		self.map_exception_runtime(&module);
+		self.map_any_runtime(&module);
+		self.map_map_runtime(&module);
+		self.map_stack_runtime(&module);
+		self.map_sandbox_runtime(&module);
+
+		self.add_packages(&module, &program, first_new_package)?;
+
+		let entry: JitFunction<EntryFunction> = unsafe { self.engine.get_function(&entry_name)? };
+		self.modules.borrow_mut().push(module);
+
+		self.call(&entry);
+
+		Ok(())
+	}
+}
+
+impl Drop for JitRuntime<'_, '_> {
+	/// The sandbox is kept per thread, so it would limit the next program on the thread too
+	fn drop(&mut self) {
+		if self.sandbox.borrow().is_some() {
+			sandbox::clear_sandbox_for_jit();
+		}
+	}
+}
+
+/// Names of the natives of `program` that the code in `module` calls
+fn called_natives(module: &Module<'_>, program: &Program) -> BTreeSet<String> {
+	program
+		.functions
+		.iter()
+		.filter(|function| function.scope.is_none())
+		.filter(|function| {
+			module
+				.get_function(&function.name)
+				.is_some_and(|declaration| {
+					declaration
+						.as_global_value()
+						.as_pointer_value()
+						.get_first_use()
+						.is_some()
+				})
+		})
+		.map(|function| function.name.clone())
+		.collect()
+}
+
+/// Fails if the sandbox does not allow one of the natives
+fn check_allowed_natives<'n>(
+	sandbox: &SandboxConfig,
+	natives: impl IntoIterator<Item = &'n String>,
+) -> Result<()> {
+	for native in natives {
+		if !sandbox.allows_native(native) {
+			bail!(
+				"Native function '{}' is not allowed in this sandbox",
+				native
+			);
+		}
+	}
+
+	Ok(())
+}
+
+/// Runs `action` on the runtime of the program that is running on this thread
+fn with_jit_runtime<T>(action: impl FnOnce(&JitRuntime) -> Result<T>) -> Result<T> {
+	// the pointer is only set while `JitRuntime::call` is running, which outlives this call
+	let jit_runtime =
+		JIT_RUNTIME.with(|jit_runtime| jit_runtime.get()) as *const JitRuntime<'static, 'static>;
+
+	if jit_runtime.is_null() {
+		bail!("No program is running");
+	}
+
+	action(unsafe { &*jit_runtime })
+}
+
+/// # Safety
+///
+/// `argument` has to point to a NUL-terminated string
+unsafe fn string_argument(argument: *const c_char) -> String {
+	unsafe { CStr::from_ptr(argument) }
+		.to_string_lossy()
+		.to_string()
+}
+
+/// Loads a file into the running JIT program, returns 1 on success and 0 on failure
+///
+/// # Safety
+///
+/// `file_name` has to point to a NUL-terminated string
+pub unsafe extern "C" fn exec_for_jit(file_name: *const c_char) -> f64 {
+	let file_name = unsafe { string_argument(file_name) };
+	match with_jit_runtime(|jit_runtime| jit_runtime.exec(&file_name)) {
+		Ok(()) => 1.0,
+		Err(error) => {
+			eprintln!("Could not exec '{}': {:?}", file_name, error);
+			0.0
+		}
+	}
+}
+
+/// Returns 1 if the package was activated and 0 if it does not exist
+///
+/// # Safety
+///
+/// `name` has to point to a NUL-terminated string
+pub unsafe extern "C" fn activate_package_for_jit(name: *const c_char) -> f64 {
+	let name = unsafe { string_argument(name) };
+	match with_jit_runtime(|jit_runtime| jit_runtime.activate_package(&name)) {
+		Ok(()) => 1.0,
+		Err(error) => {
+			eprintln!("Could not activate package: {}", error);
+			0.0
+		}
+	}
+}
+
+/// Returns 1 if the package was deactivated and 0 if it was not active
+///
+/// # Safety
+///
+/// `name` has to point to a NUL-terminated string
+pub unsafe extern "C" fn deactivate_package_for_jit(name: *const c_char) -> f64 {
+	let name = unsafe { string_argument(name) };
+	match with_jit_runtime(|jit_runtime| jit_runtime.deactivate_package(&name)) {
+		Ok(()) => 1.0,
+		Err(error) => {
+			eprintln!("Could not deactivate package: {}", error);
+			0.0
+		}
+	}
+}
+
+/// Schedules a call of a function by its name, returns the id the call can be cancelled with
+///
+/// # Safety
+///
+/// `function` has to point to a NUL-terminated string, and `arguments` to `argument_count` values
+pub unsafe extern "C" fn schedule_for_jit(
+	delay: f64,
+	function: *const c_char,
+	argument_count: u64,
+	arguments: *const any::AnyForJit,
+) -> f64 {
+	let function = unsafe { string_argument(function) };
+	let arguments = unsafe { any::values_from_jit(argument_count, arguments) };
+	match with_jit_runtime(|jit_runtime| jit_runtime.schedule(delay, &function, arguments)) {
+		Ok(id) => id,
+		Err(error) => {
+			exception::raise_for_jit(&error.to_string());
+			0.0
+		}
+	}
+}
+
+/// Returns 1 if the call was pending and 0 if it already ran or was cancelled
+pub extern "C" fn cancel_for_jit(id: f64) -> f64 {
+	with_jit_runtime(|jit_runtime| Ok(jit_runtime.scheduler.borrow_mut().cancel(id as usize)))
+		.unwrap_or_default() as u8 as f64
+}
+
+pub extern "C" fn is_event_pending_for_jit(id: f64) -> f64 {
+	with_jit_runtime(|jit_runtime| Ok(jit_runtime.scheduler.borrow().is_pending(id as usize)))
+		.unwrap_or_default() as u8 as f64
+}
+
+/// Simulated time in milliseconds
+pub extern "C" fn get_sim_time_for_jit() -> f64 {
+	with_jit_runtime(|jit_runtime| Ok(jit_runtime.scheduler.borrow().time())).unwrap_or_default()
+		as f64
+}
+
+/// Adds the natives that need access to the JIT runtime
+pub fn add_runtime_natives(function_mapping: &mut HashMap<String, usize>) {
+	function_mapping.insert("exec".to_string(), exec_for_jit as usize);
+	function_mapping.insert(
+		"activatePackage".to_string(),
+		activate_package_for_jit as usize,
+	);
+	function_mapping.insert(
+		"deactivatePackage".to_string(),
+		deactivate_package_for_jit as usize,
+	);
+	function_mapping.insert("schedule".to_string(), schedule_for_jit as usize);
+	function_mapping.insert("cancel".to_string(), cancel_for_jit as usize);
+	function_mapping.insert(
+		"isEventPending".to_string(),
+		is_event_pending_for_jit as usize,
+	);
+	function_mapping.insert("getSimTime".to_string(), get_sim_time_for_jit as usize);
+}
 
 #[allow(dead_code)]
-pub fn run_llvm_program(contents: &str, file_name: &str, debug: bool) -> Result<()> {
-	let program = parse_string(contents, file_name)?;
+pub fn run_llvm_program(
+	contents: &str,
+	file_name: &str,
+	debug: bool,
+	options: CompileOptions,
+) -> Result<()> {
+	let program = parse_string_with_options(contents, file_name, options)?;
 
 	if debug {
 		println!("{}", program.global_scope.deref());
@@ -225,7 +1090,8 @@ pub fn run_llvm_program(contents: &str, file_name: &str, debug: bool) -> Result<
 		.create_jit_execution_engine(OptimizationLevel::Default)
 		.expect("Could not create JIT execution engine");
 
-	let function_mapping = get_native_function_mapping_for_jit();
+	let mut function_mapping = get_native_function_mapping_for_jit();
+	add_runtime_natives(&mut function_mapping);
 
 	for function in program.functions.iter() {
 		if function.scope.is_some() {
@@ -256,10 +1122,15 @@ pub fn run_llvm_program(contents: &str, file_name: &str, debug: bool) -> Result<
 		}
 	}
 
-	unsafe {
-		let function: JitFunction<EntryFunction> = engine.get_function("entry")?;
-		function.call();
-	}
+	let jit_runtime = JitRuntime::new(
+		&context,
+		&engine,
+		&module,
+		program.clone(),
+		function_mapping,
+	)?;
+	let function: JitFunction<EntryFunction> = unsafe { engine.get_function("entry")? };
+	jit_runtime.run(&function);
 
 	drop(llvm_context);
 
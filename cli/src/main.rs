@@ -1,19 +1,16 @@
-mod eggscript;
-mod llvm;
-
 use std::process::exit;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use clap::Parser;
-
-#[cfg(test)]
-mod tests;
+use eggscript_ast::CompileOptions;
+use eggscript_cli::{debugger, eggscript, llvm};
 
 #[derive(Debug, Parser)]
 #[command(name = "eggscript")]
 #[command(bin_name = "eggscript")]
 enum Args {
 	Compile(CompileArgs),
+	Debug(DebugArgs),
 	Run(RunArgs),
 }
 
@@ -23,6 +20,24 @@ struct CompileArgs {
 	#[clap(flatten)]
 	contents_or_file_name: ContentsOrFileNameGroup,
 
+	/// Leave `assert` calls out of the compiled program
+	#[arg(long)]
+	strip_asserts: bool,
+
+	#[clap(flatten)]
+	interpreter_or_llvm: InterpreterOrLLVMGroup,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author, version, about, long_about = None)]
+struct DebugArgs {
+	#[clap(flatten)]
+	contents_or_file_name: ContentsOrFileNameGroup,
+
+	/// Leave `assert` calls out of the compiled program
+	#[arg(long)]
+	strip_asserts: bool,
+
 	#[clap(flatten)]
 	interpreter_or_llvm: InterpreterOrLLVMGroup,
 }
@@ -36,6 +51,10 @@ struct RunArgs {
 	#[arg(long)]
 	debug: bool,
 
+	/// Leave `assert` calls out of the compiled program
+	#[arg(long)]
+	strip_asserts: bool,
+
 	#[clap(flatten)]
 	interpreter_or_llvm: InterpreterOrLLVMGroup,
 }
@@ -69,6 +88,7 @@ fn execute() -> Result<()> {
 				file_name,
 			},
 			interpreter_or_llvm: InterpreterOrLLVMGroup { interpreter, .. },
+			strip_asserts,
 		}) => {
 			let (contents, file_name) = if let Some(contents) = contents {
 				(contents, "main.egg".to_string())
@@ -81,14 +101,48 @@ fn execute() -> Result<()> {
 				unreachable!();
 			};
 
+			let options = CompileOptions {
+				strip_asserts,
+				..CompileOptions::default()
+			};
+
 			if interpreter {
-				let result = eggscript::compile_eggscript_program(&contents, &file_name)?;
+				let result = eggscript::compile_eggscript_program(&contents, &file_name, options)?;
 				println!("{}", serde_json::to_string_pretty(&result)?);
 			} else {
-				let result = llvm::compile_llvm_program(&contents, &file_name)?;
+				let result = llvm::compile_llvm_program(&contents, &file_name, options)?;
 				println!("{}", serde_json::to_string_pretty(&result)?);
 			}
 		}
+		Args::Debug(DebugArgs {
+			contents_or_file_name: ContentsOrFileNameGroup {
+				contents,
+				file_name,
+			},
+			interpreter_or_llvm: InterpreterOrLLVMGroup { interpreter, .. },
+			strip_asserts,
+		}) => {
+			let (contents, file_name) = if let Some(contents) = contents {
+				(contents, "main.egg".to_string())
+			} else if let Some(file_name) = file_name {
+				(
+					std::fs::read_to_string(&file_name).context("Could not read file")?,
+					file_name.clone(),
+				)
+			} else {
+				unreachable!();
+			};
+
+			if !interpreter {
+				bail!("The debugger only supports the interpreter");
+			}
+
+			let options = CompileOptions {
+				strip_asserts,
+				..CompileOptions::default()
+			};
+			debugger::debug_eggscript_program(&contents, &file_name, options)?;
+		}
 		Args::Run(RunArgs {
 			contents_or_file_name: ContentsOrFileNameGroup {
 				contents,
@@ -96,6 +150,7 @@ fn execute() -> Result<()> {
 			},
 			debug,
 			interpreter_or_llvm: InterpreterOrLLVMGroup { interpreter, .. },
+			strip_asserts,
 		}) => {
 			let (contents, file_name) = if let Some(contents) = contents {
 				(contents, "main.egg".to_string())
@@ -108,10 +163,15 @@ fn execute() -> Result<()> {
 				unreachable!();
 			};
 
+			let options = CompileOptions {
+				strip_asserts,
+				..CompileOptions::default()
+			};
+
 			if interpreter {
-				eggscript::run_eggscript_program(&contents, &file_name, debug)?;
+				eggscript::run_eggscript_program(&contents, &file_name, debug, options)?;
 			} else {
-				llvm::run_llvm_program(&contents, &file_name, debug)?;
+				llvm::run_llvm_program(&contents, &file_name, debug, options)?;
 			}
 		}
 	}
@@ -0,0 +1,51 @@
+use anyhow::Result;
+use eggscript_ast::CompileOptions;
+use serial_test::serial;
+
+use super::{
+	assert_buffer, run_file_in_interpreter, run_file_in_interpreter_with_options, run_file_in_jit,
+	run_file_in_jit_with_options,
+};
+
+#[test]
+#[serial]
+fn asserts1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/asserts1.egg");
+	let file_name = "./src/tests/test_cases/asserts1.egg";
+
+	let expected = vec![
+		"1",
+		"./src/tests/test_cases/asserts1.egg:6:2: assertion `%x == 4` failed: x is four",
+		"./src/tests/test_cases/asserts1.egg:13:2: assertion `%x > 5` failed",
+		"3",
+	];
+
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
+	assert_buffer(expected.clone(), "interpreter");
+
+	run_file_in_jit(file_contents, file_name)?;
+	assert_buffer(expected, "jit");
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn strip_asserts1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/asserts1.egg");
+	let file_name = "./src/tests/test_cases/asserts1.egg";
+	let options = CompileOptions {
+		strip_asserts: true,
+		..CompileOptions::default()
+	};
+
+	let expected = vec!["1", "2", "3"];
+
+	run_file_in_interpreter_with_options(file_contents, file_name, 1_000_000, options.clone())?;
+	assert_buffer(expected.clone(), "interpreter");
+
+	run_file_in_jit_with_options(file_contents, file_name, options)?;
+	assert_buffer(expected, "jit");
+
+	Ok(())
+}
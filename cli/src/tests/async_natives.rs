@@ -0,0 +1,127 @@
+use anyhow::Result;
+use eggscript_interpreter::{
+	Function, Instruction, Interpreter, PendingToken, RunStatus, RuntimeErrorKind, Value,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::string;
+
+const LOAD: usize = 0;
+const RECORD: usize = 1;
+const ON_LOAD: usize = 2;
+
+/// Interpreter with an async `load` native, a `record` native that remembers its argument, and an
+/// `onLoad(%x)` function that records the result of `load` and then its argument
+fn create_interpreter_with_natives(
+	instructions: Vec<Instruction>,
+) -> (
+	Interpreter,
+	Rc<RefCell<Vec<PendingToken>>>,
+	Rc<RefCell<Vec<Value>>>,
+) {
+	let tokens = Rc::new(RefCell::new(vec![]));
+	let records = Rc::new(RefCell::new(vec![]));
+
+	let mut interpreter = Interpreter::new(instructions);
+
+	let load_tokens = tokens.clone();
+	interpreter.add_function(Function::new_native(
+		LOAD,
+		0,
+		Rc::new(move |interpreter: &mut Interpreter, _| {
+			load_tokens.borrow_mut().push(interpreter.pending_result());
+			Ok(Value::Null)
+		}),
+		"load",
+	));
+
+	let recorded = records.clone();
+	interpreter.add_function(Function::new_native(
+		RECORD,
+		1,
+		Rc::new(move |_, mut arguments| {
+			recorded.borrow_mut().push(arguments.remove(0));
+			Ok(Value::Null)
+		}),
+		"record",
+	));
+
+	interpreter.add_function(Function::new_eggscript_function(
+		ON_LOAD,
+		1,
+		vec![
+			Instruction::CallFunction(LOAD, 0),
+			Instruction::CallFunction(RECORD, 1),
+			Instruction::Pop,
+			Instruction::CopyPush(0),
+			Instruction::CallFunction(RECORD, 1),
+			Instruction::Pop,
+			Instruction::Return(0),
+		],
+		vec![None; 7],
+		"onLoad",
+	));
+
+	(interpreter, tokens, records)
+}
+
+#[test]
+fn async_native_in_program1() -> Result<()> {
+	let (mut interpreter, tokens, records) = create_interpreter_with_natives(vec![
+		Instruction::CallFunction(LOAD, 0),
+		Instruction::CallFunction(RECORD, 1),
+		Instruction::Pop,
+	]);
+
+	assert_eq!(interpreter.run()?, RunStatus::Waiting(1));
+	assert_eq!(interpreter.run()?, RunStatus::Waiting(1));
+	assert_eq!(*tokens.borrow(), vec![1]);
+
+	// other code keeps running while the program waits
+	interpreter.call(ON_LOAD, &[Value::Number(7.0)])?;
+	assert_eq!(*tokens.borrow(), vec![1, 2]);
+
+	interpreter.fulfill(1, string("program"))?;
+	assert_eq!(interpreter.run()?, RunStatus::Finished);
+	assert_eq!(*records.borrow(), vec![string("program")]);
+
+	Ok(())
+}
+
+#[test]
+fn async_native_in_call1() -> Result<()> {
+	let (mut interpreter, tokens, records) = create_interpreter_with_natives(vec![]);
+	assert_eq!(interpreter.run()?, RunStatus::Finished);
+
+	assert_eq!(
+		interpreter.call("onLoad", &[Value::Number(7.0)])?,
+		Value::Null
+	);
+	assert_eq!(
+		interpreter.call("onLoad", &[Value::Number(8.0)])?,
+		Value::Null
+	);
+	assert_eq!(*tokens.borrow(), vec![1, 2]);
+	assert!(records.borrow().is_empty());
+
+	// coroutines continue with the stack they had, in the order their tokens are fulfilled in
+	interpreter.fulfill(2, string("second"))?;
+	interpreter.fulfill(1, string("first"))?;
+	assert_eq!(
+		*records.borrow(),
+		vec![
+			string("second"),
+			Value::Number(8.0),
+			string("first"),
+			Value::Number(7.0),
+		]
+	);
+
+	let error = interpreter
+		.fulfill(1, Value::Null)
+		.expect_err("Expected the token to be used up");
+	assert_eq!(error.kind, RuntimeErrorKind::UnknownPendingToken(1));
+
+	Ok(())
+}
@@ -0,0 +1,28 @@
+use serial_test::serial;
+
+use super::run_file_in_interpreter;
+
+#[test]
+#[serial]
+fn backtrace1() {
+	let file_contents = include_str!("./test_cases/backtrace1.egg");
+	let file_name = "./src/tests/test_cases/backtrace1.egg";
+
+	let error = run_file_in_interpreter(file_contents, file_name, 1_000_000)
+		.expect_err("expected uncaught exception");
+
+	let expected = [
+		"Uncaught exception: too deep in function 'fib' at instruction 4",
+		"    at fib (./src/tests/test_cases/backtrace1.egg:3:3)",
+		"        throw \"too deep\";",
+		"    at fib (./src/tests/test_cases/backtrace1.egg:6:9)",
+		"        return fib(%n - 1) + fib(%n - 2);",
+		"    at fib (./src/tests/test_cases/backtrace1.egg:6:9)",
+		"        return fib(%n - 1) + fib(%n - 2);",
+		"    at <global scope> (./src/tests/test_cases/backtrace1.egg:9:13)",
+		"        printNumber(fib(3));",
+	]
+	.join("\n");
+
+	assert_eq!(error.to_string(), expected);
+}
@@ -0,0 +1,34 @@
+use anyhow::Result;
+use serial_test::serial;
+
+use super::{assert_buffer, run_file_in_interpreter, run_file_in_jit};
+
+#[test]
+#[serial]
+fn constants1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/constants1.egg");
+	let file_name = "./src/tests/test_cases/constants1.egg";
+
+	let expected = vec!["9", "-4", "9", "egg", "1", "360", "5"];
+
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
+	assert_buffer(expected.clone(), "interpreter");
+
+	run_file_in_jit(file_contents, file_name)?;
+	assert_buffer(expected, "jit");
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn constant_reassignment1() {
+	let file_contents = include_str!("./test_cases/constant_reassignment1.egg");
+	let file_name = "./src/tests/test_cases/constant_reassignment1.egg";
+
+	let result = run_file_in_interpreter(file_contents, file_name, 1_000_000);
+	assert!(
+		result.is_err_and(|error| error.to_string() == "Cannot assign to constant 'WIDTH'"),
+		"expected constant reassignment error"
+	);
+}
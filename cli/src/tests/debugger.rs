@@ -0,0 +1,115 @@
+use anyhow::Result;
+use eggscript_ast::{parse_string_with_options, CompileOptions};
+use eggscript_interpreter::get_test_native_function_mapping_for_interpreter;
+use serial_test::serial;
+
+use super::assert_buffer;
+use crate::debugger::Debugger;
+use crate::eggscript;
+
+#[test]
+#[serial]
+fn debugger1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/debugger1.egg");
+	let file_name = "./src/tests/test_cases/debugger1.egg";
+
+	let program = parse_string_with_options(file_contents, file_name, CompileOptions::default())?;
+	let (interpreter, local_slots) = eggscript::create_interpreter(
+		program.clone(),
+		get_test_native_function_mapping_for_interpreter(),
+		false,
+	)?;
+
+	let commands = [
+		"break 2",
+		"continue",
+		"print a",
+		"backtrace",
+		"out",
+		"next",
+		"print %y",
+		"step",
+		"step",
+		"finish",
+		"delete 2",
+		"continue",
+		"continue",
+	]
+	.join("\n");
+
+	let mut output = vec![];
+	let mut debugger = Debugger::new(program, interpreter, local_slots);
+	debugger.run(commands.as_bytes(), &mut output)?;
+
+	let expected = [
+		"Type 'help' for a list of commands",
+		"(egg) Breakpoint set at ./src/tests/test_cases/debugger1.egg:2",
+		"(egg) Breakpoint in add at ./src/tests/test_cases/debugger1.egg:2",
+		"   2 | %sum = %a + %b;",
+		"(egg) %a = 1",
+		"(egg) Call stack:",
+		"    at add (./src/tests/test_cases/debugger1.egg:2:9)",
+		"        %sum = %a + %b;",
+		"    at <global scope> (./src/tests/test_cases/debugger1.egg:7:6)",
+		"        %y = add(%x, 2);",
+		"(egg) Stopped in <global scope> at ./src/tests/test_cases/debugger1.egg:7",
+		"   7 | %y = add(%x, 2);",
+		"(egg) Stopped in <global scope> at ./src/tests/test_cases/debugger1.egg:8",
+		"   8 | %z = add(%y, 3);",
+		"(egg) %y = 3",
+		"(egg) Breakpoint in add at ./src/tests/test_cases/debugger1.egg:2",
+		"   2 | %sum = %a + %b;",
+		"(egg) Stopped in add at ./src/tests/test_cases/debugger1.egg:3",
+		"   3 | return %sum;",
+		"(egg) Stopped in <global scope> at ./src/tests/test_cases/debugger1.egg:8",
+		"   8 | %z = add(%y, 3);",
+		"(egg) Breakpoint removed from ./src/tests/test_cases/debugger1.egg:2",
+		"(egg) The program has finished",
+		"(egg) The program has finished",
+		"(egg) ",
+		"",
+	]
+	.join("\n");
+
+	assert_eq!(String::from_utf8(output)?, expected);
+	assert_buffer(vec!["6"], "debugger");
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn debugger2() -> Result<()> {
+	let file_contents = include_str!("./test_cases/debugger2.egg");
+	let file_name = "./src/tests/test_cases/debugger2.egg";
+
+	let program = parse_string_with_options(file_contents, file_name, CompileOptions::default())?;
+	let (interpreter, local_slots) = eggscript::create_interpreter(
+		program.clone(),
+		get_test_native_function_mapping_for_interpreter(),
+		false,
+	)?;
+
+	// the program is on line 1 before it runs, and continuing from there leaves the line
+	let commands = ["break 1", "continue", "continue"].join("\n");
+
+	let mut output = vec![];
+	let mut debugger = Debugger::new(program, interpreter, local_slots);
+	debugger.run(commands.as_bytes(), &mut output)?;
+
+	let expected = [
+		"Type 'help' for a list of commands",
+		"(egg) Breakpoint set at ./src/tests/test_cases/debugger2.egg:1",
+		"(egg) Breakpoint in <global scope> at ./src/tests/test_cases/debugger2.egg:1",
+		"   1 | printNumber(1);",
+		"(egg) The program has finished",
+		"(egg) ",
+		"",
+	]
+	.join("\n");
+
+	assert_eq!(String::from_utf8(output)?, expected);
+	assert_buffer(vec!["1", "2"], "debugger");
+
+	Ok(())
+}
@@ -0,0 +1,36 @@
+use anyhow::Result;
+use serial_test::serial;
+
+use super::{assert_buffer, run_file_in_interpreter, run_file_in_jit};
+
+#[test]
+#[serial]
+fn enums1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/enums1.egg");
+	let file_name = "./src/tests/test_cases/enums1.egg";
+
+	let expected = vec!["1", "2", "1", "3", "20", "30", "50"];
+
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
+	assert_buffer(expected.clone(), "interpreter");
+
+	run_file_in_jit(file_contents, file_name)?;
+	assert_buffer(expected, "jit");
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn enum_comparison1() {
+	let file_contents = include_str!("./test_cases/enum_comparison1.egg");
+	let file_name = "./src/tests/test_cases/enum_comparison1.egg";
+
+	let result = run_file_in_interpreter(file_contents, file_name, 1_000_000);
+	assert!(
+		result.is_err_and(
+			|error| error.to_string() == "Operator 'Plus' cannot be used on enum values"
+		),
+		"expected enum operator error"
+	);
+}
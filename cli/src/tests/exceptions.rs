@@ -0,0 +1,32 @@
+use anyhow::Result;
+use serial_test::serial;
+
+use super::{assert_buffer, run_file_in_interpreter, run_file_in_jit};
+
+#[test]
+#[serial]
+fn exceptions1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/exceptions1.egg");
+	let file_name = "./src/tests/test_cases/exceptions1.egg";
+
+	let expected = vec![
+		"1",
+		"first",
+		"deep",
+		"inner",
+		"rethrown",
+		"12",
+		"Could not parse 'twelve' as a number",
+		"5",
+		"after return",
+		"3",
+	];
+
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
+	assert_buffer(expected.clone(), "interpreter");
+
+	run_file_in_jit(file_contents, file_name)?;
+	assert_buffer(expected, "jit");
+
+	Ok(())
+}
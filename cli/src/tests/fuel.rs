@@ -0,0 +1,39 @@
+use anyhow::Result;
+use eggscript_ast::CompileOptions;
+use eggscript_interpreter::{Instruction, Interpreter, RunStatus};
+use serial_test::serial;
+
+use super::{assert_buffer, create_interpreter};
+
+#[test]
+fn out_of_fuel1() {
+	let mut interpreter = Interpreter::new(vec![Instruction::Noop; 5]);
+
+	assert_eq!(interpreter.run_with_fuel(2), Ok(RunStatus::OutOfFuel));
+	assert_eq!(interpreter.run_with_fuel(2), Ok(RunStatus::OutOfFuel));
+	assert_eq!(interpreter.run_with_fuel(2), Ok(RunStatus::Finished));
+	assert_eq!(interpreter.run_with_fuel(2), Ok(RunStatus::Finished));
+}
+
+#[test]
+#[serial]
+fn resume_after_fuel1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/fuel1.egg");
+	let file_name = "./src/tests/test_cases/fuel1.egg";
+
+	let mut interpreter = create_interpreter(file_contents, file_name, CompileOptions::default())?;
+
+	let mut runs = 1;
+	while interpreter.run_with_fuel(10)? == RunStatus::OutOfFuel {
+		runs += 1;
+	}
+
+	assert!(
+		runs > 10,
+		"expected the loop to need many runs, took {}",
+		runs
+	);
+	assert_buffer(vec!["4950"], "interpreter");
+
+	Ok(())
+}
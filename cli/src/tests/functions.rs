@@ -0,0 +1,80 @@
+use anyhow::Result;
+use serial_test::serial;
+
+use super::{assert_buffer, run_file_in_interpreter, run_file_in_jit};
+
+#[test]
+#[serial]
+fn default_arguments1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/default_arguments1.egg");
+	let file_name = "./test_cases/default_arguments1.egg";
+
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
+	assert_buffer(vec!["20.5", "30.5", "31", "19"], "interpreter");
+
+	run_file_in_jit(file_contents, file_name)?;
+	assert_buffer(vec!["20.5", "30.5", "31", "19"], "jit");
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn default_arguments2() {
+	let file_contents = include_str!("./test_cases/default_arguments2.egg");
+	let file_name = "./test_cases/default_arguments2.egg";
+
+	let error = run_file_in_interpreter(file_contents, file_name, 1_000_000)
+		.expect_err("Expected a compile error");
+	assert!(
+		format!("{:#}", error)
+			.contains("Default value of argument '%b' of function 'offset' is not constant"),
+		"{:#}",
+		error
+	);
+}
+
+#[test]
+#[serial]
+fn default_arguments3() -> Result<()> {
+	let file_contents = include_str!("./test_cases/default_arguments3.egg");
+	let file_name = "./test_cases/default_arguments3.egg";
+
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
+	assert_buffer(vec!["12"], "interpreter");
+
+	run_file_in_jit(file_contents, file_name)?;
+	assert_buffer(vec!["12"], "jit");
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn variadic1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/variadic1.egg");
+	let file_name = "./test_cases/variadic1.egg";
+
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
+	assert_buffer(vec!["", "1", "1 2 3", "5 25 5.5 7"], "interpreter");
+
+	run_file_in_jit(file_contents, file_name)?;
+	assert_buffer(vec!["", "1", "1 2 3", "5 25 5.5 7"], "jit");
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn tuple_return1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/tuple_return1.egg");
+	let file_name = "./test_cases/tuple_return1.egg";
+
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
+	assert_buffer(vec!["3", "7", "3", "1"], "interpreter");
+
+	run_file_in_jit(file_contents, file_name)?;
+	assert_buffer(vec!["3", "7", "3", "1"], "jit");
+
+	Ok(())
+}
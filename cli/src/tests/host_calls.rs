@@ -0,0 +1,114 @@
+use anyhow::{anyhow, Result};
+use eggscript_ast::CompileOptions;
+use eggscript_interpreter::{RunStatus, Value};
+use serial_test::serial;
+
+use super::{assert_buffer, create_interpreter, run_file_in_jit_with_runtime, string};
+
+/// Calls the functions of host_calls1.egg the same way on both backends
+fn check_host_calls(
+	mut call: impl FnMut(&str, &[Value]) -> Result<Value>,
+	message: &str,
+) -> Result<()> {
+	assert_eq!(call("onTick", &[Value::Number(3.0)])?, Value::Number(6.0));
+	assert_eq!(call("shout", &[string("hello")])?, string("hello"));
+	assert_buffer(vec!["loaded", "3", "hello"], message);
+
+	let error = call("onTick", &[string("3")]).expect_err("Expected a type error");
+	assert!(
+		error
+			.to_string()
+			.contains("Argument 1 of 'onTick' is a number, found a string value"),
+		"{} ({})",
+		error,
+		message
+	);
+
+	let error = call("onTick", &[]).expect_err("Expected an argument count error");
+	assert!(
+		error.to_string().contains("takes 1 arguments, got 0"),
+		"{} ({})",
+		error,
+		message
+	);
+
+	let error = call("onUpdate", &[]).expect_err("Expected an unknown function error");
+	assert!(
+		error.to_string().contains("Unknown function"),
+		"{} ({})",
+		error,
+		message
+	);
+
+	let error = call("fail", &[string("boom")]).expect_err("Expected an uncaught exception");
+	assert!(
+		error.to_string().contains("Uncaught exception: boom"),
+		"{} ({})",
+		error,
+		message
+	);
+
+	// calls go through packages like calls made by scripts
+	assert_eq!(
+		call("activatePackage", &[string("Loud")])?,
+		Value::Number(1.0)
+	);
+	assert_eq!(call("onTick", &[Value::Number(2.0)])?, Value::Number(4.0));
+	assert_buffer(vec!["20", "2"], message);
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn host_calls1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/host_calls1.egg");
+	let file_name = "./src/tests/test_cases/host_calls1.egg";
+
+	let mut interpreter = create_interpreter(file_contents, file_name, CompileOptions::default())?;
+
+	assert_eq!(interpreter.run()?, RunStatus::Finished);
+	check_host_calls(
+		|name, arguments| {
+			interpreter
+				.call(name, arguments)
+				.map_err(|error| anyhow!(error))
+		},
+		"interpreter",
+	)?;
+
+	// calls by handle skip looking up the name
+	let handle = interpreter
+		.function_handle("shout")
+		.expect("Could not find function");
+	assert_eq!(interpreter.call(handle, &[string("bye")])?, string("bye"));
+	assert_buffer(vec!["bye"], "interpreter");
+
+	run_file_in_jit_with_runtime(
+		file_contents,
+		file_name,
+		CompileOptions::default(),
+		|jit_runtime| {
+			check_host_calls(
+				|name, arguments| jit_runtime.call_function(name, arguments),
+				"jit",
+			)?;
+
+			let error = jit_runtime
+				.call_function("shout", &[string("hel\0lo")])
+				.expect_err("Expected a NUL byte error");
+			assert!(
+				error
+					.to_string()
+					.contains("Argument 1 of 'shout' contains a NUL byte"),
+				"{}",
+				error
+			);
+			assert_buffer(vec![], "jit");
+
+			Ok(())
+		},
+	)?;
+
+	Ok(())
+}
@@ -0,0 +1,110 @@
+use anyhow::{anyhow, Result};
+use eggscript_ast::CompileOptions;
+use eggscript_interpreter::{InterruptHandle, RunStatus, RuntimeErrorKind, Value};
+use serial_test::serial;
+use std::cell::RefCell;
+use std::thread;
+use std::time::Duration;
+
+use super::{assert_buffer, create_interpreter, run_file_in_jit_with_runtime};
+
+/// Interrupts the script from another thread while `call` runs it
+fn call_interrupted(
+	handle: InterruptHandle,
+	call: impl FnOnce() -> Result<Value>,
+) -> anyhow::Error {
+	let interrupter = thread::spawn(move || {
+		thread::sleep(Duration::from_millis(20));
+		handle.interrupt();
+	});
+
+	let error = call().expect_err("Expected the call to be interrupted");
+	interrupter
+		.join()
+		.expect("Could not join interrupting thread");
+
+	return error;
+}
+
+/// Interrupts the runaway functions of interrupt1.egg the same way on both backends
+fn check_interrupts(
+	handle: InterruptHandle,
+	call: impl Fn(&str, &[Value]) -> Result<Value>,
+	message: &str,
+) -> Result<()> {
+	// scripts cannot catch interrupts, so `spin` never prints
+	let error = call_interrupted(handle.clone(), || call("spin", &[]));
+	assert!(
+		error.to_string().contains("Interrupted"),
+		"{} ({})",
+		error,
+		message
+	);
+	assert_buffer(vec![], message);
+
+	let error = call_interrupted(handle.clone(), || call("fib", &[Value::Number(100.0)]));
+	assert!(
+		error.to_string().contains("Interrupted"),
+		"{} ({})",
+		error,
+		message
+	);
+
+	// the interrupt is used up, and the interrupted calls unwound completely
+	assert!(!handle.is_interrupted());
+	assert_eq!(
+		call("countDown", &[Value::Number(900.0)])?,
+		Value::Number(900.0)
+	);
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn interrupt1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/interrupt1.egg");
+	let file_name = "./src/tests/test_cases/interrupt1.egg";
+
+	let mut interpreter = create_interpreter(file_contents, file_name, CompileOptions::default())?;
+
+	// interrupted programs stop before the instruction, and continue from it
+	let handle = interpreter.interrupt_handle();
+	handle.interrupt();
+	let error = interpreter
+		.run()
+		.expect_err("Expected the program to be interrupted");
+	assert_eq!(error.kind, RuntimeErrorKind::Interrupted);
+	assert_buffer(vec![], "interpreter");
+
+	assert_eq!(interpreter.run()?, RunStatus::Finished);
+	assert_buffer(vec!["1"], "interpreter");
+
+	let interpreter = RefCell::new(interpreter);
+	check_interrupts(
+		handle,
+		|name, arguments| {
+			interpreter
+				.borrow_mut()
+				.call(name, arguments)
+				.map_err(|error| anyhow!(error))
+		},
+		"interpreter",
+	)?;
+
+	run_file_in_jit_with_runtime(
+		file_contents,
+		file_name,
+		CompileOptions::default(),
+		|jit_runtime| {
+			assert_buffer(vec!["1"], "jit");
+			check_interrupts(
+				jit_runtime.interrupt_handle(),
+				|name, arguments| jit_runtime.call_function(name, arguments),
+				"jit",
+			)
+		},
+	)?;
+
+	Ok(())
+}
@@ -9,7 +9,7 @@ fn for_loop1() -> Result<()> {
 	let file_contents = include_str!("./test_cases/for_loop1.egg");
 	let file_name = "./test_cases/for_loop1.egg";
 
-	run_file_in_interpreter(file_contents, file_name, 1000)?;
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
 	assert_buffer(vec!["1024"], "interpreter");
 
 	run_file_in_jit(file_contents, file_name)?;
@@ -24,7 +24,7 @@ fn while_loop1() -> Result<()> {
 	let file_contents = include_str!("./test_cases/while_loop1.egg");
 	let file_name = "./test_cases/while_loop1.egg";
 
-	run_file_in_interpreter(file_contents, file_name, 1000)?;
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
 	assert_buffer(vec!["1024"], "interpreter");
 
 	run_file_in_jit(file_contents, file_name)?;
@@ -32,3 +32,46 @@ fn while_loop1() -> Result<()> {
 
 	Ok(())
 }
+
+#[test]
+#[serial]
+fn foreach1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/foreach1.egg");
+	let file_name = "./src/tests/test_cases/foreach1.egg";
+
+	let expected = vec![
+		"10",
+		"1",
+		"3",
+		"4",
+		"0",
+		"2",
+		"3",
+		"5",
+		"6",
+		"10",
+		"20",
+		"after loops",
+	];
+
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
+	assert_buffer(expected.clone(), "interpreter");
+
+	run_file_in_jit(file_contents, file_name)?;
+	assert_buffer(expected, "jit");
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn break_outside_loop1() {
+	let file_contents = include_str!("./test_cases/break_outside_loop1.egg");
+	let file_name = "./src/tests/test_cases/break_outside_loop1.egg";
+
+	let result = run_file_in_interpreter(file_contents, file_name, 1_000_000);
+	assert!(
+		result.is_err_and(|error| error.to_string() == "'break' can only be used inside of a loop"),
+		"expected break outside of loop error"
+	);
+}
@@ -0,0 +1,80 @@
+use anyhow::Result;
+use eggscript_ast::CompileOptions;
+use eggscript_interpreter::runtime::map;
+use eggscript_interpreter::Value;
+use serial_test::serial;
+
+use super::{
+	assert_buffer, run_file_in_interpreter, run_file_in_jit, run_file_in_jit_with_runtime,
+};
+
+#[test]
+#[serial]
+fn maps1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/maps1.egg");
+	let file_name = "./src/tests/test_cases/maps1.egg";
+
+	let expected = vec![
+		"3",
+		"13",
+		"3",
+		"27",
+		"1",
+		"1",
+		"0",
+		"0",
+		"apple",
+		"plum",
+		"fig",
+		"one",
+		"2",
+		"Map has no key 'pear'",
+	];
+
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
+	assert_buffer(expected.clone(), "interpreter");
+
+	run_file_in_jit(file_contents, file_name)?;
+	assert_buffer(expected, "jit");
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn map_type_mismatch1() {
+	let file_contents = include_str!("./test_cases/map_type_mismatch1.egg");
+	let file_name = "./src/tests/test_cases/map_type_mismatch1.egg";
+
+	let result = run_file_in_interpreter(file_contents, file_name, 1_000_000);
+	assert!(
+		result.is_err_and(|error| error.to_string()
+			== "Map key '1' does not have the same type as the keys of the map"),
+		"expected map key type error"
+	);
+}
+
+#[test]
+#[serial]
+fn map_calls1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/map_calls1.egg");
+	let file_name = "./src/tests/test_cases/map_calls1.egg";
+
+	// maps JIT code creates are freed once the host's call into it returns
+	run_file_in_jit_with_runtime(
+		file_contents,
+		file_name,
+		CompileOptions::default(),
+		|jit_runtime| {
+			for _ in 0..100 {
+				assert_eq!(
+					jit_runtime.call_function("fill", &[Value::Number(10.0)])?,
+					Value::Number(10.0)
+				);
+				assert_eq!(map::jit_memory_usage(), 0);
+			}
+
+			Ok(())
+		},
+	)
+}
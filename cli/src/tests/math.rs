@@ -9,7 +9,7 @@ fn math1() -> Result<()> {
 	let file_contents = include_str!("./test_cases/math1.egg");
 	let file_name = "./test_cases/math1.egg";
 
-	run_file_in_interpreter(file_contents, file_name, 1000)?;
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
 	assert_buffer(vec!["50159"], "interpreter");
 
 	run_file_in_jit(file_contents, file_name)?;
@@ -24,7 +24,7 @@ fn math2() -> Result<()> {
 	let file_contents = include_str!("./test_cases/math2.egg");
 	let file_name = "./test_cases/math2.egg";
 
-	run_file_in_interpreter(file_contents, file_name, 1000)?;
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
 	assert_buffer(
 		vec!["6", "-3.5", "-3.5", "18.7", "-15", "-10"],
 		"interpreter",
@@ -42,7 +42,7 @@ fn math3() -> Result<()> {
 	let file_contents = include_str!("./test_cases/math3.egg");
 	let file_name = "./test_cases/math3.egg";
 
-	run_file_in_interpreter(file_contents, file_name, 1000)?;
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
 	assert_buffer(vec!["2", "11", "9", "80", "1", "-6", "0", "1", "0"], "interpreter");
 
 	run_file_in_jit(file_contents, file_name)?;
@@ -9,7 +9,7 @@ fn conditionals() -> Result<()> {
 	let file_contents = include_str!("./test_cases/conditionals.egg");
 	let file_name = "./test_cases/conditionals.egg";
 
-	run_file_in_interpreter(file_contents, file_name, 1000)?;
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
 	assert_buffer(
 		vec![
 			"0", "1", "1", "0", "1", "0", "0", "0", "1", "1", "0", "1", "2", "3", "2", "4", "5",
@@ -34,7 +34,7 @@ fn logic() -> Result<()> {
 	let file_contents = include_str!("./test_cases/logic.egg");
 	let file_name = "./test_cases/logic.egg";
 
-	run_file_in_interpreter(file_contents, file_name, 1000)?;
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
 	assert_buffer(
 		vec!["12", "4", "16", "5", "1", "1", "3", "5"],
 		"interpreter",
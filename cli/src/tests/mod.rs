@@ -1,9 +1,36 @@
+mod any;
+mod asserts;
+mod async_natives;
+mod backtraces;
+mod constants;
+mod debugger;
+mod enums;
+mod exceptions;
+mod fuel;
+mod functions;
+mod host_calls;
+mod interrupts;
 mod loops;
+mod maps;
 mod math;
 mod misc;
+mod modules;
+mod nullables;
+mod packages;
+mod pausing;
 mod recursion;
+mod runtime_errors;
+mod sandbox;
+mod scheduler;
 mod setup;
+mod snapshots;
+mod stack_overflow;
 
 pub use setup::assert_buffer;
+pub use setup::create_interpreter;
 pub use setup::run_file_in_interpreter;
+pub use setup::run_file_in_interpreter_with_options;
 pub use setup::run_file_in_jit;
+pub use setup::run_file_in_jit_with_options;
+pub use setup::run_file_in_jit_with_runtime;
+pub use setup::string;
@@ -0,0 +1,63 @@
+use anyhow::Result;
+use serial_test::serial;
+
+use super::{assert_buffer, run_file_in_interpreter, run_file_in_jit};
+
+#[test]
+#[serial]
+fn imports1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/imports1.egg");
+	let file_name = "./src/tests/test_cases/imports1.egg";
+
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
+	assert_buffer(vec!["12", "10", "10"], "interpreter");
+
+	run_file_in_jit(file_contents, file_name)?;
+	assert_buffer(vec!["12", "10", "10"], "jit");
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn import_cycle1() {
+	let file_contents = include_str!("./test_cases/import_cycle1.egg");
+	let file_name = "./src/tests/test_cases/import_cycle1.egg";
+
+	let result = run_file_in_interpreter(file_contents, file_name, 1_000_000);
+	assert!(
+		result.is_err_and(|error| error.to_string().starts_with("Import cycle detected")),
+		"expected import cycle error"
+	);
+}
+
+#[test]
+#[serial]
+fn exec1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/exec1.egg");
+	let file_name = "./src/tests/test_cases/exec1.egg";
+
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
+	assert_buffer(vec!["1", "12", "1", "15", "1", "0"], "interpreter");
+
+	run_file_in_jit(file_contents, file_name)?;
+	assert_buffer(vec!["1", "12", "1", "15", "1", "0"], "jit");
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn exec2() -> Result<()> {
+	let file_contents = include_str!("./test_cases/exec2.egg");
+	let file_name = "./src/tests/test_cases/exec2.egg";
+
+	// files without global code load like any other
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
+	assert_buffer(vec!["1"], "interpreter");
+
+	run_file_in_jit(file_contents, file_name)?;
+	assert_buffer(vec!["1"], "jit");
+
+	Ok(())
+}
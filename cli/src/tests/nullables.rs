@@ -0,0 +1,35 @@
+use anyhow::Result;
+use serial_test::serial;
+
+use super::{assert_buffer, run_file_in_interpreter, run_file_in_jit};
+
+#[test]
+#[serial]
+fn nullables1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/nullables1.egg");
+	let file_name = "./src/tests/test_cases/nullables1.egg";
+
+	let expected = vec!["8", "17", "-1", "4", "1", "0", "1", "nobody", "egg"];
+
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
+	assert_buffer(expected.clone(), "interpreter");
+
+	run_file_in_jit(file_contents, file_name)?;
+	assert_buffer(expected, "jit");
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn nullable_use1() {
+	let file_contents = include_str!("./test_cases/nullable_use1.egg");
+	let file_name = "./src/tests/test_cases/nullable_use1.egg";
+
+	let result = run_file_in_interpreter(file_contents, file_name, 1_000_000);
+	assert!(
+		result.is_err_and(|error| error.to_string()
+			== "'%value' may be null, compare it to null before using its value"),
+		"expected nullable use error"
+	);
+}
@@ -0,0 +1,72 @@
+use anyhow::Result;
+use serial_test::serial;
+
+use super::{assert_buffer, run_file_in_interpreter, run_file_in_jit};
+
+#[test]
+#[serial]
+fn packages1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/packages1.egg");
+	let file_name = "./src/tests/test_cases/packages1.egg";
+
+	let expected = vec!["1", "10", "2", "100", "20", "3", "100", "2", "1", "1", "0"];
+
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
+	assert_buffer(expected.clone(), "interpreter");
+
+	run_file_in_jit(file_contents, file_name)?;
+	assert_buffer(expected, "jit");
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn package_unknown_function1() {
+	let file_contents = include_str!("./test_cases/package_unknown_function1.egg");
+	let file_name = "./src/tests/test_cases/package_unknown_function1.egg";
+
+	let result = run_file_in_interpreter(file_contents, file_name, 1_000_000);
+	assert!(
+		result.is_err_and(|error| error
+			.to_string()
+			.starts_with("Package 'Broken' overrides function 'missing'")),
+		"expected unknown function error"
+	);
+}
+
+#[test]
+#[serial]
+fn package_exec1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/package_exec1.egg");
+	let file_name = "./src/tests/test_cases/package_exec1.egg";
+
+	// packages loaded with `exec` override functions the main file calls
+	let expected = vec!["1", "1", "20", "2", "30", "3", "30", "3", "4"];
+
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
+	assert_buffer(expected.clone(), "interpreter");
+
+	run_file_in_jit(file_contents, file_name)?;
+	assert_buffer(expected, "jit");
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn package_exec_errors1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/package_exec_errors1.egg");
+	let file_name = "./src/tests/test_cases/package_exec_errors1.egg";
+
+	// files with packages that cannot override their functions fail to load
+	let expected = vec!["0", "0", "1"];
+
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
+	assert_buffer(expected.clone(), "interpreter");
+
+	run_file_in_jit(file_contents, file_name)?;
+	assert_buffer(expected, "jit");
+
+	Ok(())
+}
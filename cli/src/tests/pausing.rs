@@ -0,0 +1,70 @@
+use anyhow::Result;
+use eggscript_ast::CompileOptions;
+use eggscript_interpreter::{Function, Instruction, Interpreter, RunStatus, Value};
+use serial_test::serial;
+use std::rc::Rc;
+
+use super::{assert_buffer, create_interpreter, run_file_in_jit};
+
+#[test]
+fn suspend1() -> Result<()> {
+	let mut interpreter = Interpreter::new(vec![
+		Instruction::CallFunction(0, 0),
+		Instruction::Pop,
+		Instruction::Noop,
+	]);
+
+	interpreter.add_function(Function::new_native(
+		0,
+		0,
+		Rc::new(|interpreter: &mut Interpreter, _| {
+			interpreter.suspend();
+			Ok(Value::Null)
+		}),
+		"wait",
+	));
+
+	assert_eq!(interpreter.run()?, RunStatus::Suspended);
+	assert_eq!(interpreter.resume()?, RunStatus::Finished);
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn yield1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/yield1.egg");
+	let file_name = "./src/tests/test_cases/yield1.egg";
+
+	let mut interpreter = create_interpreter(file_contents, file_name, CompileOptions::default())?;
+
+	assert_eq!(interpreter.run()?, RunStatus::Yielded(Value::Number(0.0)));
+	assert_buffer(vec!["start"], "interpreter");
+
+	assert_eq!(
+		interpreter.resume()?,
+		RunStatus::Yielded(Value::Number(10.0))
+	);
+	assert_eq!(
+		interpreter.resume()?,
+		RunStatus::Yielded(Value::Number(20.0))
+	);
+	assert_eq!(
+		interpreter.resume()?,
+		RunStatus::Yielded(Value::String(Rc::new("done".into())))
+	);
+	assert_eq!(interpreter.resume()?, RunStatus::Yielded(Value::Null));
+	assert_buffer(vec![], "interpreter");
+
+	assert_eq!(interpreter.resume()?, RunStatus::Finished);
+	assert_buffer(vec!["end"], "interpreter");
+
+	let result = run_file_in_jit(file_contents, file_name);
+	assert!(
+		result
+			.is_err_and(|error| error.to_string() == "yield is only supported by the interpreter"),
+		"expected yield to be rejected by the jit"
+	);
+
+	Ok(())
+}
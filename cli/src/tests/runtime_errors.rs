@@ -0,0 +1,73 @@
+use eggscript_interpreter::{
+	Instruction, Interpreter, MapOperation, NumberMathOperation, RuntimeErrorKind, Value,
+};
+use std::rc::Rc;
+
+#[test]
+fn invalid_instruction1() {
+	let mut interpreter = Interpreter::new(vec![Instruction::Noop, Instruction::Invalid]);
+
+	let error = interpreter
+		.run()
+		.expect_err("expected invalid instruction error");
+	assert_eq!(error.kind, RuntimeErrorKind::InvalidInstruction);
+	assert_eq!(error.function, None);
+	assert_eq!(error.instruction_index, 1);
+}
+
+#[test]
+fn return_without_frame1() {
+	let mut interpreter = Interpreter::new(vec![Instruction::Return(0)]);
+
+	let error = interpreter.run().expect_err("expected missing frame error");
+	assert_eq!(error.kind, RuntimeErrorKind::MissingFrame);
+}
+
+#[test]
+fn invalid_jump1() {
+	let mut interpreter = Interpreter::new(vec![Instruction::Jump(-5)]);
+
+	let error = interpreter.run().expect_err("expected invalid jump error");
+	assert_eq!(error.kind, RuntimeErrorKind::InvalidJump(-5));
+}
+
+#[test]
+fn type_mismatch1() {
+	let mut interpreter = Interpreter::new(vec![
+		Instruction::Push(Value::String(Rc::new("egg".into()))),
+		Instruction::Push(Value::Number(1.0)),
+		Instruction::NumberMath(NumberMathOperation::Plus, -1, -1),
+	]);
+
+	let error = interpreter.run().expect_err("expected type mismatch error");
+	assert_eq!(
+		error.kind,
+		RuntimeErrorKind::TypeMismatch {
+			expected: "number",
+			found: "string",
+		}
+	);
+}
+
+#[test]
+fn stack_underflow1() {
+	let mut interpreter = Interpreter::new(vec![Instruction::Pop, Instruction::Pop]);
+
+	let error = interpreter
+		.run()
+		.expect_err("expected stack underflow error");
+	assert_eq!(error.kind, RuntimeErrorKind::StackUnderflow);
+}
+
+#[test]
+fn odd_map_operands1() {
+	let mut interpreter = Interpreter::new(vec![
+		Instruction::Push(Value::String(Rc::new("key".into()))),
+		Instruction::MapOperation(MapOperation::New, vec![-1]),
+	]);
+
+	let error = interpreter
+		.run()
+		.expect_err("expected odd map operands error");
+	assert_eq!(error.kind, RuntimeErrorKind::OddMapOperands(1));
+}
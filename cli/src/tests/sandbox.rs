@@ -0,0 +1,328 @@
+use anyhow::{anyhow, Result};
+use eggscript_ast::CompileOptions;
+use eggscript_interpreter::{RunStatus, SandboxConfig, Value};
+use serial_test::serial;
+use std::collections::HashSet;
+use std::time::Duration;
+
+use super::{
+	assert_buffer, create_interpreter, run_file_in_jit_with_options, run_file_in_jit_with_runtime,
+};
+
+/// Calls the function of sandbox1.egg in a new sandbox on both backends, and returns the errors
+fn call_in_sandbox(config: SandboxConfig, function: &str, arguments: &[Value]) -> Vec<String> {
+	let file_contents = include_str!("./test_cases/sandbox1.egg");
+	let file_name = "./src/tests/test_cases/sandbox1.egg";
+
+	let mut interpreter = create_interpreter(file_contents, file_name, CompileOptions::default())
+		.expect("Could not create interpreter");
+	assert_eq!(
+		interpreter.run().expect("Could not run program"),
+		RunStatus::Finished
+	);
+	assert_buffer(vec!["1"], "interpreter");
+
+	interpreter.set_sandbox(config.clone());
+	let interpreter_error = interpreter
+		.call(function, arguments)
+		.expect_err("Expected the interpreter to stop");
+	assert_buffer(vec![], "interpreter");
+
+	let jit_error = run_file_in_jit_with_runtime(
+		file_contents,
+		file_name,
+		CompileOptions::default(),
+		|jit_runtime| {
+			jit_runtime.set_sandbox(config)?;
+			jit_runtime
+				.call_function(function, arguments)
+				.map(|_| ())
+				.map_err(|error| anyhow!("{}", error))
+		},
+	)
+	.expect_err("Expected the JIT to stop");
+	assert_buffer(vec!["1"], "jit");
+
+	vec![interpreter_error.to_string(), jit_error.to_string()]
+}
+
+fn assert_errors_contain(errors: Vec<String>, expected: &str) {
+	for error in errors {
+		assert!(error.contains(expected), "{}", error);
+	}
+}
+
+#[test]
+#[serial]
+fn sandbox_fuel1() {
+	let config = SandboxConfig {
+		fuel: Some(1_000),
+		..SandboxConfig::default()
+	};
+
+	// scripts cannot catch running out of fuel, so `spin` never prints
+	assert_errors_contain(
+		call_in_sandbox(config, "spin", &[]),
+		"Sandbox limit exceeded: out of fuel",
+	);
+}
+
+#[test]
+#[serial]
+fn sandbox_deadline1() {
+	let config = SandboxConfig {
+		deadline: Some(Duration::from_millis(50)),
+		..SandboxConfig::default()
+	};
+
+	assert_errors_contain(
+		call_in_sandbox(config, "spin", &[]),
+		"Sandbox limit exceeded: the deadline passed",
+	);
+}
+
+#[test]
+#[serial]
+fn sandbox_memory1() {
+	let config = SandboxConfig {
+		max_memory: Some(1 << 20),
+		..SandboxConfig::default()
+	};
+
+	assert_errors_contain(
+		call_in_sandbox(config, "fill", &[Value::Number(1_000_000.0)]),
+		"Sandbox limit exceeded: out of memory",
+	);
+}
+
+#[test]
+#[serial]
+fn sandbox_call_depth1() {
+	let config = SandboxConfig {
+		max_call_depth: Some(50),
+		..SandboxConfig::default()
+	};
+
+	assert_errors_contain(
+		call_in_sandbox(config, "countDown", &[Value::Number(100.0)]),
+		"Uncaught exception: Stack overflow",
+	);
+}
+
+#[test]
+#[serial]
+fn sandbox_memory2() -> Result<()> {
+	let file_contents = include_str!("./test_cases/sandbox1.egg");
+	let file_name = "./src/tests/test_cases/sandbox1.egg";
+
+	// maps of calls that returned do not count towards the limit
+	let config = SandboxConfig {
+		max_memory: Some(4_096),
+		..SandboxConfig::default()
+	};
+
+	let mut interpreter = create_interpreter(file_contents, file_name, CompileOptions::default())?;
+	assert_eq!(interpreter.run()?, RunStatus::Finished);
+	interpreter.set_sandbox(config.clone());
+	for _ in 0..2_000 {
+		assert_eq!(
+			interpreter.call("fill", &[Value::Number(10.0)])?,
+			Value::Number(10.0)
+		);
+	}
+	assert_buffer(vec!["1"], "interpreter");
+
+	run_file_in_jit_with_runtime(
+		file_contents,
+		file_name,
+		CompileOptions::default(),
+		|jit_runtime| {
+			jit_runtime.set_sandbox(config)?;
+			for _ in 0..2_000 {
+				assert_eq!(
+					jit_runtime.call_function("fill", &[Value::Number(10.0)])?,
+					Value::Number(10.0)
+				);
+			}
+
+			Ok(())
+		},
+	)?;
+	assert_buffer(vec!["1"], "jit");
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn sandbox_within_limits1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/sandbox1.egg");
+	let file_name = "./src/tests/test_cases/sandbox1.egg";
+
+	let config = SandboxConfig {
+		deadline: Some(Duration::from_secs(60)),
+		fuel: Some(1_000_000),
+		max_call_depth: Some(200),
+		max_memory: Some(1 << 20),
+		..SandboxConfig::default()
+	};
+
+	let mut interpreter = create_interpreter(file_contents, file_name, CompileOptions::default())?;
+	interpreter.set_sandbox(config.clone());
+	assert_eq!(interpreter.run()?, RunStatus::Finished);
+	assert_eq!(
+		interpreter.call("countTo", &[Value::Number(1_000.0)])?,
+		Value::Number(1_000.0)
+	);
+	assert_eq!(
+		interpreter.call("fill", &[Value::Number(100.0)])?,
+		Value::Number(100.0)
+	);
+	assert_eq!(
+		interpreter.call("countDown", &[Value::Number(100.0)])?,
+		Value::Number(100.0)
+	);
+	assert_buffer(vec!["1"], "interpreter");
+
+	run_file_in_jit_with_runtime(
+		file_contents,
+		file_name,
+		CompileOptions::default(),
+		|jit_runtime| {
+			jit_runtime.set_sandbox(config)?;
+			assert_eq!(
+				jit_runtime.call_function("countTo", &[Value::Number(1_000.0)])?,
+				Value::Number(1_000.0)
+			);
+			assert_eq!(
+				jit_runtime.call_function("fill", &[Value::Number(100.0)])?,
+				Value::Number(100.0)
+			);
+			assert_eq!(
+				jit_runtime.call_function("countDown", &[Value::Number(100.0)])?,
+				Value::Number(100.0)
+			);
+
+			Ok(())
+		},
+	)?;
+	assert_buffer(vec!["1"], "jit");
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn sandbox_allowed_natives1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/sandbox2.egg");
+	let file_name = "./src/tests/test_cases/sandbox2.egg";
+
+	let config = SandboxConfig {
+		allowed_natives: Some(HashSet::from([
+			"printString".to_string(),
+			"schedule".to_string(),
+		])),
+		..SandboxConfig::default()
+	};
+	let options = CompileOptions::default().with_sandbox(&config);
+
+	// natives the sandbox does not allow cannot be scheduled by their name either
+	let mut interpreter = create_interpreter(file_contents, file_name, options.clone())?;
+	interpreter.set_sandbox(config.clone());
+	assert_eq!(interpreter.run()?, RunStatus::Finished);
+	interpreter.call("scheduleNative", &[])?;
+	assert_buffer(
+		vec![
+			"loaded",
+			"Cannot schedule 'getSimTime', the sandbox does not allow it",
+		],
+		"interpreter",
+	);
+
+	run_file_in_jit_with_runtime(file_contents, file_name, options, |jit_runtime| {
+		jit_runtime.set_sandbox(config)?;
+		jit_runtime.call_function("scheduleNative", &[])
+	})?;
+	assert_buffer(
+		vec![
+			"loaded",
+			"Cannot schedule 'getSimTime', the sandbox does not allow it",
+		],
+		"jit",
+	);
+
+	// programs that call a native the sandbox does not allow fail to compile
+	let config = SandboxConfig {
+		allowed_natives: Some(HashSet::from(["printString".to_string()])),
+		..SandboxConfig::default()
+	};
+	let options = CompileOptions::default().with_sandbox(&config);
+
+	let error = create_interpreter(file_contents, file_name, options.clone())
+		.err()
+		.expect("Expected a compile error");
+	assert!(
+		format!("{:#}", error)
+			.contains("Native function 'schedule' is not allowed in this sandbox"),
+		"{:#}",
+		error
+	);
+
+	let error = run_file_in_jit_with_options(file_contents, file_name, options)
+		.expect_err("Expected a compile error");
+	assert!(
+		format!("{:#}", error)
+			.contains("Native function 'schedule' is not allowed in this sandbox"),
+		"{:#}",
+		error
+	);
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn sandbox_allowed_natives2() -> Result<()> {
+	let file_contents = include_str!("./test_cases/sandbox2.egg");
+	let file_name = "./src/tests/test_cases/sandbox2.egg";
+
+	let config = SandboxConfig {
+		allowed_natives: Some(HashSet::from(["printString".to_string()])),
+		..SandboxConfig::default()
+	};
+
+	// programs compiled without the sandbox cannot call natives it does not allow either, and
+	// scripts cannot catch the error
+	let mut interpreter = create_interpreter(file_contents, file_name, CompileOptions::default())?;
+	assert_eq!(interpreter.run()?, RunStatus::Finished);
+	interpreter.set_sandbox(config.clone());
+	let error = interpreter
+		.call("scheduleNative", &[])
+		.expect_err("Expected the interpreter to stop");
+	assert!(
+		error
+			.to_string()
+			.contains("Native function 'schedule' is not allowed in this sandbox"),
+		"{}",
+		error
+	);
+	assert_buffer(vec!["loaded"], "interpreter");
+
+	let error = run_file_in_jit_with_runtime(
+		file_contents,
+		file_name,
+		CompileOptions::default(),
+		|jit_runtime| jit_runtime.set_sandbox(config),
+	)
+	.expect_err("Expected the JIT to refuse the sandbox");
+	assert!(
+		error
+			.to_string()
+			.contains("Native function 'schedule' is not allowed in this sandbox"),
+		"{}",
+		error
+	);
+	assert_buffer(vec!["loaded"], "jit");
+
+	Ok(())
+}
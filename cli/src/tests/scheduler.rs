@@ -0,0 +1,57 @@
+use anyhow::{anyhow, Result};
+use eggscript_ast::CompileOptions;
+use eggscript_interpreter::RunStatus;
+use serial_test::serial;
+
+use super::{assert_buffer, create_interpreter, run_file_in_jit_with_runtime};
+
+/// Advances the clock of schedule1.egg the same way on both backends
+fn check_schedule(mut advance_time: impl FnMut(u64) -> Result<()>, message: &str) -> Result<()> {
+	assert_buffer(
+		vec![
+			"1",
+			"1",
+			"0",
+			"0",
+			"Cannot schedule unknown function 'missing'",
+		],
+		message,
+	);
+
+	advance_time(99)?;
+	assert_buffer(vec!["first", "30"], message);
+
+	// calls scheduled by scheduled calls run in the same advance if they are due
+	advance_time(250)?;
+	assert_buffer(vec!["1", "2", "3"], message);
+
+	advance_time(1000)?;
+	assert_buffer(vec![], message);
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn schedule1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/schedule1.egg");
+	let file_name = "./src/tests/test_cases/schedule1.egg";
+
+	let mut interpreter = create_interpreter(file_contents, file_name, CompileOptions::default())?;
+
+	assert_eq!(interpreter.run()?, RunStatus::Finished);
+	check_schedule(
+		|ms| interpreter.advance_time(ms).map_err(|error| anyhow!(error)),
+		"interpreter",
+	)?;
+	assert_eq!(interpreter.scheduler().time(), 1349);
+
+	run_file_in_jit_with_runtime(
+		file_contents,
+		file_name,
+		CompileOptions::default(),
+		|jit_runtime| check_schedule(|ms| jit_runtime.advance_time(ms), "jit"),
+	)?;
+
+	Ok(())
+}
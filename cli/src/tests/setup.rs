@@ -1,14 +1,14 @@
 use anyhow::Result;
-use eggscript_ast::{compile_expression, parse_string};
+use eggscript_ast::{compile_expression, parse_string_with_options, CompileOptions};
 use eggscript_interpreter::runtime::print::{clear_test_print_buffer, get_test_print_buffer};
 use eggscript_interpreter::{
 	get_test_native_function_mapping_for_interpreter, get_test_native_function_mapping_for_jit,
-	Interpreter,
+	Interpreter, RunStatus, Value,
 };
-use eggscript_mir::EggscriptLowerContext;
 use inkwell::context::Context;
 use inkwell::execution_engine::JitFunction;
 use inkwell::OptimizationLevel;
+use std::rc::Rc;
 
 use crate::eggscript;
 use crate::llvm;
@@ -26,50 +26,84 @@ pub fn assert_buffer(expected: Vec<&str>, message: &str) {
 	);
 }
 
-pub fn run_file_in_interpreter(contents: &str, file_name: &str, timeout: u128) -> Result<()> {
-	let program = parse_string(contents, file_name)?;
-	let (ast_content, units) = compile_expression(program.clone(), program.global_scope.clone())?;
-
-	let mut eggscript_context: EggscriptLowerContext = ast_content.into();
-	let instructions = eggscript_context.compile_to_eggscript(&units, None)?;
+pub fn string(value: &str) -> Value {
+	Value::String(Rc::new(value.to_string()))
+}
 
-	let mut interpreter = Interpreter::new(instructions);
+/// Compiles a file into an interpreter with the test natives, without running it
+pub fn create_interpreter(
+	contents: &str,
+	file_name: &str,
+	options: CompileOptions,
+) -> Result<Interpreter> {
+	let program = parse_string_with_options(contents, file_name, options)?;
+	let (interpreter, _) = eggscript::create_interpreter(
+		program,
+		get_test_native_function_mapping_for_interpreter(),
+		false,
+	)?;
+
+	return Ok(interpreter);
+}
 
-	let native_function_mapping = get_test_native_function_mapping_for_interpreter();
+pub fn run_file_in_interpreter(contents: &str, file_name: &str, fuel: u64) -> Result<()> {
+	return run_file_in_interpreter_with_options(
+		contents,
+		file_name,
+		fuel,
+		CompileOptions::default(),
+	);
+}
 
-	for function in program.functions.iter() {
-		if function.scope.is_some() {
-			let (_, instructions) = eggscript::lower_function(program.clone(), function, false)?;
-			interpreter.add_function(eggscript_interpreter::Function::new_eggscript_function(
-				function.id,
-				function.arguments.len(),
-				instructions,
-				&function.name,
-			));
-		} else {
-			interpreter.add_function(eggscript_interpreter::Function::new_native(
-				function.id,
-				function.arguments.len(),
-				native_function_mapping
-					.get(&function.name)
-					.expect("Could not get native function from map")
-					.clone(),
-				&function.name,
-			));
+pub fn run_file_in_interpreter_with_options(
+	contents: &str,
+	file_name: &str,
+	fuel: u64,
+	options: CompileOptions,
+) -> Result<()> {
+	let program = parse_string_with_options(contents, file_name, options)?;
+	let (mut interpreter, _) = eggscript::create_interpreter(
+		program.clone(),
+		get_test_native_function_mapping_for_interpreter(),
+		false,
+	)?;
+
+	loop {
+		match interpreter.run_with_fuel(fuel) {
+			Ok(RunStatus::Finished) => break,
+			Ok(RunStatus::OutOfFuel) => panic!("interpreter ran out of fuel"),
+			Ok(RunStatus::Waiting(_)) => panic!("interpreter waits for an async native"),
+			// the tests have nothing to do while a script is paused
+			Ok(RunStatus::Suspended | RunStatus::Yielded(_)) => {}
+			Err(error) => return Err(program.runtime_error_with_backtrace(error)),
 		}
 	}
 
-	if let Err(_) = interpreter.run_with_timeout(timeout) {
-		panic!("interpreter timeout");
-	}
-
 	Ok(())
 }
 
 type EntryFunction = unsafe extern "C" fn() -> ();
 
 pub fn run_file_in_jit(contents: &str, file_name: &str) -> Result<()> {
-	let program = parse_string(contents, file_name)?;
+	return run_file_in_jit_with_options(contents, file_name, CompileOptions::default());
+}
+
+pub fn run_file_in_jit_with_options(
+	contents: &str,
+	file_name: &str,
+	options: CompileOptions,
+) -> Result<()> {
+	return run_file_in_jit_with_runtime(contents, file_name, options, |_| Ok(()));
+}
+
+/// Runs the file, then hands the runtime to `action` while the program is still loaded
+pub fn run_file_in_jit_with_runtime<T>(
+	contents: &str,
+	file_name: &str,
+	options: CompileOptions,
+	action: impl FnOnce(&llvm::JitRuntime) -> Result<T>,
+) -> Result<T> {
+	let program = parse_string_with_options(contents, file_name, options)?;
 
 	let (ast_context, units) = compile_expression(program.clone(), program.global_scope.clone())?;
 
@@ -89,7 +123,8 @@ pub fn run_file_in_jit(contents: &str, file_name: &str) -> Result<()> {
 		.create_jit_execution_engine(OptimizationLevel::Default)
 		.expect("Could not create JIT execution engine");
 
-	let function_mapping = get_test_native_function_mapping_for_jit();
+	let mut function_mapping = get_test_native_function_mapping_for_jit();
+	llvm::add_runtime_natives(&mut function_mapping);
 
 	for function in program.functions.iter() {
 		if function.scope.is_some() {
@@ -115,12 +150,19 @@ pub fn run_file_in_jit(contents: &str, file_name: &str) -> Result<()> {
 		}
 	}
 
-	unsafe {
-		let function: JitFunction<EntryFunction> = engine.get_function("entry")?;
-		function.call();
-	}
+	let jit_runtime = llvm::JitRuntime::new(
+		&context,
+		&engine,
+		&module,
+		program.clone(),
+		function_mapping,
+	)?;
+	let function: JitFunction<EntryFunction> = unsafe { engine.get_function("entry")? };
+	jit_runtime.run(&function);
+
+	let result = action(&jit_runtime);
 
 	drop(llvm_context);
 
-	Ok(())
+	return result;
 }
@@ -0,0 +1,137 @@
+use anyhow::{anyhow, Result};
+use eggscript_ast::CompileOptions;
+use eggscript_interpreter::{
+	Instruction, Interpreter, RunStatus, SnapshotError, Value, SNAPSHOT_VERSION,
+};
+use serial_test::serial;
+
+use super::{assert_buffer, create_interpreter};
+
+/// Runs snapshot1.egg from where it was saved, the same way for the original and the restored
+/// interpreter
+fn finish_adventure(interpreter: &mut Interpreter, message: &str) -> Result<()> {
+	interpreter
+		.advance_time(100)
+		.map_err(|error| anyhow!(error))?;
+	assert_buffer(vec!["2"], message);
+
+	assert_eq!(
+		interpreter.resume()?,
+		RunStatus::Yielded(Value::Number(2.0))
+	);
+	assert_buffer(vec!["trap"], message);
+
+	interpreter
+		.advance_time(100)
+		.map_err(|error| anyhow!(error))?;
+	assert_buffer(vec!["3"], message);
+
+	assert_eq!(interpreter.resume()?, RunStatus::Finished);
+	assert_buffer(vec!["3", "greedy", "13"], message);
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn snapshot1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/snapshot1.egg");
+	let file_name = "./src/tests/test_cases/snapshot1.egg";
+
+	let mut interpreter = create_interpreter(file_contents, file_name, CompileOptions::default())?;
+	assert_eq!(interpreter.run()?, RunStatus::Yielded(Value::Number(0.0)));
+	interpreter
+		.advance_time(100)
+		.map_err(|error| anyhow!(error))?;
+	assert_eq!(interpreter.run()?, RunStatus::Yielded(Value::Number(1.0)));
+	assert_buffer(vec!["1"], "before snapshot");
+
+	// saved inside of a try block of a function, with a scheduled call pending
+	let snapshot = interpreter.snapshot()?;
+	finish_adventure(&mut interpreter, "original")?;
+
+	let mut restored = create_interpreter(file_contents, file_name, CompileOptions::default())?;
+	restored.restore(&snapshot)?;
+	finish_adventure(&mut restored, "restored")?;
+
+	// snapshots can be restored more than once, even into an interpreter that already ran
+	restored.restore(&snapshot)?;
+	finish_adventure(&mut restored, "restored again")?;
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn snapshot_errors1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/snapshot1.egg");
+	let file_name = "./src/tests/test_cases/snapshot1.egg";
+
+	let mut interpreter = create_interpreter(file_contents, file_name, CompileOptions::default())?;
+	assert_eq!(interpreter.run()?, RunStatus::Yielded(Value::Number(0.0)));
+	let snapshot = interpreter.snapshot()?;
+	assert_buffer(vec![], "snapshot errors");
+
+	let changed_function = file_contents.replace("%found += 1;", "%found += 2;");
+	let error = create_interpreter(&changed_function, file_name, CompileOptions::default())?
+		.restore(&snapshot)
+		.expect_err("Expected the changed function to be refused");
+	assert_eq!(error, SnapshotError::SourceChanged("adventure".into()));
+	assert_eq!(
+		error.to_string(),
+		"The source of 'adventure' changed since the snapshot was taken"
+	);
+
+	let changed_global_scope = file_contents.replace("\"gold\": 10", "\"gold\": 20");
+	let error = create_interpreter(&changed_global_scope, file_name, CompileOptions::default())?
+		.restore(&snapshot)
+		.expect_err("Expected the changed global scope to be refused");
+	assert_eq!(error, SnapshotError::SourceChanged("<global scope>".into()));
+
+	let mut restored = create_interpreter(file_contents, file_name, CompileOptions::default())?;
+
+	let mut newer = snapshot.clone();
+	newer[8..12].copy_from_slice(&(SNAPSHOT_VERSION + 1).to_le_bytes());
+	assert_eq!(
+		restored.restore(&newer),
+		Err(SnapshotError::UnsupportedVersion(SNAPSHOT_VERSION + 1))
+	);
+
+	assert_eq!(
+		restored.restore(&snapshot[..snapshot.len() - 1]),
+		Err(SnapshotError::Corrupt("ended early".into()))
+	);
+	assert_eq!(
+		restored.restore(b"not a snapshot"),
+		Err(SnapshotError::NotASnapshot)
+	);
+
+	// failed restores leave the interpreter as it was
+	assert_eq!(restored.run()?, RunStatus::Yielded(Value::Number(0.0)));
+
+	Ok(())
+}
+
+#[test]
+fn snapshot_instruction_index1() -> Result<()> {
+	let mut interpreter = Interpreter::new(vec![Instruction::Noop, Instruction::Noop]);
+	assert_eq!(interpreter.run_with_fuel(1)?, RunStatus::OutOfFuel);
+	let mut snapshot = interpreter.snapshot()?;
+
+	// the index of the global scope follows the header, the hash of the global scope, the empty
+	// function and package lists, the empty stack, the stack base and the code tag
+	let index = 12 + 8 + 8 + 8 + 8 + 8 + 1;
+	assert_eq!(snapshot[index..index + 8], 1u64.to_le_bytes());
+	snapshot[index..index + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+
+	let mut restored = Interpreter::new(vec![Instruction::Noop, Instruction::Noop]);
+	assert_eq!(
+		restored.restore(&snapshot),
+		Err(SnapshotError::Corrupt(format!(
+			"instruction {} is past the end of '<global scope>'",
+			u64::MAX
+		)))
+	);
+
+	Ok(())
+}
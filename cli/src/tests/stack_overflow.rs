@@ -0,0 +1,98 @@
+use anyhow::Result;
+use eggscript_ast::CompileOptions;
+use eggscript_interpreter::runtime::stack::DEFAULT_MAX_CALL_DEPTH;
+use eggscript_interpreter::Value;
+use serial_test::serial;
+
+use super::{
+	assert_buffer, create_interpreter, run_file_in_interpreter, run_file_in_jit,
+	run_file_in_jit_with_runtime,
+};
+
+#[test]
+#[serial]
+fn stack_overflow1() -> Result<()> {
+	let file_contents = include_str!("./test_cases/stack_overflow1.egg");
+	let file_name = "./src/tests/test_cases/stack_overflow1.egg";
+
+	run_file_in_interpreter(file_contents, file_name, 1_000_000)?;
+	assert_buffer(vec!["Stack overflow", "500"], "interpreter");
+
+	run_file_in_jit(file_contents, file_name)?;
+	assert_buffer(vec!["Stack overflow", "500"], "jit");
+
+	// countDown(500) goes deeper than the lowered limit
+	let mut interpreter = create_interpreter(file_contents, file_name, CompileOptions::default())?;
+	interpreter.set_max_call_depth(100);
+	let error = interpreter.run().expect_err("Expected a stack overflow");
+	assert!(
+		error
+			.to_string()
+			.starts_with("Uncaught exception: Stack overflow in function 'countDown'"),
+		"{}",
+		error
+	);
+	assert_buffer(vec!["Stack overflow"], "interpreter");
+
+	let mut interpreter = create_interpreter(file_contents, file_name, CompileOptions::default())?;
+	interpreter.set_max_stack_size(200);
+	let error = interpreter.run().expect_err("Expected a stack overflow");
+	assert!(error.to_string().contains("Stack overflow"), "{}", error);
+	assert_buffer(vec!["Stack overflow"], "interpreter");
+
+	run_file_in_jit_with_runtime(
+		file_contents,
+		file_name,
+		CompileOptions::default(),
+		|jit_runtime| {
+			jit_runtime.set_max_call_depth(100);
+			let result = jit_runtime.call_function("countDown", &[Value::Number(500.0)]);
+			jit_runtime.set_max_call_depth(DEFAULT_MAX_CALL_DEPTH);
+
+			let error = result.expect_err("Expected a stack overflow");
+			assert!(
+				error
+					.to_string()
+					.contains("Uncaught exception: Stack overflow"),
+				"{}",
+				error
+			);
+
+			Ok(())
+		},
+	)?;
+	assert_buffer(vec!["Stack overflow", "500"], "jit");
+
+	Ok(())
+}
+
+#[test]
+#[serial]
+fn stack_overflow2() -> Result<()> {
+	let file_contents = include_str!("./test_cases/stack_overflow2.egg");
+	let file_name = "./src/tests/test_cases/stack_overflow2.egg";
+
+	let error = run_file_in_interpreter(file_contents, file_name, 1_000_000)
+		.expect_err("Expected an uncaught stack overflow");
+
+	let expected = [
+		"Uncaught exception: Stack overflow in function 'recurse' at instruction",
+		"    at recurse (./src/tests/test_cases/stack_overflow2.egg:2:9)",
+		"        return recurse(%depth + 1) + 1;",
+	];
+
+	let error = error.to_string();
+	for line in expected {
+		assert!(error.contains(line), "{}", error);
+	}
+
+	assert!(
+		error.ends_with(
+			"    at <global scope> (./src/tests/test_cases/stack_overflow2.egg:5:13)\n        printNumber(recurse(0));"
+		),
+		"{}",
+		error
+	);
+
+	Ok(())
+}
@@ -0,0 +1,16 @@
+/// Part of a source file an instruction was compiled from, as byte offsets into the file
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SourceSpan {
+	pub file: u32,
+	pub start: u32,
+	pub end: u32,
+}
+
+/// Function that was running when a backtrace was taken, and the code it was running
+#[derive(Clone, Debug, PartialEq)]
+pub struct StackFrame {
+	/// `None` for global scopes
+	pub function: Option<String>,
+	/// `None` for instructions that were not compiled from source, like hand written bytecode
+	pub span: Option<SourceSpan>,
+}
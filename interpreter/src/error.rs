@@ -0,0 +1,123 @@
+use crate::function::FunctionHandle;
+use crate::sandbox::SandboxLimit;
+use crate::RelativeStackAddress;
+use crate::StackFrame;
+
+/// Why the interpreter could not keep running. These are errors in the bytecode or the host,
+/// errors in scripts are raised as exceptions instead
+#[derive(Clone, Debug, PartialEq)]
+pub enum RuntimeErrorKind {
+	/// The host called a function that does not exist or with the wrong arguments
+	InvalidCall(String),
+	/// The host interrupted the script through its `InterruptHandle`
+	Interrupted,
+	/// Ran an `Instruction::Invalid`
+	InvalidInstruction,
+	/// A jump would have moved before the first instruction
+	InvalidJump(isize),
+	/// Read a stack slot that does not exist
+	InvalidStackAddress(RelativeStackAddress),
+	/// Returned without a frame to return to
+	MissingFrame,
+	/// Created a map from operands that are not pairs of keys and values
+	OddMapOperands(usize),
+	/// A sandboxed script called a native the sandbox does not allow
+	NativeNotAllowed(String),
+	/// A native panicked, with the message it panicked with
+	NativePanic(String, String),
+	/// A sandboxed script ran into one of the limits of its sandbox
+	SandboxLimit(SandboxLimit),
+	/// Popped more values than there are on the stack
+	StackUnderflow,
+	/// `run_with_timeout` ran out of time
+	Timeout,
+	/// An exception was thrown outside of every try block
+	UncaughtException(String),
+	/// The host fulfilled a token that no code waits for
+	UnknownPendingToken(usize),
+	/// An instruction was given a value of the wrong type
+	TypeMismatch {
+		expected: &'static str,
+		found: &'static str,
+	},
+	/// Called a function that was never added to the interpreter
+	UnknownFunction(FunctionHandle),
+}
+
+impl std::fmt::Display for RuntimeErrorKind {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			RuntimeErrorKind::InvalidCall(message) => {
+				f.write_fmt(format_args!("Invalid call: {}", message))
+			}
+			RuntimeErrorKind::Interrupted => f.write_str("Interrupted"),
+			RuntimeErrorKind::InvalidInstruction => f.write_str("Invalid instruction"),
+			RuntimeErrorKind::InvalidJump(offset) => {
+				f.write_fmt(format_args!("Invalid relative jump by {}", offset))
+			}
+			RuntimeErrorKind::InvalidStackAddress(address) => {
+				f.write_fmt(format_args!("Invalid stack address {}", address))
+			}
+			RuntimeErrorKind::MissingFrame => f.write_str("Returned without a frame to return to"),
+			RuntimeErrorKind::OddMapOperands(count) => f.write_fmt(format_args!(
+				"Cannot create a map from {} operands, keys and values come in pairs",
+				count
+			)),
+			RuntimeErrorKind::NativeNotAllowed(name) => f.write_fmt(format_args!(
+				"Native function '{}' is not allowed in this sandbox",
+				name
+			)),
+			RuntimeErrorKind::NativePanic(name, message) => f.write_fmt(format_args!(
+				"Native function '{}' panicked: {}",
+				name, message
+			)),
+			RuntimeErrorKind::SandboxLimit(limit) => {
+				f.write_fmt(format_args!("Sandbox limit exceeded: {}", limit))
+			}
+			RuntimeErrorKind::StackUnderflow => f.write_str("Stack underflow"),
+			RuntimeErrorKind::Timeout => f.write_str("Timed out"),
+			RuntimeErrorKind::UncaughtException(exception) => {
+				f.write_fmt(format_args!("Uncaught exception: {}", exception))
+			}
+			RuntimeErrorKind::TypeMismatch { expected, found } => f.write_fmt(format_args!(
+				"Expected a {} value, found a {} value",
+				expected, found
+			)),
+			RuntimeErrorKind::UnknownPendingToken(token) => {
+				f.write_fmt(format_args!("Nothing waits for pending token {}", token))
+			}
+			RuntimeErrorKind::UnknownFunction(handle) => {
+				f.write_fmt(format_args!("Unknown function {}", handle))
+			}
+		}
+	}
+}
+
+impl std::error::Error for RuntimeErrorKind {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuntimeError {
+	pub kind: RuntimeErrorKind,
+	/// Name of the function that was running, `None` for global scopes
+	pub function: Option<String>,
+	pub instruction_index: usize,
+	/// Functions that were running when the error happened, innermost first
+	pub backtrace: Vec<StackFrame>,
+}
+
+impl std::fmt::Display for RuntimeError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match &self.function {
+			Some(function) => f.write_fmt(format_args!(
+				"{} in function '{}' at instruction {}",
+				self.kind, function, self.instruction_index
+			)),
+			None => f.write_fmt(format_args!(
+				"{} in global scope at instruction {}",
+				self.kind, self.instruction_index
+			)),
+		}
+	}
+}
+
+impl std::error::Error for RuntimeError {}
@@ -1,10 +1,13 @@
+use anyhow::Result;
 use std::rc::Rc;
 
-use crate::{Instruction, Value};
+use crate::{Instruction, Interpreter, SourceSpan, Value};
 
 pub type FunctionHandle = usize;
 
-pub type NativeFunction = Rc<dyn Fn(Vec<Value>) -> Value>;
+/// Natives get access to the interpreter that called them, so they can load code or inspect its
+/// state. Errors are raised as exceptions that scripts can catch
+pub type NativeFunction = Rc<dyn Fn(&mut Interpreter, Vec<Value>) -> Result<Value>>;
 
 pub enum Function {
 	Eggscript {
@@ -12,6 +15,8 @@ pub enum Function {
 		id: FunctionHandle,
 		instructions: Rc<Vec<Instruction>>,
 		name: String,
+		/// Source span of every instruction, for backtraces
+		spans: Rc<Vec<Option<SourceSpan>>>,
 	},
 	Native {
 		argument_count: usize,
@@ -22,10 +27,18 @@ pub enum Function {
 }
 
 impl Function {
+	pub fn name(&self) -> &str {
+		match self {
+			Function::Eggscript { name, .. } => name,
+			Function::Native { name, .. } => name,
+		}
+	}
+
 	pub fn new_eggscript_function(
 		id: FunctionHandle,
 		argument_count: usize,
 		instructions: Vec<Instruction>,
+		spans: Vec<Option<SourceSpan>>,
 		name: &str,
 	) -> Function {
 		Function::Eggscript {
@@ -33,6 +46,7 @@ impl Function {
 			id,
 			instructions: Rc::new(instructions),
 			name: name.into(),
+			spans: Rc::new(spans),
 		}
 	}
 
@@ -50,3 +64,22 @@ impl Function {
 		}
 	}
 }
+
+/// Function the host calls, either by the name scripts know it by or by its handle
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FunctionReference<'a> {
+	Handle(FunctionHandle),
+	Name(&'a str),
+}
+
+impl<'a> From<&'a str> for FunctionReference<'a> {
+	fn from(name: &'a str) -> Self {
+		FunctionReference::Name(name)
+	}
+}
+
+impl From<FunctionHandle> for FunctionReference<'_> {
+	fn from(handle: FunctionHandle) -> Self {
+		FunctionReference::Handle(handle)
+	}
+}
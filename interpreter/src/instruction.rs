@@ -1,20 +1,75 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::error::RuntimeErrorKind;
 use crate::function::FunctionHandle;
+use crate::runtime::map::Map;
 
 pub type AbsoluteStackAddress = usize;
 pub type RelativeStackAddress = isize;
 
 #[derive(Clone, Debug)]
 pub enum Value {
+	/// Maps are shared between every value that refers to them
+	Map(Rc<RefCell<Map>>),
 	Number(f64),
 	Null,
+	String(Rc<String>),
 }
 
 impl Value {
-	pub fn as_number(&self) -> f64 {
+	pub fn as_number(&self) -> Result<f64, RuntimeErrorKind> {
 		if let Value::Number(value) = self {
-			return *value;
+			return Ok(*value);
+		} else {
+			return Err(RuntimeErrorKind::TypeMismatch {
+				expected: "number",
+				found: self.type_name(),
+			});
+		}
+	}
+
+	pub fn as_string(&self) -> Result<&str, RuntimeErrorKind> {
+		if let Value::String(value) = self {
+			return Ok(value);
 		} else {
-			unreachable!();
+			return Err(RuntimeErrorKind::TypeMismatch {
+				expected: "string",
+				found: self.type_name(),
+			});
+		}
+	}
+
+	pub fn type_name(&self) -> &'static str {
+		match self {
+			Value::Map(_) => "map",
+			Value::Number(_) => "number",
+			Value::Null => "null",
+			Value::String(_) => "string",
+		}
+	}
+}
+
+/// Maps are only equal to themselves, since they are shared by reference
+impl PartialEq for Value {
+	fn eq(&self, other: &Value) -> bool {
+		match (self, other) {
+			(Value::Map(map), Value::Map(other)) => Rc::ptr_eq(map, other),
+			(Value::Number(number), Value::Number(other)) => number == other,
+			(Value::Null, Value::Null) => true,
+			(Value::String(string), Value::String(other)) => string == other,
+			_ => false,
+		}
+	}
+}
+
+impl std::fmt::Display for Value {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Value::Map(map) => f.write_fmt(format_args!("map of {} keys", map.borrow().len())),
+			Value::Number(number) => f.write_fmt(format_args!("{}", number)),
+			Value::Null => f.write_str("null"),
+			Value::String(string) => f.write_str(string),
 		}
 	}
 }
@@ -38,13 +93,50 @@ pub enum Instruction {
 		RelativeStackAddress,
 	),
 	ImmediateNumberMath(NumberMathOperation, Value, RelativeStackAddress),
-	CallFunction(FunctionHandle),
-	Return(bool),
+	CallFunction(FunctionHandle, usize),
+	/// `Parent::` call made by the given package function
+	CallParentFunction(FunctionHandle, usize),
+	Return(usize),
+	/// Starts a try block, exceptions continue at the relative target until the matching `PopCatch`
+	PushCatch(isize),
+	PopCatch,
+	Throw(RelativeStackAddress),
+	/// Pauses the program after this instruction, handing the value to the host
+	Yield(RelativeStackAddress),
 	NumberUnary(NumberUnaryOperation, RelativeStackAddress),
+	/// Pushes 1 if the value is null, 0 otherwise
+	IsNull(RelativeStackAddress),
+	/// Converts an `any` value to a number, raising an exception if it cannot be converted
+	ConvertToNumber(RelativeStackAddress),
+	/// Converts an `any` value to a string
+	ConvertToString(RelativeStackAddress),
+	/// Performs the operation on the operands, and pushes its result if it has one
+	MapOperation(MapOperation, Vec<RelativeStackAddress>),
 	LogicalAnd(RelativeStackAddress, isize, bool),
 	LogicalOr(RelativeStackAddress, isize, bool),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MapOperation {
+	/// Creates a map from the key and value operands that follow each other
+	New,
+	/// Gets the value of the key operand in the map operand, raising an exception if it is missing
+	Get,
+	/// Sets the key operand in the map operand to the value operand, without a result
+	Set,
+	Contains,
+	Remove,
+	Count,
+	/// Gets the key at the index operand, in the order keys were inserted in
+	KeyAt,
+}
+
+impl MapOperation {
+	pub fn has_result(&self) -> bool {
+		self != &MapOperation::Set
+	}
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum NumberMathOperation {
 	Plus,
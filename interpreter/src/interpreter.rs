@@ -1,8 +1,26 @@
+use anyhow::{Context, Result};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::panic::AssertUnwindSafe;
 use std::rc::Rc;
 use std::time::Instant;
 
-use crate::instruction::{Instruction, NumberMathOperation, Value};
-use crate::{Function, NumberUnaryOperation, RelativeStackAddress};
+use crate::error::{RuntimeError, RuntimeErrorKind};
+use crate::function::{FunctionHandle, FunctionReference};
+use crate::instruction::{Instruction, MapOperation, NumberMathOperation, Value};
+use crate::runtime::any::{any_to_number, any_to_string};
+use crate::runtime::map::{self, Map};
+use crate::runtime::stack::{DEFAULT_MAX_CALL_DEPTH, DEFAULT_MAX_STACK_SIZE, STACK_OVERFLOW};
+use crate::sandbox::{value_size, SandboxConfig, SandboxLimit, SandboxState};
+use crate::snapshot::{
+	code_hash, SnapshotError, SnapshotReader, SnapshotWriter, GLOBAL_SCOPE_NAME,
+};
+use crate::value_type::check_argument_types;
+use crate::{
+	CompiledSource, Function, InterruptHandle, NumberUnaryOperation, Package, PackageTable,
+	RelativeStackAddress, ScheduledCall, Scheduler, SourceCompiler, SourceSpan, StackFrame,
+	ValueType,
+};
 
 // extract values off of the stack based on isize stack index (negative means pop, positive means index into stack)
 macro_rules! stack_extract {
@@ -10,7 +28,10 @@ macro_rules! stack_extract {
 		if ($i) < 0 {
 			pop_stack(&$self.stack, &mut $self.stack_pointer)
 		} else {
-			&$self.stack[$self.stack_base + ($i) as usize]
+			$self
+				.stack
+				.get($self.stack_base + ($i) as usize)
+				.ok_or(RuntimeErrorKind::InvalidStackAddress($i))
 		}
 	};
 }
@@ -29,22 +50,135 @@ macro_rules! stack_resize {
 	};
 }
 
+/// Instructions `run_with_timeout` runs between checks of the time
+const TIMEOUT_CHECK_INTERVAL: u64 = 10_000;
+
+/// Why the interpreter stopped running
+#[derive(Clone, Debug, PartialEq)]
+pub enum RunStatus {
+	/// Ran out of instructions
+	Finished,
+	/// Used up its fuel before it finished
+	OutOfFuel,
+	/// A native asked the interpreter to hand control back to the host
+	Suspended,
+	/// The script called an async native, and waits until the host fulfills its token
+	Waiting(PendingToken),
+	/// The script yielded a value to the host
+	Yielded(Value),
+}
+
+/// Async natives hand one of these to the host, which passes it to `fulfill` with their result
+pub type PendingToken = usize;
+
 struct InterpreterFrame {
 	argument_count: usize,
+	function: Option<FunctionHandle>,
+	instruction_index: usize,
+	instructions: Rc<Vec<Instruction>>,
+	spans: Rc<Vec<Option<SourceSpan>>>,
+	stack_base: usize,
+	stack_pointer: usize,
+}
+
+impl InterpreterFrame {
+	/// Moves the stack positions of the frame by the difference between two stack bottoms
+	fn rebase(mut self, from: usize, to: usize) -> InterpreterFrame {
+		self.stack_base = self.stack_base - from + to;
+		self.stack_pointer = self.stack_pointer - from + to;
+		self
+	}
+}
+
+/// Where execution continues when an exception is thrown inside a try block
+struct ExceptionHandler {
+	frame_count: usize,
+	function: Option<FunctionHandle>,
 	instruction_index: usize,
 	instructions: Rc<Vec<Instruction>>,
+	spans: Rc<Vec<Option<SourceSpan>>>,
 	stack_base: usize,
 	stack_pointer: usize,
 }
 
+impl ExceptionHandler {
+	/// Moves the handler like `InterpreterFrame::rebase`, along with the frames below it
+	fn rebase(mut self, from: (usize, usize), to: (usize, usize)) -> ExceptionHandler {
+		self.frame_count = self.frame_count - from.0 + to.0;
+		self.stack_base = self.stack_base - from.1 + to.1;
+		self.stack_pointer = self.stack_pointer - from.1 + to.1;
+		self
+	}
+}
+
+/// Nested code that is waiting for an async native. Its stack positions and frame counts are
+/// relative to the bottom of its part of the stack, since it continues wherever the stack is when
+/// the host fulfills the token
+struct Coroutine {
+	frames: Vec<InterpreterFrame>,
+	function: Option<FunctionHandle>,
+	handlers: Vec<ExceptionHandler>,
+	instruction_index: usize,
+	instructions: Rc<Vec<Instruction>>,
+	spans: Rc<Vec<Option<SourceSpan>>>,
+	stack: Vec<Value>,
+	stack_base: usize,
+}
+
+/// Code a frame of a snapshot runs, found in the interpreter the snapshot is restored into
+struct SavedCode {
+	function: Option<FunctionHandle>,
+	instruction_index: usize,
+	instructions: Rc<Vec<Instruction>>,
+	spans: Rc<Vec<Option<SourceSpan>>>,
+}
+
 pub struct Interpreter {
+	/// Types of the arguments of functions the host can call, checked by `call`
+	argument_types: HashMap<FunctionHandle, Vec<ValueType>>,
+	compiler: Option<Rc<RefCell<dyn SourceCompiler>>>,
+	coroutines: HashMap<PendingToken, Coroutine>,
+	/// Index of the frame `run_nested` pushed for the code that ran the nested code, `None` outside
+	/// of nested code
+	frame_base: Option<usize>,
 	frames: Vec<InterpreterFrame>,
+	/// Function that is running, `None` for global scopes
+	function: Option<FunctionHandle>,
+	/// Maps the function a call refers to onto the function that runs, packages rebind entries
+	function_table: Vec<FunctionHandle>,
 	functions: Vec<Function>,
+	/// Global scope of the program the interpreter was created with, snapshots refer to it
+	global_instructions: Rc<Vec<Instruction>>,
+	global_spans: Rc<Vec<Option<SourceSpan>>>,
+	/// Handlers below this index belong to code outside of the current `run_nested` call
+	handler_base: usize,
+	handlers: Vec<ExceptionHandler>,
 	instruction_index: usize,
 	instructions: Rc<Vec<Instruction>>,
+	interrupt: InterruptHandle,
+	/// Frames that can be pushed before calls raise a stack overflow
+	max_call_depth: usize,
+	/// Values the stack can hold before calls raise a stack overflow
+	max_stack_size: usize,
+	next_pending_token: PendingToken,
+	packages: PackageTable,
+	/// Set by the instruction that paused the program, until `step` reports it
+	pause: Option<RunStatus>,
+	/// Maps package functions onto the function their `Parent::` calls go to
+	parent_table: HashMap<FunctionHandle, FunctionHandle>,
+	/// Set by the native that is running if its result comes later
+	pending_token: Option<PendingToken>,
+	/// Limits of the sandbox the interpreter runs in, `None` if it is not sandboxed
+	sandbox: Option<SandboxState>,
+	scheduler: Scheduler,
+	/// Source span of every instruction in `instructions`
+	spans: Rc<Vec<Option<SourceSpan>>>,
 	stack: Vec<Value>,
 	stack_base: usize,
 	stack_pointer: usize,
+	uncaught_exception: Option<Value>,
+	/// Token of the async native the program waits for, outside of nested code
+	waiting: Option<PendingToken>,
 }
 
 impl Interpreter {
@@ -52,35 +186,366 @@ impl Interpreter {
 		let global_instructions = Rc::new(global_instructions);
 
 		Interpreter {
+			argument_types: HashMap::new(),
+			compiler: None,
+			coroutines: HashMap::new(),
+			frame_base: None,
 			frames: vec![],
+			function: None,
+			function_table: vec![],
 			functions: vec![],
+			global_instructions: global_instructions.clone(),
+			global_spans: Rc::new(vec![]),
+			handler_base: 0,
+			handlers: vec![],
 			instructions: global_instructions,
 			instruction_index: 0,
+			interrupt: InterruptHandle::default(),
+			max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+			max_stack_size: DEFAULT_MAX_STACK_SIZE,
+			next_pending_token: 0,
+			packages: PackageTable::default(),
+			pause: None,
+			parent_table: HashMap::new(),
+			pending_token: None,
+			sandbox: None,
+			scheduler: Scheduler::default(),
+			spans: Rc::new(vec![]),
 			stack: vec![Value::Null],
 			stack_base: 0,
 			stack_pointer: 0,
+			uncaught_exception: None,
+			waiting: None,
+		}
+	}
+
+	/// Sets how many calls can be running at once before calls raise a stack overflow
+	pub fn set_max_call_depth(&mut self, depth: usize) {
+		self.max_call_depth = depth;
+	}
+
+	/// Sets how many values the stack can hold before calls raise a stack overflow
+	pub fn set_max_stack_size(&mut self, size: usize) {
+		self.max_stack_size = size;
+	}
+
+	/// Handle that stops the script from another thread. It is checked before back-edges and calls,
+	/// so an interrupted program stops before the instruction and can be resumed from there
+	pub fn interrupt_handle(&self) -> InterruptHandle {
+		self.interrupt.clone()
+	}
+
+	/// Runs scripts with the limits of the sandbox from now on. Running into a limit other than the
+	/// call depth stops the program with an error that scripts cannot catch
+	pub fn set_sandbox(&mut self, config: SandboxConfig) {
+		if let Some(depth) = config.max_call_depth {
+			self.max_call_depth = depth;
+		}
+
+		self.sandbox = Some(SandboxState::new(config));
+	}
+
+	/// Whether the sandbox lets scripts call the function, script functions are always allowed
+	pub fn is_allowed_by_sandbox(&self, name: &str) -> bool {
+		match self
+			.function_handle(name)
+			.map(|handle| &self.functions[handle])
+		{
+			Some(Function::Native { .. }) => self.allows_native(name),
+			_ => true,
+		}
+	}
+
+	fn allows_native(&self, name: &str) -> bool {
+		self.sandbox
+			.as_ref()
+			.is_none_or(|sandbox| sandbox.config.allows_native(name))
+	}
+
+	/// Estimate of the bytes the stack and the strings and maps it refers to take up, including
+	/// the stacks of coroutines
+	pub fn memory_usage(&self) -> usize {
+		let mut visited = HashSet::new();
+
+		let coroutine_values = self
+			.coroutines
+			.values()
+			.flat_map(|coroutine| coroutine.stack.iter());
+
+		return self.stack[..self.stack_pointer]
+			.iter()
+			.chain(coroutine_values)
+			.map(|value| value_size(value, &mut visited))
+			.sum();
+	}
+
+	/// Sets the source spans of the global instructions the interpreter was created with
+	pub fn set_spans(&mut self, spans: Vec<Option<SourceSpan>>) {
+		self.global_spans = Rc::new(spans);
+		if Rc::ptr_eq(&self.instructions, &self.global_instructions) {
+			self.spans = self.global_spans.clone();
+		}
+	}
+
+	/// Runs until the program finishes or pauses. A paused interpreter continues from the
+	/// instruction it paused at the next time it is run
+	pub fn run(&mut self) -> Result<RunStatus, RuntimeError> {
+		while !self.is_finished() {
+			if let Some(status) = self.step()? {
+				return Ok(status);
+			}
 		}
+
+		self.check_uncaught_exception()?;
+		Ok(RunStatus::Finished)
 	}
 
-	pub fn run(&mut self) {
-		while self.instruction_index < self.instructions.len() {
-			self.interpret();
+	/// Continues a program that was suspended, yielded or ran out of fuel
+	pub fn resume(&mut self) -> Result<RunStatus, RuntimeError> {
+		self.run()
+	}
+
+	/// Runs at most `fuel` instructions. An interpreter that ran out of fuel stops where it was and
+	/// continues from there the next time it is run
+	pub fn run_with_fuel(&mut self, fuel: u64) -> Result<RunStatus, RuntimeError> {
+		for _ in 0..fuel {
+			if self.is_finished() {
+				break;
+			}
+
+			if let Some(status) = self.step()? {
+				return Ok(status);
+			}
+		}
+
+		if !self.is_finished() {
+			return Ok(RunStatus::OutOfFuel);
 		}
+
+		self.check_uncaught_exception()?;
+		Ok(RunStatus::Finished)
 	}
 
-	pub fn run_with_timeout(&mut self, ms: u128) -> Result<(), ()> {
+	pub fn run_with_timeout(&mut self, ms: u128) -> Result<RunStatus, RuntimeError> {
 		let start = Instant::now();
-		while self.instruction_index < self.instructions.len() {
-			self.interpret();
+
+		// reading the clock is slow, so it is only read between runs of many instructions
+		loop {
+			let status = self.run_with_fuel(TIMEOUT_CHECK_INTERVAL)?;
+			if status != RunStatus::OutOfFuel {
+				return Ok(status);
+			}
 
 			if start.elapsed().as_millis() > ms {
-				return Err(());
+				return Err(self.runtime_error(RuntimeErrorKind::Timeout));
 			}
 		}
+	}
+
+	/// Runs a single instruction, and returns how the program paused if the instruction paused it.
+	/// Stepping a finished interpreter does nothing
+	pub fn step(&mut self) -> Result<Option<RunStatus>, RuntimeError> {
+		if self.is_finished() {
+			return Ok(None);
+		}
+
+		if let Some(token) = self.waiting {
+			return Ok(Some(RunStatus::Waiting(token)));
+		}
+
+		self.interpret().map_err(|kind| self.runtime_error(kind))?;
+		Ok(self.pause.take())
+	}
+
+	/// Pauses the program after the instruction that is running, with `run` returning `Suspended`.
+	/// Natives call this to hand control back to the host
+	pub fn suspend(&mut self) {
+		self.pause = Some(RunStatus::Suspended);
+	}
+
+	/// Called by async natives to hand back their result later, the value they return is not used.
+	/// The code that called the native waits until the host passes the token to `fulfill`
+	pub fn pending_result(&mut self) -> PendingToken {
+		self.next_pending_token += 1;
+		self.pending_token = Some(self.next_pending_token);
+
+		return self.next_pending_token;
+	}
+
+	/// Hands the result of an async native to the code that waits for it. Nested code, like a
+	/// function the host called, continues right away and runs until it finishes or waits again.
+	/// A program that waits continues the next time it is run
+	pub fn fulfill(&mut self, token: PendingToken, value: Value) -> Result<(), RuntimeError> {
+		if self.waiting == Some(token) {
+			self.waiting = None;
+			self.push_stack(value);
+			return Ok(());
+		}
+
+		let Some(mut coroutine) = self.coroutines.remove(&token) else {
+			return Err(self.runtime_error(RuntimeErrorKind::UnknownPendingToken(token)));
+		};
+
+		coroutine.stack.push(value);
+
+		let pause = self.pause.take();
+		let result = self.run_coroutine(coroutine);
+		self.pause = pause;
+		result?;
+
+		if let Some(exception) = self.uncaught_exception.take() {
+			return Err(
+				self.runtime_error(RuntimeErrorKind::UncaughtException(exception.to_string()))
+			);
+		}
 
 		Ok(())
 	}
 
+	/// Stops the code that called an async native until its token is fulfilled. Nested code
+	/// becomes a coroutine and returns to the code that ran it, and the program waits where it is
+	/// otherwise. Returns whether the running code was swapped out
+	fn wait_for(&mut self, token: PendingToken) -> bool {
+		let Some(frame_base) = self.frame_base else {
+			self.waiting = Some(token);
+			self.pause = Some(RunStatus::Waiting(token));
+			return false;
+		};
+
+		let bottom = self.frames[frame_base].stack_pointer;
+		let frames = self
+			.frames
+			.drain(frame_base + 1..)
+			.map(|frame| frame.rebase(bottom, 0))
+			.collect();
+		let handlers = self
+			.handlers
+			.drain(self.handler_base..)
+			.map(|handler| handler.rebase((frame_base + 1, bottom), (0, 0)))
+			.collect();
+
+		self.coroutines.insert(
+			token,
+			Coroutine {
+				frames,
+				function: self.function,
+				handlers,
+				instruction_index: self.instruction_index + 1,
+				instructions: self.instructions.clone(),
+				spans: self.spans.clone(),
+				stack: self.stack[bottom..self.stack_pointer].to_vec(),
+				stack_base: self.stack_base - bottom,
+			},
+		);
+
+		// to `run_coroutine`, it looks like the nested code finished without leaving values behind
+		self.instruction_index = 0;
+		self.instructions = Rc::new(vec![]);
+		self.spans = Rc::new(vec![]);
+		self.stack_base = bottom;
+		self.stack_pointer = bottom;
+
+		return true;
+	}
+
+	/// Whether the interpreter ran out of instructions or stopped because of an uncaught exception
+	pub fn is_finished(&self) -> bool {
+		self.instruction_index >= self.instructions.len() || self.uncaught_exception.is_some()
+	}
+
+	fn runtime_error(&self, kind: RuntimeErrorKind) -> RuntimeError {
+		RuntimeError {
+			kind,
+			function: self.function_name(self.function),
+			instruction_index: self.instruction_index,
+			backtrace: self.backtrace(),
+		}
+	}
+
+	/// Uncaught exceptions stop the interpreter where they were thrown, so the backtrace of the
+	/// error shows where that was
+	fn check_uncaught_exception(&self) -> Result<(), RuntimeError> {
+		match &self.uncaught_exception {
+			Some(exception) => {
+				Err(self.runtime_error(RuntimeErrorKind::UncaughtException(exception.to_string())))
+			}
+			None => Ok(()),
+		}
+	}
+
+	/// Functions that are running, innermost first. Callers are at the call they are waiting on
+	pub fn backtrace(&self) -> Vec<StackFrame> {
+		let mut backtrace = vec![StackFrame {
+			function: self.function_name(self.function),
+			span: self.spans.get(self.instruction_index).copied().flatten(),
+		}];
+
+		for frame in self.frames.iter().rev() {
+			backtrace.push(StackFrame {
+				function: self.function_name(frame.function),
+				span: frame.spans.get(frame.instruction_index).copied().flatten(),
+			});
+		}
+
+		return backtrace;
+	}
+
+	/// Number of frames below the function that is running
+	pub fn call_depth(&self) -> usize {
+		self.frames.len()
+	}
+
+	/// Function that is running, `None` for global scopes
+	pub fn current_function(&self) -> Option<FunctionHandle> {
+		self.function
+	}
+
+	/// Source span of the instruction that runs next
+	pub fn current_span(&self) -> Option<SourceSpan> {
+		self.spans.get(self.instruction_index).copied().flatten()
+	}
+
+	/// Value in a stack slot of the running function's frame, if the frame has that many slots
+	pub fn local(&self, slot: usize) -> Option<&Value> {
+		let index = self.stack_base + slot;
+		if index >= self.stack_pointer {
+			return None;
+		}
+
+		self.stack.get(index)
+	}
+
+	fn function_name(&self, function: Option<FunctionHandle>) -> Option<String> {
+		function.and_then(|function| {
+			self.functions
+				.get(function)
+				.map(|function| function.name().to_string())
+		})
+	}
+
+	/// Continues execution at the innermost try block's catch unit, with the exception on top of
+	/// the stack. Exceptions that are not caught stop the interpreter
+	fn throw(&mut self, value: Value) {
+		if self.handlers.len() <= self.handler_base {
+			self.uncaught_exception = Some(value);
+			return;
+		}
+
+		let handler = self
+			.handlers
+			.pop()
+			.expect("Could not pop exception handler");
+		self.frames.truncate(handler.frame_count);
+		self.function = handler.function;
+		self.instruction_index = handler.instruction_index;
+		self.instructions = handler.instructions;
+		self.spans = handler.spans;
+		self.stack_base = handler.stack_base;
+		self.stack_pointer = handler.stack_pointer;
+
+		self.push_stack(value);
+	}
+
 	pub fn print_stack(&self) {
 		self.print_stack_region(0, self.stack_pointer);
 	}
@@ -93,9 +558,703 @@ impl Interpreter {
 	}
 
 	pub fn add_function(&mut self, function: Function) {
+		self.function_table.push(self.functions.len());
 		self.functions.push(function);
 	}
 
+	pub fn add_package(&mut self, package: Package) {
+		self.packages.add_package(package);
+		self.rebind_functions();
+	}
+
+	pub fn activate_package(&mut self, name: &str) -> Result<()> {
+		self.packages.activate(name)?;
+		self.rebind_functions();
+		Ok(())
+	}
+
+	pub fn deactivate_package(&mut self, name: &str) -> Result<()> {
+		self.packages.deactivate(name)?;
+		self.rebind_functions();
+		Ok(())
+	}
+
+	fn rebind_functions(&mut self) {
+		let bindings = self.packages.bindings();
+		for (overridden, function) in bindings.current.into_iter() {
+			self.function_table[overridden] = function;
+		}
+
+		self.parent_table = bindings.parent;
+	}
+
+	/// Sets the compiler used to load code while the interpreter is running
+	pub fn set_compiler<T: SourceCompiler + 'static>(&mut self, compiler: T) {
+		self.compiler = Some(Rc::new(RefCell::new(compiler)));
+	}
+
+	/// Compiles source code, adds the functions it declares and runs its global scope to completion.
+	/// Functions that were loaded this way can be called by code that is loaded afterwards
+	pub fn exec(&mut self, contents: &str, file_name: &str) -> Result<()> {
+		let compiler = self
+			.compiler
+			.clone()
+			.context("Interpreter does not have a compiler")?;
+
+		let CompiledSource {
+			functions,
+			instructions,
+			packages,
+			spans,
+		} = compiler.borrow_mut().compile(contents, file_name)?;
+
+		for function in functions.into_iter() {
+			self.add_function(function);
+		}
+
+		for package in packages.into_iter() {
+			self.add_package(package);
+		}
+
+		self.run_nested(instructions, spans)?;
+
+		Ok(())
+	}
+
+	/// Sets the types `call` checks the arguments of a function against
+	pub fn set_argument_types(&mut self, function: FunctionHandle, argument_types: Vec<ValueType>) {
+		self.argument_types.insert(function, argument_types);
+	}
+
+	/// Handle of the function scripts call by a name
+	pub fn function_handle(&self, name: &str) -> Option<FunctionHandle> {
+		self.functions
+			.iter()
+			.position(|function| function.name() == name)
+	}
+
+	/// Calls a function from the host and runs it to completion, like a callback such as
+	/// `onTick(%dt)` would be. Calls go to the function packages bind the name to. Pauses made by
+	/// the function are ignored, and exceptions it does not catch are returned as errors. Functions
+	/// that wait for an async native return null, and continue when the host fulfills the token
+	pub fn call<'a>(
+		&mut self,
+		function: impl Into<FunctionReference<'a>>,
+		arguments: &[Value],
+	) -> Result<Value, RuntimeError> {
+		let handle = match function.into() {
+			FunctionReference::Handle(handle) => handle,
+			FunctionReference::Name(name) => self.function_handle(name).ok_or_else(|| {
+				self.runtime_error(RuntimeErrorKind::InvalidCall(format!(
+					"Unknown function '{}'",
+					name
+				)))
+			})?,
+		};
+
+		self.check_call_arguments(handle, arguments)
+			.map_err(|kind| self.runtime_error(kind))?;
+
+		let mut instructions = arguments
+			.iter()
+			.map(|argument| Instruction::Push(argument.clone()))
+			.collect::<Vec<Instruction>>();
+		instructions.push(Instruction::CallFunction(handle, arguments.len()));
+		let spans = vec![None; instructions.len()];
+
+		let pause = self.pause.take();
+		let values = self.run_nested(instructions, spans);
+		self.pause = pause;
+		let mut values = values?;
+
+		if let Some(exception) = self.uncaught_exception.take() {
+			return Err(
+				self.runtime_error(RuntimeErrorKind::UncaughtException(exception.to_string()))
+			);
+		}
+
+		match values.len() {
+			0 => Ok(Value::Null),
+			1 => Ok(values.pop().expect("Could not pop return value")),
+			count => Err(self.runtime_error(RuntimeErrorKind::InvalidCall(format!(
+				"'{}' returned {} values",
+				self.function_name(Some(handle)).unwrap_or_default(),
+				count
+			)))),
+		}
+	}
+
+	/// Calls that scripts scheduled, and the simulated time
+	pub fn scheduler(&mut self) -> &mut Scheduler {
+		&mut self.scheduler
+	}
+
+	/// Moves the simulated time forward, and calls every scheduled function that is due in the
+	/// order they are due in. Stops at the first call that fails
+	pub fn advance_time(&mut self, ms: u64) -> Result<(), RuntimeError> {
+		let until = self.scheduler.time().saturating_add(ms);
+		while let Some(scheduled_call) = self.scheduler.next_due(until) {
+			self.call(scheduled_call.function.as_str(), &scheduled_call.arguments)?;
+		}
+
+		self.scheduler.set_time(until);
+
+		Ok(())
+	}
+
+	/// Saves the state of the program, so an interpreter created from the same program can
+	/// continue from here with `restore`. The program cannot be in the middle of nested code, like
+	/// a call from the host, and no nested code can be waiting for an async native
+	pub fn snapshot(&self) -> Result<Vec<u8>, SnapshotError> {
+		if self.frame_base.is_some() {
+			return Err(SnapshotError::CannotSnapshot(
+				"while nested code is running",
+			));
+		}
+
+		if !self.coroutines.is_empty() {
+			return Err(SnapshotError::CannotSnapshot(
+				"while nested code waits for an async native",
+			));
+		}
+
+		let mut writer = SnapshotWriter::new();
+
+		// every function is listed, since instructions refer to functions by their handle
+		writer.write_u64(code_hash(&self.global_instructions));
+		writer.write_usize(self.functions.len());
+		for function in self.functions.iter() {
+			writer.write_string(function.name());
+			writer.write_u64(function_hash(function));
+		}
+
+		writer.write_usize(self.packages.active_packages().len());
+		for name in self.packages.active_packages().iter() {
+			writer.write_string(name);
+		}
+
+		writer.write_usize(self.stack_pointer);
+		for value in self.stack[..self.stack_pointer].iter() {
+			writer.write_value(value);
+		}
+
+		writer.write_usize(self.stack_base);
+		self.write_code(
+			&mut writer,
+			self.function,
+			&self.instructions,
+			self.instruction_index,
+		)?;
+
+		writer.write_usize(self.frames.len());
+		for frame in self.frames.iter() {
+			writer.write_usize(frame.argument_count);
+			self.write_code(
+				&mut writer,
+				frame.function,
+				&frame.instructions,
+				frame.instruction_index,
+			)?;
+			writer.write_usize(frame.stack_base);
+			writer.write_usize(frame.stack_pointer);
+		}
+
+		writer.write_usize(self.handlers.len());
+		for handler in self.handlers.iter() {
+			writer.write_usize(handler.frame_count);
+			self.write_code(
+				&mut writer,
+				handler.function,
+				&handler.instructions,
+				handler.instruction_index,
+			)?;
+			writer.write_usize(handler.stack_base);
+			writer.write_usize(handler.stack_pointer);
+		}
+
+		match &self.uncaught_exception {
+			Some(exception) => {
+				writer.write_u8(1);
+				writer.write_value(exception);
+			}
+			None => writer.write_u8(0),
+		}
+
+		match self.waiting {
+			Some(token) => {
+				writer.write_u8(1);
+				writer.write_usize(token);
+			}
+			None => writer.write_u8(0),
+		}
+		writer.write_usize(self.next_pending_token);
+
+		writer.write_u64(self.scheduler.time());
+		writer.write_usize(self.scheduler.last_id());
+		writer.write_usize(self.scheduler.pending_calls().count());
+		for call in self.scheduler.pending_calls() {
+			writer.write_usize(call.id);
+			writer.write_u64(call.time);
+			writer.write_string(&call.function);
+			writer.write_usize(call.arguments.len());
+			for argument in call.arguments.iter() {
+				writer.write_value(argument);
+			}
+		}
+
+		return Ok(writer.finish());
+	}
+
+	/// Continues from a snapshot of an interpreter created from the same program. Fails without
+	/// changing the interpreter if the snapshot is broken or the program was compiled from
+	/// different source. Natives, argument types, the sandbox and the stack limits stay as they are
+	pub fn restore(&mut self, snapshot: &[u8]) -> Result<(), SnapshotError> {
+		let mut reader = SnapshotReader::new(snapshot)?;
+
+		if reader.read_u64()? != code_hash(&self.global_instructions) {
+			return Err(SnapshotError::SourceChanged(GLOBAL_SCOPE_NAME.into()));
+		}
+
+		for index in 0..reader.read_count()? {
+			let name = reader.read_string()?;
+			let hash = reader.read_u64()?;
+			let Some(function) = self.functions.get(index) else {
+				return Err(SnapshotError::MissingFunction(name));
+			};
+
+			if function.name() != name || function_hash(function) != hash {
+				if self.function_handle(&name).is_none() {
+					return Err(SnapshotError::MissingFunction(name));
+				}
+
+				return Err(SnapshotError::SourceChanged(name));
+			}
+		}
+
+		let mut packages = vec![];
+		for _ in 0..reader.read_count()? {
+			let name = reader.read_string()?;
+			if !self
+				.packages
+				.packages()
+				.iter()
+				.any(|package| package.name == name)
+			{
+				return Err(SnapshotError::MissingPackage(name));
+			}
+
+			packages.push(name);
+		}
+
+		let stack_pointer = reader.read_count()?;
+		let mut stack = Vec::with_capacity(stack_pointer + 1);
+		for _ in 0..stack_pointer {
+			stack.push(reader.read_value()?);
+		}
+
+		// the stack never shrinks to nothing, see `stack_resize`
+		stack.push(Value::Null);
+
+		let check_stack = |position: usize| {
+			if position > stack_pointer {
+				return Err(SnapshotError::Corrupt(format!(
+					"stack position {} is past the top of the stack",
+					position
+				)));
+			}
+
+			Ok(position)
+		};
+
+		let stack_base = check_stack(reader.read_usize()?)?;
+		let code = self.read_code(&mut reader)?;
+
+		let mut frames = vec![];
+		for _ in 0..reader.read_count()? {
+			let argument_count = reader.read_usize()?;
+			let code = self.read_code(&mut reader)?;
+			frames.push(InterpreterFrame {
+				argument_count,
+				function: code.function,
+				instruction_index: code.instruction_index,
+				instructions: code.instructions,
+				spans: code.spans,
+				stack_base: check_stack(reader.read_usize()?)?,
+				stack_pointer: check_stack(reader.read_usize()?)?,
+			});
+		}
+
+		let mut handlers = vec![];
+		for _ in 0..reader.read_count()? {
+			let frame_count = reader.read_usize()?;
+			if frame_count > frames.len() {
+				return Err(SnapshotError::Corrupt(format!(
+					"exception handler refers to {} frames, there are {}",
+					frame_count,
+					frames.len()
+				)));
+			}
+
+			let code = self.read_code(&mut reader)?;
+			handlers.push(ExceptionHandler {
+				frame_count,
+				function: code.function,
+				instruction_index: code.instruction_index,
+				instructions: code.instructions,
+				spans: code.spans,
+				stack_base: check_stack(reader.read_usize()?)?,
+				stack_pointer: check_stack(reader.read_usize()?)?,
+			});
+		}
+
+		let uncaught_exception = match reader.read_u8()? {
+			0 => None,
+			_ => Some(reader.read_value()?),
+		};
+
+		let waiting = match reader.read_u8()? {
+			0 => None,
+			_ => Some(reader.read_usize()?),
+		};
+		let next_pending_token = reader.read_usize()?;
+
+		let time = reader.read_u64()?;
+		let last_id = reader.read_usize()?;
+		let mut calls = vec![];
+		for _ in 0..reader.read_count()? {
+			let id = reader.read_usize()?;
+			let time = reader.read_u64()?;
+			let function = reader.read_string()?;
+
+			let mut arguments = vec![];
+			for _ in 0..reader.read_count()? {
+				arguments.push(reader.read_value()?);
+			}
+
+			calls.push(ScheduledCall {
+				arguments,
+				function,
+				id,
+				time,
+			});
+		}
+
+		reader.finish()?;
+
+		for name in self.packages.active_packages().to_vec().iter().rev() {
+			self.packages
+				.deactivate(name)
+				.expect("Could not deactivate package");
+		}
+
+		for name in packages.iter() {
+			self.packages
+				.activate(name)
+				.expect("Could not activate package");
+		}
+		self.rebind_functions();
+
+		self.coroutines.clear();
+		self.frame_base = None;
+		self.frames = frames;
+		self.function = code.function;
+		self.handler_base = 0;
+		self.handlers = handlers;
+		self.instruction_index = code.instruction_index;
+		self.instructions = code.instructions;
+		self.next_pending_token = next_pending_token;
+		self.pause = None;
+		self.pending_token = None;
+		self.scheduler = Scheduler::restore(time, last_id, calls);
+		self.spans = code.spans;
+		self.stack = stack;
+		self.stack_base = stack_base;
+		self.stack_pointer = stack_pointer;
+		self.uncaught_exception = uncaught_exception;
+		self.waiting = waiting;
+
+		Ok(())
+	}
+
+	/// Writes the code a frame runs as the name and hash of its function, since the instructions
+	/// are only the same in the interpreter that takes the snapshot, followed by where in the code
+	/// the frame is
+	fn write_code(
+		&self,
+		writer: &mut SnapshotWriter,
+		function: Option<FunctionHandle>,
+		instructions: &Rc<Vec<Instruction>>,
+		instruction_index: usize,
+	) -> Result<(), SnapshotError> {
+		let is_program_code = match function.map(|function| &self.functions[function]) {
+			Some(Function::Eggscript {
+				instructions: function_instructions,
+				..
+			}) => Rc::ptr_eq(instructions, function_instructions),
+			Some(Function::Native { .. }) => false,
+			None => Rc::ptr_eq(instructions, &self.global_instructions),
+		};
+
+		if !is_program_code {
+			return Err(SnapshotError::CannotSnapshot(
+				"while code that is not part of the program is running",
+			));
+		}
+
+		match function.map(|function| &self.functions[function]) {
+			Some(function) => {
+				writer.write_u8(1);
+				writer.write_string(function.name());
+				writer.write_u64(function_hash(function));
+			}
+			None => writer.write_u8(0),
+		}
+
+		writer.write_usize(instruction_index);
+		Ok(())
+	}
+
+	/// Finds the code `write_code` wrote in this interpreter
+	fn read_code(&self, reader: &mut SnapshotReader) -> Result<SavedCode, SnapshotError> {
+		let (function, instructions, spans) = match reader.read_u8()? {
+			0 => (
+				None,
+				self.global_instructions.clone(),
+				self.global_spans.clone(),
+			),
+			_ => {
+				let name = reader.read_string()?;
+				let hash = reader.read_u64()?;
+				let handle = self
+					.function_handle(&name)
+					.ok_or_else(|| SnapshotError::MissingFunction(name.clone()))?;
+
+				let function = &self.functions[handle];
+				let Function::Eggscript {
+					instructions,
+					spans,
+					..
+				} = function
+				else {
+					return Err(SnapshotError::Corrupt(format!(
+						"'{}' is a native, natives do not run in frames",
+						name
+					)));
+				};
+
+				if function_hash(function) != hash {
+					return Err(SnapshotError::SourceChanged(name));
+				}
+
+				(Some(handle), instructions.clone(), spans.clone())
+			}
+		};
+
+		// code that finished is at the index right after its last instruction
+		let instruction_index = reader.read_usize()?;
+		if instruction_index > instructions.len() {
+			return Err(SnapshotError::Corrupt(format!(
+				"instruction {} is past the end of '{}'",
+				instruction_index,
+				self.function_name(function)
+					.unwrap_or_else(|| GLOBAL_SCOPE_NAME.into())
+			)));
+		}
+
+		return Ok(SavedCode {
+			function,
+			instruction_index,
+			instructions,
+			spans,
+		});
+	}
+
+	fn check_call_arguments(
+		&self,
+		handle: FunctionHandle,
+		arguments: &[Value],
+	) -> Result<(), RuntimeErrorKind> {
+		let function = *self
+			.function_table
+			.get(handle)
+			.ok_or(RuntimeErrorKind::UnknownFunction(handle))?;
+
+		let name = self.function_name(Some(handle)).unwrap_or_default();
+
+		// natives can be variadic, so only script functions have a fixed number of arguments
+		if let Some(Function::Eggscript { argument_count, .. }) = self.functions.get(function)
+			&& *argument_count != arguments.len()
+		{
+			return Err(RuntimeErrorKind::InvalidCall(format!(
+				"'{}' takes {} arguments, got {}",
+				name,
+				argument_count,
+				arguments.len()
+			)));
+		}
+
+		let Some(argument_types) = self.argument_types.get(&handle) else {
+			return Ok(());
+		};
+
+		check_argument_types(&name, argument_types, arguments)
+			.map_err(RuntimeErrorKind::InvalidCall)
+	}
+
+	/// Runs instructions as if they were the body of a function called by the current instruction.
+	/// Exceptions the instructions do not catch are left in `uncaught_exception` for the caller.
+	/// Nested code cannot be paused, so it pauses the program once it finishes. Returns the values
+	/// the instructions left on the stack
+	fn run_nested(
+		&mut self,
+		instructions: Vec<Instruction>,
+		spans: Vec<Option<SourceSpan>>,
+	) -> Result<Vec<Value>, RuntimeError> {
+		self.run_coroutine(Coroutine {
+			frames: vec![],
+			function: None,
+			handlers: vec![],
+			instruction_index: 0,
+			instructions: Rc::new(instructions),
+			spans: Rc::new(spans),
+			stack: vec![],
+			stack_base: 0,
+		})
+	}
+
+	/// Runs nested code like `run_nested`, starting from where the coroutine stopped
+	fn run_coroutine(&mut self, coroutine: Coroutine) -> Result<Vec<Value>, RuntimeError> {
+		let frame_count = self.frames.len();
+		let frame_base = self.frame_base.replace(frame_count);
+		let handler_base = self.handler_base;
+		self.handler_base = self.handlers.len();
+
+		self.frames.push(InterpreterFrame {
+			argument_count: 0,
+			function: self.function,
+			instruction_index: self.instruction_index,
+			instructions: self.instructions.clone(),
+			spans: self.spans.clone(),
+			stack_base: self.stack_base,
+			stack_pointer: self.stack_pointer,
+		});
+
+		let bottom = self.stack_pointer;
+		for value in coroutine.stack.into_iter() {
+			self.push_stack(value);
+		}
+
+		self.frames.extend(
+			coroutine
+				.frames
+				.into_iter()
+				.map(|frame| frame.rebase(0, bottom)),
+		);
+		self.handlers.extend(
+			coroutine
+				.handlers
+				.into_iter()
+				.map(|handler| handler.rebase((0, 0), (frame_count + 1, bottom))),
+		);
+
+		self.function = coroutine.function;
+		self.instruction_index = coroutine.instruction_index;
+		self.instructions = coroutine.instructions;
+		self.spans = coroutine.spans;
+		self.stack_base = bottom + coroutine.stack_base;
+
+		let mut values = vec![];
+		let mut error = None;
+		while self.frames.len() > frame_count {
+			// global scopes do not end in a return instruction, so restore the frame once we run
+			// out of instructions. uncaught exceptions unwind every frame the nested code entered
+			if self.instruction_index < self.instructions.len() && self.uncaught_exception.is_none()
+			{
+				match self.interpret() {
+					Ok(()) => continue,
+					// errors unwind the nested code too, so the code that ran it can keep running
+					Err(kind) => error = Some(self.runtime_error(kind)),
+				}
+			}
+
+			self.frames.truncate(frame_count + 1);
+
+			if self.uncaught_exception.is_none() && error.is_none() {
+				values = self.stack[self.stack_base..self.stack_pointer].to_vec();
+			}
+
+			let old_frame = self.frames.pop().expect("Could not pop old frame");
+			self.function = old_frame.function;
+			self.instruction_index = old_frame.instruction_index;
+			self.instructions = old_frame.instructions;
+			self.spans = old_frame.spans;
+			self.stack_pointer = old_frame.stack_pointer;
+			self.stack_base = old_frame.stack_base;
+		}
+
+		self.handlers.truncate(self.handler_base);
+		self.handler_base = handler_base;
+		self.frame_base = frame_base;
+
+		match error {
+			Some(error) => Err(error),
+			None => Ok(values),
+		}
+	}
+
+	fn map_operation(operation: MapOperation, operands: &[Value]) -> Result<Option<Value>> {
+		if operation == MapOperation::New {
+			let entries = operands.chunks_exact(2);
+			if !entries.remainder().is_empty() {
+				return Err(RuntimeErrorKind::OddMapOperands(operands.len()).into());
+			}
+
+			let mut map = Map::new();
+			for entry in entries {
+				map::map_set(&mut map, &entry[0], entry[1].clone())?;
+			}
+
+			return Ok(Some(Value::Map(Rc::new(RefCell::new(map)))));
+		}
+
+		let map = match operands.get(0) {
+			Some(Value::Map(map)) => map,
+			Some(value) => {
+				return Err(RuntimeErrorKind::TypeMismatch {
+					expected: "map",
+					found: value.type_name(),
+				}
+				.into());
+			}
+			None => return Err(RuntimeErrorKind::StackUnderflow.into()),
+		};
+
+		let operand = |index: usize| {
+			operands
+				.get(index)
+				.context("Could not get map operation operand")
+		};
+
+		let result = match operation {
+			MapOperation::New => unreachable!(),
+			MapOperation::Get => map::map_get(&map.borrow(), operand(1)?)?,
+			MapOperation::Set => {
+				map::map_set(&mut map.borrow_mut(), operand(1)?, operand(2)?.clone())?;
+				return Ok(None);
+			}
+			MapOperation::Contains => {
+				Value::Number(map::map_contains(&map.borrow(), operand(1)?)? as u8 as f64)
+			}
+			MapOperation::Remove => {
+				Value::Number(map::map_remove(&mut map.borrow_mut(), operand(1)?)? as u8 as f64)
+			}
+			MapOperation::Count => Value::Number(map.borrow().len() as f64),
+			MapOperation::KeyAt => map::map_key_at(&map.borrow(), operand(1)?.as_number()?)?,
+		};
+
+		Ok(Some(result))
+	}
+
 	pub fn number_math(&mut self, operator: NumberMathOperation, lvalue: f64, rvalue: f64) {
 		match operator {
 			NumberMathOperation::Plus => self.push_stack(Value::Number(lvalue + rvalue)),
@@ -139,161 +1298,309 @@ impl Interpreter {
 		}
 	}
 
-	fn interpret(&mut self) {
-		let instruction = &self.instructions[self.instruction_index];
+	/// Returns true if the call entered a new frame or threw an exception, in which case the
+	/// instruction index must not be advanced
+	fn call_function(
+		&mut self,
+		function_handle: FunctionHandle,
+		call_argument_count: usize,
+	) -> Result<bool, RuntimeErrorKind> {
+		let function = self
+			.functions
+			.get(function_handle)
+			.ok_or(RuntimeErrorKind::UnknownFunction(function_handle))?;
 
-		match instruction {
-			Instruction::NumberMath(operator, lvalue, rvalue) => {
-				let rvalue = stack_extract!(self, *rvalue);
-				let lvalue = stack_extract!(self, *lvalue);
+		match function {
+			Function::Eggscript {
+				argument_count,
+				instructions,
+				spans,
+				..
+			} => {
+				let stack_base = self
+					.stack_pointer
+					.checked_sub(*argument_count)
+					.ok_or(RuntimeErrorKind::StackUnderflow)?;
 
-				let Value::Number(lvalue) = lvalue else {
-					unreachable!();
-				};
+				// runaway recursion is raised as an exception, so scripts can recover from it
+				if self.frames.len() >= self.max_call_depth
+					|| self.stack_pointer >= self.max_stack_size
+				{
+					self.throw(Value::String(Rc::new(STACK_OVERFLOW.to_string())));
+					return Ok(true);
+				}
 
-				let Value::Number(rvalue) = rvalue else {
-					unreachable!();
-				};
+				self.frames.push(InterpreterFrame {
+					argument_count: *argument_count,
+					function: self.function,
+					instruction_index: self.instruction_index,
+					instructions: self.instructions.clone(),
+					spans: self.spans.clone(),
+					stack_base: self.stack_base,
+					stack_pointer: self.stack_pointer,
+				});
+
+				self.function = Some(function_handle);
+				self.instruction_index = 0;
+				self.instructions = instructions.clone();
+				self.spans = spans.clone();
+				self.stack_base = stack_base;
 
-				self.number_math(*operator, *lvalue, *rvalue);
+				return Ok(true);
 			}
-			Instruction::ImmediateNumberMath(operator, lvalue, rvalue) => {
-				let rvalue = stack_extract!(self, *rvalue);
+			Function::Native { function, name, .. } => {
+				let function = function.clone();
+				let name = name.clone();
 
-				let Value::Number(lvalue) = lvalue else {
-					unreachable!();
-				};
+				if !self.allows_native(&name) {
+					return Err(RuntimeErrorKind::NativeNotAllowed(name));
+				}
 
-				let Value::Number(rvalue) = rvalue else {
-					unreachable!();
-				};
+				// variadic natives take however many arguments the call site pushed
+				let mut arguments = vec![];
+				for _ in 0..call_argument_count {
+					arguments.push(pop_stack(&self.stack, &mut self.stack_pointer)?.clone());
+				}
+
+				arguments.reverse();
+
+				// a native that panics must not take the application embedding the interpreter
+				// down with it
+				let result =
+					std::panic::catch_unwind(AssertUnwindSafe(|| function(self, arguments)))
+						.map_err(|payload| {
+							let message = payload
+								.downcast_ref::<&str>()
+								.map(|message| message.to_string())
+								.or_else(|| payload.downcast_ref::<String>().cloned())
+								.unwrap_or_default();
+
+							RuntimeErrorKind::NativePanic(name, message)
+						})?;
+
+				// async natives give their result to the host, which hands it back later
+				let pending_token = self.pending_token.take();
+				if result.is_ok()
+					&& let Some(token) = pending_token
+				{
+					return Ok(self.wait_for(token));
+				}
+
+				match result {
+					Ok(result) => self.push_stack(result),
+					Err(error) => {
+						self.throw_error(error)?;
+						return Ok(true);
+					}
+				}
+
+				// natives that run code, like exec, pass on exceptions that code did not catch
+				if let Some(exception) = self.uncaught_exception.take() {
+					self.throw(exception);
+					return Ok(true);
+				}
 
-				self.number_math(*operator, *lvalue, *rvalue);
+				return Ok(false);
 			}
-			Instruction::Invalid => panic!("Invalid instruction"),
+		}
+	}
+
+	/// Raises errors as exceptions that scripts can catch, except for runtime errors, which stop
+	/// the interpreter
+	fn throw_error(&mut self, error: anyhow::Error) -> Result<(), RuntimeErrorKind> {
+		if let Some(error) = error.downcast_ref::<RuntimeError>() {
+			return Err(error.kind.clone());
+		}
+
+		if let Some(kind) = error.downcast_ref::<RuntimeErrorKind>() {
+			return Err(kind.clone());
+		}
+
+		self.throw(Value::String(Rc::new(error.to_string())));
+
+		Ok(())
+	}
+
+	fn jump(&mut self, position: isize) -> Result<(), RuntimeErrorKind> {
+		self.instruction_index = self.jump_target(position)?;
+		Ok(())
+	}
+
+	fn jump_target(&self, position: isize) -> Result<usize, RuntimeErrorKind> {
+		self.instruction_index
+			.checked_add_signed(position)
+			.ok_or(RuntimeErrorKind::InvalidJump(position))
+	}
+
+	fn interpret(&mut self) -> Result<(), RuntimeErrorKind> {
+		if let Some(sandbox) = &mut self.sandbox {
+			let max_memory = sandbox.charge().map_err(RuntimeErrorKind::SandboxLimit)?;
+			if let Some(max_memory) = max_memory
+				&& self.memory_usage() > max_memory
+			{
+				return Err(RuntimeErrorKind::SandboxLimit(SandboxLimit::Memory));
+			}
+		}
+
+		let instruction = &self.instructions[self.instruction_index];
+
+		// nothing of the instruction ran yet, so the program can be resumed from it
+		if is_interrupt_point(instruction) && self.interrupt.take() {
+			return Err(RuntimeErrorKind::Interrupted);
+		}
+
+		match instruction {
+			Instruction::NumberMath(operator, lvalue, rvalue) => {
+				let rvalue = stack_extract!(self, *rvalue)?.as_number()?;
+				let lvalue = stack_extract!(self, *lvalue)?.as_number()?;
+
+				self.number_math(*operator, lvalue, rvalue);
+			}
+			Instruction::ImmediateNumberMath(operator, lvalue, rvalue) => {
+				let rvalue = stack_extract!(self, *rvalue)?.as_number()?;
+				let lvalue = lvalue.as_number()?;
+
+				self.number_math(*operator, lvalue, rvalue);
+			}
+			Instruction::Invalid => return Err(RuntimeErrorKind::InvalidInstruction),
 			Instruction::Noop => {}
 			Instruction::Push(value) => {
 				self.push_stack(value.clone());
 			}
 			Instruction::CopyPush(position) => {
-				self.push_stack(self.stack[self.stack_base + *position].clone());
+				let value = self
+					.stack
+					.get(self.stack_base + *position)
+					.ok_or(RuntimeErrorKind::InvalidStackAddress(*position as isize))?
+					.clone();
+
+				self.push_stack(value);
 			}
 			Instruction::RestorePop => {
 				self.stack_pointer += 1;
 			}
 			Instruction::Pop => {
-				pop_stack(&self.stack, &mut self.stack_pointer);
+				pop_stack(&self.stack, &mut self.stack_pointer)?;
 			}
 			Instruction::Store(index, position) => {
-				let value = stack_extract!(self, *position);
-				self.stack[self.stack_base + *index] = value.clone();
+				let value = stack_extract!(self, *position)?.clone();
+				let slot = self
+					.stack
+					.get_mut(self.stack_base + *index)
+					.ok_or(RuntimeErrorKind::InvalidStackAddress(*index as isize))?;
+
+				*slot = value;
 			}
 			Instruction::Reserve(amount) => {
 				self.stack_pointer += amount;
 				stack_resize!(self);
 			}
 			Instruction::Jump(position) => {
-				self.instruction_index = self
-					.instruction_index
-					.checked_add_signed(*position)
-					.expect("Failed relative jump");
-				return;
+				self.jump(*position)?;
+				return Ok(());
 			}
 			Instruction::JumpIfFalse(position, value_position) => {
-				let value = stack_extract!(self, *value_position);
+				let value = stack_extract!(self, *value_position)?;
 				if let Value::Number(number) = value
 					&& number == &0.0
 				{
-					self.instruction_index = self
-						.instruction_index
-						.checked_add_signed(*position)
-						.expect("Failed relative jump");
-
-					return;
+					self.jump(*position)?;
+					return Ok(());
 				}
 			}
 			Instruction::JumpIfTrue(position, value_position) => {
-				let value = stack_extract!(self, *value_position);
+				let value = stack_extract!(self, *value_position)?;
 				if let Value::Number(number) = value
 					&& number != &0.0
 				{
-					self.instruction_index = self
-						.instruction_index
-						.checked_add_signed(*position)
-						.expect("Failed relative jump");
+					self.jump(*position)?;
+					return Ok(());
+				}
+			}
+			Instruction::CallFunction(function_handle, call_argument_count) => {
+				let function = *self
+					.function_table
+					.get(*function_handle)
+					.ok_or(RuntimeErrorKind::UnknownFunction(*function_handle))?;
 
-					return;
+				if self.call_function(function, *call_argument_count)? {
+					return Ok(());
 				}
 			}
-			Instruction::CallFunction(function_handle) => {
-				let function = &self.functions[*function_handle];
-				match function {
-					Function::Eggscript {
-						argument_count,
-						instructions,
-						..
-					} => {
-						self.frames.push(InterpreterFrame {
-							argument_count: *argument_count,
-							instruction_index: self.instruction_index,
-							instructions: self.instructions.clone(),
-							stack_base: self.stack_base,
-							stack_pointer: self.stack_pointer,
-						});
-
-						self.instruction_index = 0;
-						self.instructions = instructions.clone();
-						self.stack_base = self.stack_pointer - *argument_count;
-
-						return;
-					}
-					Function::Native {
-						argument_count,
-						function,
-						..
-					} => {
-						let mut arguments = vec![];
-						for _ in 0..*argument_count {
-							arguments.push(pop_stack(&self.stack, &mut self.stack_pointer).clone());
-						}
+			Instruction::CallParentFunction(function_handle, call_argument_count) => {
+				let parent = *self
+					.parent_table
+					.get(function_handle)
+					.ok_or(RuntimeErrorKind::UnknownFunction(*function_handle))?;
 
-						self.push_stack(function(arguments));
-					}
+				if self.call_function(parent, *call_argument_count)? {
+					return Ok(());
 				}
 			}
-			Instruction::Return(has_value) => {
-				let value = if *has_value {
-					Some(pop_stack(&self.stack, &mut self.stack_pointer))
-				} else {
-					None
-				};
+			Instruction::Return(value_count) => {
+				let mut values = vec![];
+				for _ in 0..*value_count {
+					values.push(pop_stack(&self.stack, &mut self.stack_pointer)?.clone());
+				}
 
-				let old_frame = self.frames.pop().expect("Could not pop old frame");
+				let old_frame = self.frames.pop().ok_or(RuntimeErrorKind::MissingFrame)?;
+				self.function = old_frame.function;
 				self.instruction_index = old_frame.instruction_index;
 				self.instructions = old_frame.instructions;
-				self.stack_pointer = old_frame.stack_pointer - old_frame.argument_count;
+				self.spans = old_frame.spans;
+				self.stack_pointer = old_frame
+					.stack_pointer
+					.checked_sub(old_frame.argument_count)
+					.ok_or(RuntimeErrorKind::StackUnderflow)?;
 				self.stack_base = old_frame.stack_base;
 
-				if let Some(value) = value {
-					self.push_stack(value.clone());
+				// returning from inside a try block leaves its handler behind
+				while let Some(handler) = self.handlers.last()
+					&& handler.frame_count > self.frames.len()
+				{
+					self.handlers.pop();
+				}
+
+				// values were popped last to first, push them back in their original order
+				for value in values.into_iter().rev() {
+					self.push_stack(value);
 				}
 			}
+			Instruction::PushCatch(position) => {
+				self.handlers.push(ExceptionHandler {
+					frame_count: self.frames.len(),
+					function: self.function,
+					instruction_index: self.jump_target(*position)?,
+					instructions: self.instructions.clone(),
+					spans: self.spans.clone(),
+					stack_base: self.stack_base,
+					stack_pointer: self.stack_pointer,
+				});
+			}
+			Instruction::PopCatch => {
+				self.handlers.pop();
+			}
+			Instruction::Throw(value_position) => {
+				let value = stack_extract!(self, *value_position)?.clone();
+				self.throw(value);
+				return Ok(());
+			}
+			Instruction::Yield(value_position) => {
+				let value = stack_extract!(self, *value_position)?.clone();
+				self.pause = Some(RunStatus::Yielded(value));
+			}
 			Instruction::NumberUnary(operator, value_position) => {
-				let value = stack_extract!(self, *value_position);
-
-				let Value::Number(value) = value else {
-					unreachable!();
-				};
+				let value = stack_extract!(self, *value_position)?.as_number()?;
 
 				match operator {
 					NumberUnaryOperation::BitwiseNot => {
-						self.push_stack(Value::Number(!(*value as i64) as f64));
+						self.push_stack(Value::Number(!(value as i64) as f64));
 					}
 					NumberUnaryOperation::Minus => {
 						self.push_stack(Value::Number(-value));
 					}
 					NumberUnaryOperation::Not => {
-						if value == &0.0 {
+						if value == 0.0 {
 							self.push_stack(Value::Number(1.0));
 						} else {
 							self.push_stack(Value::Number(0.0));
@@ -301,8 +1608,51 @@ impl Interpreter {
 					}
 				}
 			}
+			Instruction::IsNull(value_position) => {
+				let value = stack_extract!(self, *value_position)?;
+				let is_null = matches!(value, Value::Null);
+
+				self.push_stack(Value::Number(is_null as u8 as f64));
+			}
+			Instruction::ConvertToNumber(value_position) => {
+				let value = stack_extract!(self, *value_position)?;
+				match any_to_number(value) {
+					Ok(number) => self.push_stack(Value::Number(number)),
+					Err(error) => {
+						self.throw_error(error)?;
+						return Ok(());
+					}
+				}
+			}
+			Instruction::ConvertToString(value_position) => {
+				let value = stack_extract!(self, *value_position)?;
+				match any_to_string(value) {
+					Ok(string) => self.push_stack(Value::String(string)),
+					Err(error) => {
+						self.throw_error(error)?;
+						return Ok(());
+					}
+				}
+			}
+			Instruction::MapOperation(operation, operand_positions) => {
+				// operands at the top of the stack are popped in reverse order
+				let mut operands = vec![];
+				for position in operand_positions.iter().rev() {
+					operands.push(stack_extract!(self, *position)?.clone());
+				}
+				operands.reverse();
+
+				match Interpreter::map_operation(*operation, &operands) {
+					Ok(Some(result)) => self.push_stack(result),
+					Ok(None) => {}
+					Err(error) => {
+						self.throw_error(error)?;
+						return Ok(());
+					}
+				}
+			}
 			Instruction::LogicalAnd(value_position, target, final_logic) => {
-				let value = self.peek_stack(*value_position);
+				let value = self.peek_stack(*value_position)?;
 				if let Value::Number(number) = value
 					&& number == &0.0
 				{
@@ -310,14 +1660,10 @@ impl Interpreter {
 						self.stack_pointer -= 1;
 					}
 
-					self.instruction_index = self
-						.instruction_index
-						.checked_add_signed(*target)
-						.expect("Failed relative jump");
-
+					self.jump(*target)?;
 					self.push_stack(Value::Number(0.0));
 
-					return;
+					return Ok(());
 				} else if *final_logic && value_position >= &0 {
 					self.push_stack(value.clone());
 				} else if *final_logic == false && value_position < &0 {
@@ -325,22 +1671,19 @@ impl Interpreter {
 				}
 			}
 			Instruction::LogicalOr(value_position, target, final_logic) => {
-				let value = self.peek_stack(*value_position);
+				let value = self.peek_stack(*value_position)?;
 				if let Value::Number(number) = value
 					&& number != &0.0
 				{
 					let value = value.clone();
 
-					self.instruction_index = self
-						.instruction_index
-						.checked_add_signed(*target)
-						.expect("Failed relative jump");
+					self.instruction_index = self.jump_target(*target)?;
 
 					if value_position >= &0 {
 						self.push_stack(value);
 					}
 
-					return;
+					return Ok(());
 				} else if *final_logic {
 					if value_position < &0 {
 						self.stack_pointer -= 1;
@@ -354,14 +1697,20 @@ impl Interpreter {
 		}
 
 		self.instruction_index += 1;
+
+		Ok(())
 	}
 
-	fn peek_stack(&self, stack_position: RelativeStackAddress) -> &Value {
-		if stack_position < 0 {
-			&self.stack[self.stack_pointer - 1]
+	fn peek_stack(&self, stack_position: RelativeStackAddress) -> Result<&Value, RuntimeErrorKind> {
+		let index = if stack_position < 0 {
+			self.stack_pointer.checked_sub(1)
 		} else {
-			&self.stack[self.stack_base + stack_position as usize]
-		}
+			Some(self.stack_base + stack_position as usize)
+		};
+
+		index
+			.and_then(|index| self.stack.get(index))
+			.ok_or(RuntimeErrorKind::InvalidStackAddress(stack_position))
 	}
 
 	fn push_stack(&mut self, value: Value) {
@@ -371,8 +1720,34 @@ impl Interpreter {
 	}
 }
 
-fn pop_stack<'a, 'b>(stack: &'a Vec<Value>, stack_pointer: &'b mut usize) -> &'a Value {
-	let value = &stack[*stack_pointer - 1];
-	*stack_pointer -= 1;
-	return value;
+/// Hash `restore` compares to tell whether a function was compiled from the same source. Natives
+/// are only compared by name, the host can implement them differently
+fn function_hash(function: &Function) -> u64 {
+	match function {
+		Function::Eggscript { instructions, .. } => code_hash(instructions),
+		Function::Native { .. } => 0,
+	}
+}
+
+/// Back-edges and calls, which every program that runs for a long time keeps running into
+fn is_interrupt_point(instruction: &Instruction) -> bool {
+	match instruction {
+		Instruction::Jump(offset)
+		| Instruction::JumpIfFalse(offset, _)
+		| Instruction::JumpIfTrue(offset, _) => *offset < 0,
+		Instruction::CallFunction(..) | Instruction::CallParentFunction(..) => true,
+		_ => false,
+	}
+}
+
+fn pop_stack<'a, 'b>(
+	stack: &'a Vec<Value>,
+	stack_pointer: &'b mut usize,
+) -> Result<&'a Value, RuntimeErrorKind> {
+	let index = stack_pointer
+		.checked_sub(1)
+		.ok_or(RuntimeErrorKind::StackUnderflow)?;
+	let value = stack.get(index).ok_or(RuntimeErrorKind::StackUnderflow)?;
+	*stack_pointer = index;
+	return Ok(value);
 }
@@ -0,0 +1,27 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Stops a running script from another thread, like when the player that runs it leaves. The
+/// script stops at its next loop iteration or call with an `Interrupted` error that it cannot catch
+#[derive(Clone, Debug, Default)]
+pub struct InterruptHandle(Arc<AtomicBool>);
+
+impl InterruptHandle {
+	pub fn interrupt(&self) {
+		self.0.store(true, Ordering::Relaxed);
+	}
+
+	pub fn is_interrupted(&self) -> bool {
+		self.0.load(Ordering::Relaxed)
+	}
+
+	/// Clears the interrupt and returns whether there was one, each interrupt stops the script once
+	pub fn take(&self) -> bool {
+		self.is_interrupted() && self.0.swap(false, Ordering::Relaxed)
+	}
+
+	/// Address of the flag, which JIT code reads at the back-edges of loops
+	pub fn flag_address(&self) -> usize {
+		self.0.as_ptr() as usize
+	}
+}
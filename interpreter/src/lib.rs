@@ -1,19 +1,58 @@
 #![feature(let_chains)]
 
+mod backtrace;
+mod error;
 mod function;
 mod instruction;
 mod interpreter;
+mod interrupt;
+mod package;
 pub mod runtime;
+mod sandbox;
+mod scheduler;
+mod snapshot;
+mod source_compiler;
+mod value_type;
 
+pub use backtrace::SourceSpan;
+pub use backtrace::StackFrame;
+pub use error::RuntimeError;
+pub use error::RuntimeErrorKind;
 pub use function::Function;
+pub use function::FunctionHandle;
+pub use function::FunctionReference;
+pub use function::NativeFunction;
 pub use instruction::AbsoluteStackAddress;
+pub use instruction::Instruction;
+pub use instruction::MapOperation;
 pub use instruction::NumberMathOperation;
 pub use instruction::NumberUnaryOperation;
-pub use instruction::Instruction;
 pub use instruction::RelativeStackAddress;
 pub use instruction::Value;
 pub use interpreter::Interpreter;
+pub use interpreter::PendingToken;
+pub use interpreter::RunStatus;
+pub use interrupt::InterruptHandle;
+pub use package::Package;
+pub use package::PackageBindings;
+pub use package::PackageTable;
 pub use runtime::get_native_function_mapping_for_interpreter;
 pub use runtime::get_native_function_mapping_for_jit;
 pub use runtime::get_test_native_function_mapping_for_interpreter;
 pub use runtime::get_test_native_function_mapping_for_jit;
+pub use sandbox::map_size;
+pub use sandbox::value_size;
+pub use sandbox::SandboxConfig;
+pub use sandbox::SandboxLimit;
+pub use sandbox::SANDBOX_CHECK_INTERVAL;
+pub use scheduler::EventId;
+pub use scheduler::ScheduledCall;
+pub use scheduler::Scheduler;
+pub use snapshot::code_hash;
+pub use snapshot::SnapshotError;
+pub use snapshot::SNAPSHOT_MAGIC;
+pub use snapshot::SNAPSHOT_VERSION;
+pub use source_compiler::CompiledSource;
+pub use source_compiler::SourceCompiler;
+pub use value_type::check_argument_types;
+pub use value_type::ValueType;
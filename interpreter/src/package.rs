@@ -0,0 +1,91 @@
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+
+use crate::function::FunctionHandle;
+
+pub struct Package {
+	pub name: String,
+	/// Pairs of the function that is overridden and the package function that overrides it
+	pub overrides: Vec<(FunctionHandle, FunctionHandle)>,
+}
+
+/// The functions calls resolve to after packages are activated or deactivated
+#[derive(Debug, Default, PartialEq)]
+pub struct PackageBindings {
+	/// Maps every overridden function to the function that calls to it go to
+	pub current: HashMap<FunctionHandle, FunctionHandle>,
+	/// Maps every package function to the function its `Parent::` calls go to
+	pub parent: HashMap<FunctionHandle, FunctionHandle>,
+}
+
+/// Keeps track of the declared packages and the order they were activated in. Both backends use
+/// this to decide how to rebind their function tables
+#[derive(Default)]
+pub struct PackageTable {
+	active: Vec<String>,
+	packages: Vec<Package>,
+}
+
+impl PackageTable {
+	pub fn add_package(&mut self, package: Package) {
+		self.packages.push(package);
+	}
+
+	pub fn packages(&self) -> &[Package] {
+		return &self.packages;
+	}
+
+	/// Names of the active packages in the order they were activated in
+	pub fn active_packages(&self) -> &[String] {
+		return &self.active;
+	}
+
+	/// Activating a package that is already active does nothing
+	pub fn activate(&mut self, name: &str) -> Result<()> {
+		if !self.packages.iter().any(|package| package.name == name) {
+			bail!("Package '{}' does not exist", name);
+		}
+
+		if !self.active.iter().any(|active| active == name) {
+			self.active.push(name.into());
+		}
+
+		Ok(())
+	}
+
+	/// Packages that were activated after the deactivated package stay active
+	pub fn deactivate(&mut self, name: &str) -> Result<()> {
+		let Some(position) = self.active.iter().position(|active| active == name) else {
+			bail!("Package '{}' is not active", name);
+		};
+
+		self.active.remove(position);
+
+		Ok(())
+	}
+
+	pub fn bindings(&self) -> PackageBindings {
+		let mut bindings = PackageBindings::default();
+
+		// functions of inactive packages still get bindings, so calls go back to the overridden
+		// function once a package is deactivated
+		for package in self.packages.iter() {
+			for (overridden, function) in package.overrides.iter() {
+				bindings.current.insert(*overridden, *overridden);
+				bindings.parent.insert(*function, *overridden);
+			}
+		}
+
+		for name in self.active.iter() {
+			for package in self.packages.iter().filter(|package| &package.name == name) {
+				for (overridden, function) in package.overrides.iter() {
+					let parent = bindings.current[overridden];
+					bindings.parent.insert(*function, parent);
+					bindings.current.insert(*overridden, *function);
+				}
+			}
+		}
+
+		return bindings;
+	}
+}
@@ -0,0 +1,110 @@
+use anyhow::{bail, Result};
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::rc::Rc;
+
+use super::{exception, parse::parse_number};
+use crate::Value;
+
+/// Tag of an `any` value that holds a number in JIT code. The interpreter's values carry their type
+/// already
+pub const ANY_NUMBER_TAG: u8 = 0;
+/// Tag of an `any` value that holds a string in JIT code
+pub const ANY_STRING_TAG: u8 = 1;
+
+thread_local! {
+	/// Strings created by the runtime are handed to JIT code as pointers, so they have to live
+	/// until the host's call into JIT code returns
+	static CONVERTED_STRINGS: RefCell<Vec<CString>> = const { RefCell::new(vec![]) };
+}
+
+pub fn any_to_number(value: &Value) -> Result<f64> {
+	match value {
+		Value::Number(number) => Ok(*number),
+		Value::String(string) => parse_number(string),
+		Value::Map(_) => bail!("Cannot convert a map to a number"),
+		Value::Null => bail!("Cannot convert null to a number"),
+	}
+}
+
+pub fn any_to_string(value: &Value) -> Result<Rc<String>> {
+	match value {
+		Value::Number(number) => Ok(Rc::new(format!("{}", number))),
+		Value::String(string) => Ok(string.clone()),
+		Value::Map(_) => bail!("Cannot convert a map to a string"),
+		Value::Null => bail!("Cannot convert null to a string"),
+	}
+}
+
+fn any_from_jit(tag: u8, number: f64, string: *const c_char) -> Value {
+	if tag == ANY_STRING_TAG {
+		Value::String(Rc::new(
+			unsafe { CStr::from_ptr(string) }
+				.to_string_lossy()
+				.to_string(),
+		))
+	} else {
+		Value::Number(number)
+	}
+}
+
+/// Layout of the `any` values JIT code passes to variadic natives
+#[repr(C)]
+pub struct AnyForJit {
+	pub tag: u8,
+	pub number: f64,
+	pub string: *const c_char,
+}
+
+/// Converts the variadic `any` arguments JIT code passed to a native
+///
+/// # Safety
+///
+/// `values` has to point to `count` values, and the strings among them have to be NUL-terminated
+pub unsafe fn values_from_jit(count: u64, values: *const AnyForJit) -> Vec<Value> {
+	if count == 0 {
+		return vec![];
+	}
+
+	unsafe { std::slice::from_raw_parts(values, count as usize) }
+		.iter()
+		.map(|value| any_from_jit(value.tag, value.number, value.string))
+		.collect()
+}
+
+pub extern "C" fn any_to_number_for_jit(tag: u8, number: f64, string: *const c_char) -> f64 {
+	match any_to_number(&any_from_jit(tag, number, string)) {
+		Ok(number) => number,
+		Err(error) => {
+			exception::raise_for_jit(&error.to_string());
+			0.0
+		}
+	}
+}
+
+pub extern "C" fn any_to_string_for_jit(
+	tag: u8,
+	number: f64,
+	string: *const c_char,
+) -> *const c_char {
+	if tag == ANY_STRING_TAG {
+		return string;
+	}
+
+	return string_for_jit(&format!("{}", number));
+}
+
+/// Hands a string that was created by the runtime to JIT code
+pub(crate) fn string_for_jit(string: &str) -> *const c_char {
+	let converted = CString::new(string.replace('\0', "")).expect("Could not create string");
+	let pointer = converted.as_ptr();
+	CONVERTED_STRINGS.with(|strings| strings.borrow_mut().push(converted));
+
+	return pointer;
+}
+
+/// Frees the strings handed to JIT code. Only call it once no JIT code is running, it could still
+/// hold pointers to them
+pub fn free_strings_for_jit() {
+	CONVERTED_STRINGS.with(|strings| strings.borrow_mut().clear());
+}
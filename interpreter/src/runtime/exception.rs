@@ -0,0 +1,85 @@
+use std::cell::{Cell, RefCell};
+use std::ffi::{c_char, CStr, CString};
+
+// JIT code checks the pending flag after every call it makes, and either branches to the catch unit
+// or returns to its own caller so the check repeats there
+thread_local! {
+	static EXCEPTION_PENDING: Cell<u8> = const { Cell::new(0) };
+	static EXCEPTION: RefCell<Option<CString>> = const { RefCell::new(None) };
+	/// Set while an exception that scripts cannot catch unwinds, like the ones the sandbox raises
+	static ABORTING: Cell<bool> = const { Cell::new(false) };
+	/// Caught exceptions are handed to JIT code as pointers, so they have to live until the host's
+	/// call into JIT code returns
+	static CAUGHT_EXCEPTIONS: RefCell<Vec<CString>> = const { RefCell::new(vec![]) };
+}
+
+/// Address of the flag that JIT code reads to find out whether an exception is pending
+pub fn exception_pending_address() -> usize {
+	EXCEPTION_PENDING.with(|pending| pending.as_ptr() as usize)
+}
+
+/// Raises an exception from a native called by JIT code
+pub fn raise_for_jit(message: &str) {
+	if ABORTING.with(Cell::get) {
+		return;
+	}
+
+	let message = CString::new(message.replace('\0', "")).expect("Could not create exception");
+	EXCEPTION.with(|exception| *exception.borrow_mut() = Some(message));
+	EXCEPTION_PENDING.with(|pending| pending.set(1));
+}
+
+/// # Safety
+///
+/// `value` has to point to a NUL-terminated string
+pub unsafe extern "C" fn throw_for_jit(value: *const c_char) {
+	raise_for_jit(&unsafe { CStr::from_ptr(value) }.to_string_lossy());
+}
+
+/// Raises an exception that catch units pass on instead of handling, it unwinds out of the function
+/// the host called
+pub fn abort_for_jit(message: &str) {
+	raise_for_jit(message);
+	ABORTING.with(|aborting| aborting.set(true));
+}
+
+/// Clears the pending exception and returns it to the catch unit that handles it
+pub extern "C" fn catch_for_jit() -> *const c_char {
+	// catch units check the flag after catching, and keep unwinding
+	if ABORTING.with(Cell::get) {
+		return c"".as_ptr();
+	}
+
+	EXCEPTION_PENDING.with(|pending| pending.set(0));
+
+	let exception = EXCEPTION
+		.with(|exception| exception.borrow_mut().take())
+		.unwrap_or_default();
+
+	let pointer = exception.as_ptr();
+	CAUGHT_EXCEPTIONS.with(|caught| caught.borrow_mut().push(exception));
+
+	return pointer;
+}
+
+/// Frees the exceptions handed to catch units. Only call it once no JIT code is running, it could
+/// still hold pointers to them
+pub fn free_caught_exceptions_for_jit() {
+	CAUGHT_EXCEPTIONS.with(|caught| caught.borrow_mut().clear());
+}
+
+/// Clears the pending exception once it unwound out of the function the host called
+pub fn take_uncaught_exception_for_jit() -> Option<String> {
+	ABORTING.with(|aborting| aborting.set(false));
+
+	if EXCEPTION_PENDING.with(|pending| pending.replace(0)) == 0 {
+		return None;
+	}
+
+	EXCEPTION.with(|exception| {
+		exception
+			.borrow_mut()
+			.take()
+			.map(|exception| exception.to_string_lossy().to_string())
+	})
+}
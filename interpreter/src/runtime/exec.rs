@@ -0,0 +1,20 @@
+use anyhow::{Context, Result};
+
+use crate::Interpreter;
+
+/// Loads a file into a running interpreter, returns 1 on success and 0 on failure like
+/// TorqueScript's `exec`
+pub fn exec(interpreter: &mut Interpreter, file_name: &str) -> f64 {
+	match exec_file(interpreter, file_name) {
+		Ok(()) => 1.0,
+		Err(error) => {
+			eprintln!("Could not exec '{}': {:?}", file_name, error);
+			0.0
+		}
+	}
+}
+
+fn exec_file(interpreter: &mut Interpreter, file_name: &str) -> Result<()> {
+	let contents = std::fs::read_to_string(file_name).context("Could not read file")?;
+	interpreter.exec(&contents, file_name)
+}
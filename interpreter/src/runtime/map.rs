@@ -0,0 +1,268 @@
+use anyhow::{bail, Context, Result};
+use indexmap::IndexMap;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::ffi::{c_char, CStr};
+use std::rc::Rc;
+
+use super::any::{string_for_jit, ANY_STRING_TAG};
+use super::exception;
+use crate::sandbox::map_size;
+use crate::Value;
+
+/// Maps remember the order keys were inserted in, which is the order `foreach` visits them in
+pub type Map = IndexMap<MapKey, Value>;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum MapKey {
+	/// Numbers are compared by their bits, with `-0` stored as `0`
+	Number(u64),
+	String(Rc<String>),
+}
+
+impl MapKey {
+	pub fn from_value(value: &Value) -> Result<MapKey> {
+		match value {
+			Value::Number(number) => Ok(MapKey::Number((number + 0.0).to_bits())),
+			Value::String(string) => Ok(MapKey::String(string.clone())),
+			_ => bail!("Map keys have to be numbers or strings"),
+		}
+	}
+
+	pub fn to_value(&self) -> Value {
+		match self {
+			MapKey::Number(bits) => Value::Number(f64::from_bits(*bits)),
+			MapKey::String(string) => Value::String(string.clone()),
+		}
+	}
+}
+
+impl std::fmt::Display for MapKey {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			MapKey::Number(bits) => f.write_fmt(format_args!("{}", f64::from_bits(*bits))),
+			MapKey::String(string) => f.write_str(string),
+		}
+	}
+}
+
+pub fn map_get(map: &Map, key: &Value) -> Result<Value> {
+	let key = MapKey::from_value(key)?;
+	map.get(&key)
+		.cloned()
+		.context(format!("Map has no key '{}'", key))
+}
+
+pub fn map_set(map: &mut Map, key: &Value, value: Value) -> Result<()> {
+	map.insert(MapKey::from_value(key)?, value);
+	Ok(())
+}
+
+pub fn map_contains(map: &Map, key: &Value) -> Result<bool> {
+	Ok(map.contains_key(&MapKey::from_value(key)?))
+}
+
+/// Returns whether the key was in the map. Keys after it keep their order
+pub fn map_remove(map: &mut Map, key: &Value) -> Result<bool> {
+	Ok(map.shift_remove(&MapKey::from_value(key)?).is_some())
+}
+
+/// Key at the given position in insertion order, used to iterate over maps
+pub fn map_key_at(map: &Map, index: f64) -> Result<Value> {
+	map.get_index(index as usize)
+		.map(|(key, _)| key.to_value())
+		.context(format!(
+			"Map changed size while iterating over it, it has no key at index {}",
+			index
+		))
+}
+
+thread_local! {
+	/// JIT code refers to maps through pointers, so they are kept alive until the host's call into
+	/// JIT code returns
+	static JIT_MAPS: RefCell<Vec<Rc<RefCell<Map>>>> = const { RefCell::new(vec![]) };
+}
+
+/// JIT code passes keys and values in the same way as `any` values
+fn value_from_jit(tag: u8, number: f64, string: *const c_char) -> Value {
+	if tag == ANY_STRING_TAG {
+		Value::String(Rc::new(
+			unsafe { CStr::from_ptr(string) }
+				.to_string_lossy()
+				.to_string(),
+		))
+	} else {
+		Value::Number(number)
+	}
+}
+
+fn map_from_jit<'a>(map: *const RefCell<Map>) -> &'a RefCell<Map> {
+	unsafe { &*map }
+}
+
+/// Raises errors as exceptions in JIT code, which returns the default value until it unwinds
+fn raise_for_jit<T: Default>(result: Result<T>) -> T {
+	match result {
+		Ok(value) => value,
+		Err(error) => {
+			exception::raise_for_jit(&error.to_string());
+			T::default()
+		}
+	}
+}
+
+/// Bytes the maps JIT code created during the host's current call take up, like `value_size`
+pub fn jit_memory_usage() -> usize {
+	let mut visited = HashSet::new();
+
+	JIT_MAPS.with(|maps| {
+		let mut size = 0;
+		for map in maps.borrow().iter() {
+			if visited.insert(Rc::as_ptr(map)) {
+				size += map_size(&map.borrow(), &mut visited);
+			}
+		}
+
+		return size;
+	})
+}
+
+/// Frees the maps JIT code created. Only call it once no JIT code is running, it could still hold
+/// pointers to them
+pub fn free_maps_for_jit() {
+	JIT_MAPS.with(|maps| maps.borrow_mut().clear());
+}
+
+pub extern "C" fn map_new_for_jit() -> *const RefCell<Map> {
+	let map = Rc::new(RefCell::new(Map::new()));
+	let pointer = Rc::as_ptr(&map);
+	JIT_MAPS.with(|maps| maps.borrow_mut().push(map));
+
+	return pointer;
+}
+
+pub extern "C" fn map_get_number_for_jit(
+	map: *const RefCell<Map>,
+	key_tag: u8,
+	key_number: f64,
+	key_string: *const c_char,
+) -> f64 {
+	let key = value_from_jit(key_tag, key_number, key_string);
+	raise_for_jit(
+		map_get(&map_from_jit(map).borrow(), &key).and_then(|value| Ok(value.as_number()?)),
+	)
+}
+
+pub extern "C" fn map_get_string_for_jit(
+	map: *const RefCell<Map>,
+	key_tag: u8,
+	key_number: f64,
+	key_string: *const c_char,
+) -> *const c_char {
+	let key = value_from_jit(key_tag, key_number, key_string);
+	match map_get(&map_from_jit(map).borrow(), &key)
+		.and_then(|value| Ok(string_for_jit(value.as_string()?)))
+	{
+		Ok(string) => string,
+		Err(error) => {
+			exception::raise_for_jit(&error.to_string());
+			std::ptr::null()
+		}
+	}
+}
+
+pub extern "C" fn map_set_for_jit(
+	map: *const RefCell<Map>,
+	key_tag: u8,
+	key_number: f64,
+	key_string: *const c_char,
+	value_tag: u8,
+	value_number: f64,
+	value_string: *const c_char,
+) {
+	let key = value_from_jit(key_tag, key_number, key_string);
+	let value = value_from_jit(value_tag, value_number, value_string);
+	raise_for_jit(map_set(&mut map_from_jit(map).borrow_mut(), &key, value));
+}
+
+pub extern "C" fn map_contains_for_jit(
+	map: *const RefCell<Map>,
+	key_tag: u8,
+	key_number: f64,
+	key_string: *const c_char,
+) -> f64 {
+	let key = value_from_jit(key_tag, key_number, key_string);
+	raise_for_jit(map_contains(&map_from_jit(map).borrow(), &key)) as u8 as f64
+}
+
+pub extern "C" fn map_remove_for_jit(
+	map: *const RefCell<Map>,
+	key_tag: u8,
+	key_number: f64,
+	key_string: *const c_char,
+) -> f64 {
+	let key = value_from_jit(key_tag, key_number, key_string);
+	raise_for_jit(map_remove(&mut map_from_jit(map).borrow_mut(), &key)) as u8 as f64
+}
+
+pub extern "C" fn map_count_for_jit(map: *const RefCell<Map>) -> f64 {
+	map_from_jit(map).borrow().len() as f64
+}
+
+pub extern "C" fn map_key_at_number_for_jit(map: *const RefCell<Map>, index: f64) -> f64 {
+	raise_for_jit(
+		map_key_at(&map_from_jit(map).borrow(), index).and_then(|key| Ok(key.as_number()?)),
+	)
+}
+
+pub extern "C" fn map_key_at_string_for_jit(map: *const RefCell<Map>, index: f64) -> *const c_char {
+	match map_key_at(&map_from_jit(map).borrow(), index)
+		.and_then(|key| Ok(string_for_jit(key.as_string()?)))
+	{
+		Ok(string) => string,
+		Err(error) => {
+			exception::raise_for_jit(&error.to_string());
+			std::ptr::null()
+		}
+	}
+}
+
+/// `ptr()` function that creates a map
+pub const MAP_NEW_FUNCTION: &str = "eggscript_map_new";
+/// `double(ptr, key)` function that gets a number, keys are passed like `any` values
+pub const MAP_GET_NUMBER_FUNCTION: &str = "eggscript_map_get_number";
+/// `ptr(ptr, key)` function that gets a string
+pub const MAP_GET_STRING_FUNCTION: &str = "eggscript_map_get_string";
+/// `void(ptr, key, value)` function that sets a key, values are passed like `any` values
+pub const MAP_SET_FUNCTION: &str = "eggscript_map_set";
+/// `double(ptr, key)` function that returns 1 if the map contains the key
+pub const MAP_CONTAINS_FUNCTION: &str = "eggscript_map_contains";
+/// `double(ptr, key)` function that returns 1 if the key was removed
+pub const MAP_REMOVE_FUNCTION: &str = "eggscript_map_remove";
+/// `double(ptr)` function that returns the amount of keys
+pub const MAP_COUNT_FUNCTION: &str = "eggscript_map_count";
+/// `double(ptr, double)` function that returns the number key at an index
+pub const MAP_KEY_AT_NUMBER_FUNCTION: &str = "eggscript_map_key_at_number";
+/// `ptr(ptr, double)` function that returns the string key at an index
+pub const MAP_KEY_AT_STRING_FUNCTION: &str = "eggscript_map_key_at_string";
+
+/// Addresses of the functions JIT code calls to work with maps
+pub fn get_map_function_mapping_for_jit() -> Vec<(&'static str, usize)> {
+	vec![
+		(MAP_NEW_FUNCTION, map_new_for_jit as usize),
+		(MAP_GET_NUMBER_FUNCTION, map_get_number_for_jit as usize),
+		(MAP_GET_STRING_FUNCTION, map_get_string_for_jit as usize),
+		(MAP_SET_FUNCTION, map_set_for_jit as usize),
+		(MAP_CONTAINS_FUNCTION, map_contains_for_jit as usize),
+		(MAP_REMOVE_FUNCTION, map_remove_for_jit as usize),
+		(MAP_COUNT_FUNCTION, map_count_for_jit as usize),
+		(
+			MAP_KEY_AT_NUMBER_FUNCTION,
+			map_key_at_number_for_jit as usize,
+		),
+		(
+			MAP_KEY_AT_STRING_FUNCTION,
+			map_key_at_string_for_jit as usize,
+		),
+	]
+}
@@ -1,6 +1,7 @@
+use anyhow::Context;
 use std::{collections::HashMap, rc::Rc};
 
-use super::print;
+use super::{exec, package, parse, print, schedule};
 use crate::{function::NativeFunction, Value};
 
 pub fn get_native_function_mapping_for_interpreter() -> HashMap<String, NativeFunction> {
@@ -8,9 +9,138 @@ pub fn get_native_function_mapping_for_interpreter() -> HashMap<String, NativeFu
 
 	mapping.insert(
 		"printNumber".to_string(),
-		Rc::new(|values| {
-			print::print_number(values.get(0).expect("Could not get argument 0").as_number());
-			return Value::Null;
+		Rc::new(|_, values| {
+			print::print_number(
+				values
+					.get(0)
+					.context("Could not get argument 0")?
+					.as_number()?,
+			);
+			return Ok(Value::Null);
+		}),
+	);
+
+	mapping.insert(
+		"printString".to_string(),
+		Rc::new(|_, values| {
+			print::print_string(
+				values
+					.get(0)
+					.context("Could not get argument 0")?
+					.as_string()?,
+			);
+			return Ok(Value::Null);
+		}),
+	);
+
+	mapping.insert(
+		"parseNumber".to_string(),
+		Rc::new(|_, values| {
+			let value = values
+				.get(0)
+				.context("Could not get argument 0")?
+				.as_string()?;
+			return Ok(Value::Number(parse::parse_number(value)?));
+		}),
+	);
+
+	mapping.insert(
+		"echo".to_string(),
+		Rc::new(|_, values| {
+			print::echo(
+				&values
+					.iter()
+					.map(Value::as_number)
+					.collect::<Result<Vec<f64>, _>>()?,
+			);
+			return Ok(Value::Null);
+		}),
+	);
+
+	mapping.insert(
+		"exec".to_string(),
+		Rc::new(|interpreter, values| {
+			let file_name = values
+				.get(0)
+				.context("Could not get argument 0")?
+				.as_string()?;
+			return Ok(Value::Number(exec::exec(interpreter, file_name)));
+		}),
+	);
+
+	mapping.insert(
+		"activatePackage".to_string(),
+		Rc::new(|interpreter, values| {
+			let name = values
+				.get(0)
+				.context("Could not get argument 0")?
+				.as_string()?;
+			return Ok(Value::Number(package::activate_package(interpreter, name)));
+		}),
+	);
+
+	mapping.insert(
+		"deactivatePackage".to_string(),
+		Rc::new(|interpreter, values| {
+			let name = values
+				.get(0)
+				.context("Could not get argument 0")?
+				.as_string()?;
+			return Ok(Value::Number(package::deactivate_package(
+				interpreter,
+				name,
+			)));
+		}),
+	);
+
+	mapping.insert(
+		"schedule".to_string(),
+		Rc::new(|interpreter, mut values| {
+			// arguments after the function name are passed on to it
+			let arguments = values.split_off(values.len().min(2));
+			let delay = values
+				.get(0)
+				.context("Could not get argument 0")?
+				.as_number()?;
+			let function = values
+				.get(1)
+				.context("Could not get argument 1")?
+				.as_string()?;
+			return Ok(Value::Number(schedule::schedule(
+				interpreter,
+				delay,
+				function,
+				arguments,
+			)?));
+		}),
+	);
+
+	mapping.insert(
+		"cancel".to_string(),
+		Rc::new(|interpreter, values| {
+			let id = values
+				.get(0)
+				.context("Could not get argument 0")?
+				.as_number()?;
+			return Ok(Value::Number(schedule::cancel(interpreter, id)));
+		}),
+	);
+
+	mapping.insert(
+		"isEventPending".to_string(),
+		Rc::new(|interpreter, values| {
+			let id = values
+				.get(0)
+				.context("Could not get argument 0")?
+				.as_number()?;
+			return Ok(Value::Number(schedule::is_event_pending(interpreter, id)));
+		}),
+	);
+
+	mapping.insert(
+		"getSimTime".to_string(),
+		Rc::new(|interpreter, _| {
+			return Ok(Value::Number(schedule::get_sim_time(interpreter)));
 		}),
 	);
 
@@ -22,9 +152,40 @@ pub fn get_test_native_function_mapping_for_interpreter() -> HashMap<String, Nat
 
 	mapping.insert(
 		"printNumber".to_string(),
-		Rc::new(|values| {
-			print::test_print_number(values.get(0).expect("Could not get argument 0").as_number());
-			return Value::Null;
+		Rc::new(|_, values| {
+			print::test_print_number(
+				values
+					.get(0)
+					.context("Could not get argument 0")?
+					.as_number()?,
+			);
+			return Ok(Value::Null);
+		}),
+	);
+
+	mapping.insert(
+		"printString".to_string(),
+		Rc::new(|_, values| {
+			print::test_print_string(
+				values
+					.get(0)
+					.context("Could not get argument 0")?
+					.as_string()?,
+			);
+			return Ok(Value::Null);
+		}),
+	);
+
+	mapping.insert(
+		"echo".to_string(),
+		Rc::new(|_, values| {
+			print::test_echo(
+				&values
+					.iter()
+					.map(Value::as_number)
+					.collect::<Result<Vec<f64>, _>>()?,
+			);
+			return Ok(Value::Null);
 		}),
 	);
 
@@ -34,11 +195,29 @@ pub fn get_test_native_function_mapping_for_interpreter() -> HashMap<String, Nat
 pub fn get_native_function_mapping_for_jit() -> HashMap<String, usize> {
 	let mut mapping: HashMap<String, usize> = HashMap::new();
 	mapping.insert("printNumber".to_string(), print::print_number as usize);
+	mapping.insert("echo".to_string(), print::echo_for_jit as usize);
+	mapping.insert(
+		"printString".to_string(),
+		print::print_string_for_jit as usize,
+	);
+	mapping.insert(
+		"parseNumber".to_string(),
+		parse::parse_number_for_jit as usize,
+	);
 	return mapping;
 }
 
 pub fn get_test_native_function_mapping_for_jit() -> HashMap<String, usize> {
 	let mut mapping: HashMap<String, usize> = HashMap::new();
 	mapping.insert("printNumber".to_string(), print::test_print_number as usize);
+	mapping.insert("echo".to_string(), print::test_echo_for_jit as usize);
+	mapping.insert(
+		"printString".to_string(),
+		print::test_print_string_for_jit as usize,
+	);
+	mapping.insert(
+		"parseNumber".to_string(),
+		parse::parse_number_for_jit as usize,
+	);
 	return mapping;
 }
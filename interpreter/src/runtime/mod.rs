@@ -1,5 +1,14 @@
+pub mod any;
+pub mod exception;
+mod exec;
+pub mod map;
 mod mapping;
+mod package;
+mod parse;
 pub mod print;
+pub mod sandbox;
+mod schedule;
+pub mod stack;
 
 pub use mapping::get_native_function_mapping_for_interpreter;
 pub use mapping::get_native_function_mapping_for_jit;
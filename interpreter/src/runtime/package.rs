@@ -0,0 +1,23 @@
+use crate::Interpreter;
+
+/// Returns 1 if the package was activated and 0 if it does not exist
+pub fn activate_package(interpreter: &mut Interpreter, name: &str) -> f64 {
+	match interpreter.activate_package(name) {
+		Ok(()) => 1.0,
+		Err(error) => {
+			eprintln!("Could not activate package: {}", error);
+			0.0
+		}
+	}
+}
+
+/// Returns 1 if the package was deactivated and 0 if it was not active
+pub fn deactivate_package(interpreter: &mut Interpreter, name: &str) -> f64 {
+	match interpreter.deactivate_package(name) {
+		Ok(()) => 1.0,
+		Err(error) => {
+			eprintln!("Could not deactivate package: {}", error);
+			0.0
+		}
+	}
+}
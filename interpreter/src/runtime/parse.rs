@@ -0,0 +1,21 @@
+use anyhow::{bail, Result};
+use std::ffi::{c_char, CStr};
+
+use super::exception;
+
+pub fn parse_number(value: &str) -> Result<f64> {
+	match value.trim().parse::<f64>() {
+		Ok(number) => Ok(number),
+		Err(_) => bail!("Could not parse '{}' as a number", value),
+	}
+}
+
+pub extern "C" fn parse_number_for_jit(value: *const c_char) -> f64 {
+	match parse_number(&unsafe { CStr::from_ptr(value) }.to_string_lossy()) {
+		Ok(number) => number,
+		Err(error) => {
+			exception::raise_for_jit(&error.to_string());
+			0.0
+		}
+	}
+}
@@ -1,9 +1,40 @@
+use std::ffi::{c_char, CStr};
 use std::sync::Mutex;
 
 pub fn print_number(value: f64) {
 	println!("{}", value);
 }
 
+pub fn print_string(value: &str) {
+	println!("{}", value);
+}
+
+/// # Safety
+///
+/// `value` has to point to a NUL-terminated string
+pub unsafe extern "C" fn print_string_for_jit(value: *const c_char) {
+	print_string(&unsafe { CStr::from_ptr(value) }.to_string_lossy());
+}
+
+pub fn echo(values: &[f64]) {
+	println!("{}", format_echo(values));
+}
+
+/// # Safety
+///
+/// `values` has to point to `count` numbers
+pub unsafe extern "C" fn echo_for_jit(count: u64, values: *const f64) {
+	echo(unsafe { std::slice::from_raw_parts(values, count as usize) });
+}
+
+fn format_echo(values: &[f64]) -> String {
+	values
+		.iter()
+		.map(|value| format!("{}", value))
+		.collect::<Vec<String>>()
+		.join(" ")
+}
+
 static TEST_PRINT_BUFFER: Mutex<Vec<String>> = Mutex::new(vec![]);
 
 pub fn test_print_number(value: f64) {
@@ -13,6 +44,34 @@ pub fn test_print_number(value: f64) {
 		.push(format!("{}", value))
 }
 
+pub fn test_print_string(value: &str) {
+	TEST_PRINT_BUFFER
+		.lock()
+		.expect("Could not lock TEST_PRINT_BUFFER")
+		.push(value.to_string())
+}
+
+/// # Safety
+///
+/// `value` has to point to a NUL-terminated string
+pub unsafe extern "C" fn test_print_string_for_jit(value: *const c_char) {
+	test_print_string(&unsafe { CStr::from_ptr(value) }.to_string_lossy());
+}
+
+pub fn test_echo(values: &[f64]) {
+	TEST_PRINT_BUFFER
+		.lock()
+		.expect("Could not lock TEST_PRINT_BUFFER")
+		.push(format_echo(values))
+}
+
+/// # Safety
+///
+/// `values` has to point to `count` numbers
+pub unsafe extern "C" fn test_echo_for_jit(count: u64, values: *const f64) {
+	test_echo(unsafe { std::slice::from_raw_parts(values, count as usize) });
+}
+
 pub fn clear_test_print_buffer() {
 	TEST_PRINT_BUFFER
 		.lock()
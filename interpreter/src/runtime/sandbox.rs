@@ -0,0 +1,117 @@
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use super::stack::{self, DEFAULT_MAX_CALL_DEPTH};
+use super::{exception, map};
+use crate::sandbox::{SandboxConfig, SandboxLimit, SANDBOX_CHECK_INTERVAL};
+
+/// What is left of the limits of the sandbox JIT code runs in
+struct JitSandbox {
+	deadline: Option<Instant>,
+	/// Loop iterations that were not granted to the countdown yet
+	fuel: Option<u64>,
+	max_memory: Option<usize>,
+}
+
+impl JitSandbox {
+	/// Takes the loop iterations JIT code can run until it polls again out of the fuel
+	fn grant(&mut self) -> u64 {
+		match &mut self.fuel {
+			Some(fuel) => {
+				let granted = (*fuel).min(SANDBOX_CHECK_INTERVAL);
+				*fuel -= granted;
+				granted
+			}
+			None => SANDBOX_CHECK_INTERVAL,
+		}
+	}
+
+	fn check(&self) -> Result<(), SandboxLimit> {
+		if self.fuel == Some(0) {
+			return Err(SandboxLimit::Fuel);
+		}
+
+		if let Some(deadline) = self.deadline
+			&& Instant::now() > deadline
+		{
+			return Err(SandboxLimit::Deadline);
+		}
+
+		if let Some(max_memory) = self.max_memory
+			&& map::jit_memory_usage() > max_memory
+		{
+			return Err(SandboxLimit::Memory);
+		}
+
+		Ok(())
+	}
+}
+
+// JIT code decrements the countdown at the back-edges of loops, and polls once it goes below zero
+// or the script was interrupted. Without a sandbox the countdown never gets there
+thread_local! {
+	static POLL_COUNTDOWN: Cell<i64> = const { Cell::new(i64::MAX) };
+	static SANDBOX: RefCell<Option<JitSandbox>> = const { RefCell::new(None) };
+}
+
+/// Address of the countdown JIT code decrements at back-edges
+pub fn poll_countdown_address() -> usize {
+	POLL_COUNTDOWN.with(|countdown| countdown.as_ptr() as usize)
+}
+
+/// Runs JIT code with the limits of the sandbox from now on
+pub fn set_sandbox_for_jit(config: &SandboxConfig) {
+	stack::set_max_call_depth_for_jit(config.max_call_depth.unwrap_or(DEFAULT_MAX_CALL_DEPTH));
+
+	let mut sandbox = JitSandbox {
+		deadline: config.deadline.map(|deadline| Instant::now() + deadline),
+		fuel: config.fuel,
+		max_memory: config.max_memory,
+	};
+
+	POLL_COUNTDOWN.with(|countdown| countdown.set(sandbox.grant() as i64));
+	SANDBOX.with(|current| *current.borrow_mut() = Some(sandbox));
+}
+
+pub fn clear_sandbox_for_jit() {
+	stack::set_max_call_depth_for_jit(DEFAULT_MAX_CALL_DEPTH);
+	POLL_COUNTDOWN.with(|countdown| countdown.set(i64::MAX));
+	SANDBOX.with(|current| *current.borrow_mut() = None);
+}
+
+/// Called by JIT code once the countdown runs out or the script was interrupted
+///
+/// # Safety
+///
+/// `interrupted` has to point to the flag of an `InterruptHandle` that outlives the call
+pub unsafe extern "C" fn poll_for_jit(interrupted: *const AtomicBool) {
+	let interrupted = unsafe { &*interrupted };
+	if interrupted.load(Ordering::Relaxed) && interrupted.swap(false, Ordering::Relaxed) {
+		exception::abort_for_jit("Interrupted");
+		return;
+	}
+
+	// polls made for an interrupt that was already taken leave the countdown alone
+	if POLL_COUNTDOWN.with(Cell::get) >= 0 {
+		return;
+	}
+
+	let result: Result<(), SandboxLimit> = SANDBOX.with(|sandbox| {
+		let mut sandbox = sandbox.borrow_mut();
+		let Some(sandbox) = sandbox.as_mut() else {
+			POLL_COUNTDOWN.with(|countdown| countdown.set(i64::MAX));
+			return Ok(());
+		};
+
+		sandbox.check()?;
+
+		// the iteration that polled is the first of the ones that were granted
+		POLL_COUNTDOWN.with(|countdown| countdown.set(sandbox.grant() as i64 - 1));
+		Ok(())
+	});
+
+	if let Err(limit) = result {
+		exception::abort_for_jit(&format!("Sandbox limit exceeded: {}", limit));
+	}
+}
@@ -0,0 +1,38 @@
+use anyhow::{bail, Result};
+
+use crate::{Interpreter, Value};
+
+/// Schedules a call of a function by its name, returns the id the call can be cancelled with
+pub fn schedule(
+	interpreter: &mut Interpreter,
+	delay: f64,
+	function: &str,
+	arguments: Vec<Value>,
+) -> Result<f64> {
+	if interpreter.function_handle(function).is_none() {
+		bail!("Cannot schedule unknown function '{}'", function);
+	}
+
+	if !interpreter.is_allowed_by_sandbox(function) {
+		bail!(
+			"Cannot schedule '{}', the sandbox does not allow it",
+			function
+		);
+	}
+
+	Ok(interpreter.scheduler().schedule(delay, function, arguments) as f64)
+}
+
+/// Returns 1 if the call was pending and 0 if it already ran or was cancelled
+pub fn cancel(interpreter: &mut Interpreter, id: f64) -> f64 {
+	interpreter.scheduler().cancel(id as usize) as u8 as f64
+}
+
+pub fn is_event_pending(interpreter: &mut Interpreter, id: f64) -> f64 {
+	interpreter.scheduler().is_pending(id as usize) as u8 as f64
+}
+
+/// Simulated time in milliseconds
+pub fn get_sim_time(interpreter: &mut Interpreter) -> f64 {
+	interpreter.scheduler().time() as f64
+}
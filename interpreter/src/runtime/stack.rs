@@ -0,0 +1,30 @@
+use std::cell::Cell;
+
+/// Exception raised by calls that would go deeper than the maximum call depth or stack size
+pub const STACK_OVERFLOW: &str = "Stack overflow";
+
+/// Calls that can be running at once before calls raise a stack overflow
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1_000;
+
+/// Values the interpreter's stack can hold before calls raise a stack overflow
+pub const DEFAULT_MAX_STACK_SIZE: usize = 1 << 20;
+
+// JIT code counts the script functions that are running in its prologues and before it returns
+thread_local! {
+	static CALL_DEPTH: Cell<u64> = const { Cell::new(0) };
+	static MAX_CALL_DEPTH: Cell<u64> = const { Cell::new(DEFAULT_MAX_CALL_DEPTH as u64) };
+}
+
+/// Address of the counter JIT code keeps the call depth in
+pub fn call_depth_address() -> usize {
+	CALL_DEPTH.with(|depth| depth.as_ptr() as usize)
+}
+
+/// Address of the limit JIT code compares the call depth against
+pub fn max_call_depth_address() -> usize {
+	MAX_CALL_DEPTH.with(|depth| depth.as_ptr() as usize)
+}
+
+pub fn set_max_call_depth_for_jit(depth: usize) {
+	MAX_CALL_DEPTH.with(|max_depth| max_depth.set(depth as u64));
+}
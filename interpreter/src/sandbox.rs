@@ -0,0 +1,135 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use crate::runtime::map::{Map, MapKey};
+use crate::Value;
+
+/// Instructions the interpreter runs between checks of the clock and of the memory it uses, since
+/// both are slow to measure
+pub const SANDBOX_CHECK_INTERVAL: u64 = 10_000;
+
+/// Limits for scripts that cannot be trusted, like user-submitted mods. Limits that are `None` are
+/// not enforced
+#[derive(Clone, Debug, Default)]
+pub struct SandboxConfig {
+	/// Natives scripts can call, every native when `None`. Calling other natives stops the program,
+	/// and programs compiled with `CompileOptions::with_sandbox` fail to compile instead
+	pub allowed_natives: Option<HashSet<String>>,
+	/// Wall-clock time scripts can run for, counted from when the sandbox is applied
+	pub deadline: Option<Duration>,
+	/// Instructions the interpreter can run. The JIT counts loop iterations instead
+	pub fuel: Option<u64>,
+	/// Calls that can be running at once before calls raise a stack overflow
+	pub max_call_depth: Option<usize>,
+	/// Bytes the stack and the strings and maps it refers to can take up. The JIT only counts the
+	/// maps created during the host's current call into it
+	pub max_memory: Option<usize>,
+}
+
+impl SandboxConfig {
+	/// Whether scripts can call the native
+	pub fn allows_native(&self, name: &str) -> bool {
+		self.allowed_natives
+			.as_ref()
+			.is_none_or(|allowed| allowed.contains(name))
+	}
+}
+
+/// Limit a sandboxed script ran into. Scripts cannot catch these, they stop the program
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SandboxLimit {
+	Deadline,
+	Fuel,
+	Memory,
+}
+
+impl std::fmt::Display for SandboxLimit {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			SandboxLimit::Deadline => f.write_str("the deadline passed"),
+			SandboxLimit::Fuel => f.write_str("out of fuel"),
+			SandboxLimit::Memory => f.write_str("out of memory"),
+		}
+	}
+}
+
+/// What is left of the limits of a sandbox that was applied
+pub(crate) struct SandboxState {
+	/// Config the sandbox was applied with
+	pub(crate) config: SandboxConfig,
+	deadline: Option<Instant>,
+	fuel: Option<u64>,
+	/// Instructions until the clock and the memory are checked again
+	until_check: u64,
+}
+
+impl SandboxState {
+	pub(crate) fn new(config: SandboxConfig) -> SandboxState {
+		SandboxState {
+			deadline: config.deadline.map(|deadline| Instant::now() + deadline),
+			fuel: config.fuel,
+			until_check: SANDBOX_CHECK_INTERVAL,
+			config,
+		}
+	}
+
+	/// Uses up the fuel of one instruction. Returns the memory limit when it is time to measure the
+	/// memory the interpreter uses
+	pub(crate) fn charge(&mut self) -> Result<Option<usize>, SandboxLimit> {
+		if let Some(fuel) = &mut self.fuel {
+			if *fuel == 0 {
+				return Err(SandboxLimit::Fuel);
+			}
+
+			*fuel -= 1;
+		}
+
+		self.until_check -= 1;
+		if self.until_check > 0 {
+			return Ok(None);
+		}
+
+		self.until_check = SANDBOX_CHECK_INTERVAL;
+
+		if let Some(deadline) = self.deadline
+			&& Instant::now() > deadline
+		{
+			return Err(SandboxLimit::Deadline);
+		}
+
+		Ok(self.config.max_memory)
+	}
+}
+
+/// Bytes a value takes up, along with the strings and maps it refers to. Maps that were already
+/// visited are shared with an earlier value, and are not counted again
+pub fn value_size(value: &Value, visited: &mut HashSet<*const RefCell<Map>>) -> usize {
+	let heap_size = match value {
+		Value::Map(map) => {
+			if visited.insert(std::rc::Rc::as_ptr(map)) {
+				map_size(&map.borrow(), visited)
+			} else {
+				0
+			}
+		}
+		Value::String(string) => string.len(),
+		Value::Null | Value::Number(_) => 0,
+	};
+
+	return std::mem::size_of::<Value>() + heap_size;
+}
+
+/// Bytes the entries of a map take up, like `value_size`
+pub fn map_size(map: &Map, visited: &mut HashSet<*const RefCell<Map>>) -> usize {
+	map.iter()
+		.map(|(key, value)| {
+			let key_size = match key {
+				MapKey::Number(_) => 0,
+				MapKey::String(string) => string.len(),
+			};
+
+			std::mem::size_of::<MapKey>() + key_size + value_size(value, visited)
+		})
+		.sum()
+}
@@ -0,0 +1,114 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::Value;
+
+/// Scripts refer to scheduled calls by the number `schedule` returned. Ids start at 1, so 0 never
+/// refers to a call
+pub type EventId = usize;
+
+/// Function call that runs once the simulated time reaches the time it was scheduled for
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScheduledCall {
+	pub arguments: Vec<Value>,
+	pub function: String,
+	pub id: EventId,
+	pub time: u64,
+}
+
+/// Timers of a program, like TorqueScript's `schedule`. Time only passes when the host advances it,
+/// so scheduled calls run at the same simulated times no matter how fast the program runs
+#[derive(Default)]
+pub struct Scheduler {
+	/// Pending calls in the order they run in, calls scheduled for the same time run in the order
+	/// they were scheduled in
+	events: BTreeMap<(u64, EventId), ScheduledCall>,
+	next_id: EventId,
+	/// Time every pending call runs at, by its id
+	pending: HashMap<EventId, u64>,
+	/// Simulated time in milliseconds
+	time: u64,
+}
+
+impl Scheduler {
+	/// Schedules a call `delay` milliseconds from now. Negative delays schedule it for now
+	pub fn schedule(&mut self, delay: f64, function: &str, arguments: Vec<Value>) -> EventId {
+		self.next_id += 1;
+
+		let id = self.next_id;
+		let time = self.time.saturating_add(delay.max(0.0) as u64);
+		self.events.insert(
+			(time, id),
+			ScheduledCall {
+				arguments,
+				function: function.to_string(),
+				id,
+				time,
+			},
+		);
+		self.pending.insert(id, time);
+
+		return id;
+	}
+
+	/// Removes a pending call, returns whether there was one
+	pub fn cancel(&mut self, id: EventId) -> bool {
+		let Some(time) = self.pending.remove(&id) else {
+			return false;
+		};
+
+		self.events.remove(&(time, id));
+		return true;
+	}
+
+	pub fn is_pending(&self, id: EventId) -> bool {
+		self.pending.contains_key(&id)
+	}
+
+	pub fn time(&self) -> u64 {
+		self.time
+	}
+
+	/// Removes the next call that is due at or before `until`, and moves the clock to the time it
+	/// was scheduled for. Calls it schedules with a short enough delay are due before `until` too
+	pub fn next_due(&mut self, until: u64) -> Option<ScheduledCall> {
+		let (&(time, id), _) = self.events.first_key_value()?;
+		if time > until {
+			return None;
+		}
+
+		self.pending.remove(&id);
+		self.time = self.time.max(time);
+		self.events.remove(&(time, id))
+	}
+
+	/// Moves the clock to `time` once every call due before it ran
+	pub fn set_time(&mut self, time: u64) {
+		self.time = self.time.max(time);
+	}
+
+	/// Pending calls in the order they run in
+	pub fn pending_calls(&self) -> impl Iterator<Item = &ScheduledCall> {
+		self.events.values()
+	}
+
+	/// Id the last scheduled call got
+	pub(crate) fn last_id(&self) -> EventId {
+		self.next_id
+	}
+
+	/// Scheduler that continues where a saved one left off
+	pub(crate) fn restore(time: u64, last_id: EventId, calls: Vec<ScheduledCall>) -> Scheduler {
+		let mut scheduler = Scheduler {
+			next_id: last_id,
+			time,
+			..Scheduler::default()
+		};
+
+		for call in calls.into_iter() {
+			scheduler.pending.insert(call.id, call.time);
+			scheduler.events.insert((call.time, call.id), call);
+		}
+
+		return scheduler;
+	}
+}
@@ -0,0 +1,482 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::runtime::map::{Map, MapKey};
+use crate::{Instruction, MapOperation, NumberMathOperation, NumberUnaryOperation, Value};
+
+/// Bytes every snapshot starts with
+pub const SNAPSHOT_MAGIC: &[u8; 8] = b"EGGSNAP\0";
+
+/// Version of the snapshot format. Snapshots of other versions are refused instead of being read
+/// wrong
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Name snapshots use for the global scope of the program
+pub(crate) const GLOBAL_SCOPE_NAME: &str = "<global scope>";
+
+/// Why the interpreter could not take a snapshot or restore one
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnapshotError {
+	/// The program was in a state that snapshots cannot capture
+	CannotSnapshot(&'static str),
+	/// The snapshot ended early or contains values that do not make sense
+	Corrupt(String),
+	/// The snapshot refers to a function the interpreter does not have
+	MissingFunction(String),
+	/// The snapshot refers to a package the interpreter does not have
+	MissingPackage(String),
+	/// The snapshot does not start with `SNAPSHOT_MAGIC`
+	NotASnapshot,
+	/// A function or the global scope was compiled from different source than the snapshot's
+	SourceChanged(String),
+	/// The snapshot was written by another version of the format
+	UnsupportedVersion(u32),
+}
+
+impl std::fmt::Display for SnapshotError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			SnapshotError::CannotSnapshot(reason) => {
+				f.write_fmt(format_args!("Cannot take a snapshot {}", reason))
+			}
+			SnapshotError::Corrupt(message) => {
+				f.write_fmt(format_args!("Snapshot is corrupt: {}", message))
+			}
+			SnapshotError::MissingFunction(name) => f.write_fmt(format_args!(
+				"Function '{}' from the snapshot does not exist",
+				name
+			)),
+			SnapshotError::MissingPackage(name) => f.write_fmt(format_args!(
+				"Package '{}' from the snapshot does not exist",
+				name
+			)),
+			SnapshotError::NotASnapshot => f.write_str("Data is not a snapshot"),
+			SnapshotError::SourceChanged(name) => f.write_fmt(format_args!(
+				"The source of '{}' changed since the snapshot was taken",
+				name
+			)),
+			SnapshotError::UnsupportedVersion(version) => f.write_fmt(format_args!(
+				"Snapshot format version {} is not supported, expected version {}",
+				version, SNAPSHOT_VERSION
+			)),
+		}
+	}
+}
+
+impl std::error::Error for SnapshotError {}
+
+/// Hash of compiled code that stays the same between runs and builds, so snapshots can tell
+/// whether a function was compiled from the same source. FNV-1a over the encoding of the
+/// instructions by `encode_instruction`
+pub fn code_hash(instructions: &[Instruction]) -> u64 {
+	let mut bytes = vec![];
+	for instruction in instructions.iter() {
+		encode_instruction(&mut bytes, instruction);
+	}
+
+	let mut hash: u64 = 0xcbf29ce484222325;
+	for byte in bytes.into_iter() {
+		hash ^= byte as u64;
+		hash = hash.wrapping_mul(0x100000001b3);
+	}
+
+	return hash;
+}
+
+/// Encodes an instruction with tags that are spelled out, so reordering or renaming variants does
+/// not change the hash of existing code. New variants need new tags
+fn encode_instruction(bytes: &mut Vec<u8>, instruction: &Instruction) {
+	match instruction {
+		Instruction::Invalid => bytes.push(0),
+		Instruction::Noop => bytes.push(1),
+		Instruction::RestorePop => bytes.push(2),
+		Instruction::Push(value) => {
+			bytes.push(3);
+			encode_constant(bytes, value);
+		}
+		Instruction::CopyPush(address) => {
+			bytes.push(4);
+			push_i64(bytes, *address as i64);
+		}
+		Instruction::Pop => bytes.push(5),
+		Instruction::Store(absolute, relative) => {
+			bytes.push(6);
+			push_i64(bytes, *absolute as i64);
+			push_i64(bytes, *relative as i64);
+		}
+		Instruction::Reserve(count) => {
+			bytes.push(7);
+			push_i64(bytes, *count as i64);
+		}
+		Instruction::Jump(offset) => {
+			bytes.push(8);
+			push_i64(bytes, *offset as i64);
+		}
+		Instruction::JumpIfFalse(offset, address) => {
+			bytes.push(9);
+			push_i64(bytes, *offset as i64);
+			push_i64(bytes, *address as i64);
+		}
+		Instruction::JumpIfTrue(offset, address) => {
+			bytes.push(10);
+			push_i64(bytes, *offset as i64);
+			push_i64(bytes, *address as i64);
+		}
+		Instruction::NumberMath(operation, lvalue, rvalue) => {
+			bytes.push(11);
+			bytes.push(number_math_tag(*operation));
+			push_i64(bytes, *lvalue as i64);
+			push_i64(bytes, *rvalue as i64);
+		}
+		Instruction::ImmediateNumberMath(operation, value, address) => {
+			bytes.push(12);
+			bytes.push(number_math_tag(*operation));
+			encode_constant(bytes, value);
+			push_i64(bytes, *address as i64);
+		}
+		Instruction::CallFunction(function, argument_count) => {
+			bytes.push(13);
+			push_i64(bytes, *function as i64);
+			push_i64(bytes, *argument_count as i64);
+		}
+		Instruction::CallParentFunction(function, argument_count) => {
+			bytes.push(14);
+			push_i64(bytes, *function as i64);
+			push_i64(bytes, *argument_count as i64);
+		}
+		Instruction::Return(count) => {
+			bytes.push(15);
+			push_i64(bytes, *count as i64);
+		}
+		Instruction::PushCatch(offset) => {
+			bytes.push(16);
+			push_i64(bytes, *offset as i64);
+		}
+		Instruction::PopCatch => bytes.push(17),
+		Instruction::Throw(address) => {
+			bytes.push(18);
+			push_i64(bytes, *address as i64);
+		}
+		Instruction::Yield(address) => {
+			bytes.push(19);
+			push_i64(bytes, *address as i64);
+		}
+		Instruction::NumberUnary(operation, address) => {
+			bytes.push(20);
+			bytes.push(match operation {
+				NumberUnaryOperation::BitwiseNot => 0,
+				NumberUnaryOperation::Minus => 1,
+				NumberUnaryOperation::Not => 2,
+			});
+			push_i64(bytes, *address as i64);
+		}
+		Instruction::IsNull(address) => {
+			bytes.push(21);
+			push_i64(bytes, *address as i64);
+		}
+		Instruction::ConvertToNumber(address) => {
+			bytes.push(22);
+			push_i64(bytes, *address as i64);
+		}
+		Instruction::ConvertToString(address) => {
+			bytes.push(23);
+			push_i64(bytes, *address as i64);
+		}
+		Instruction::MapOperation(operation, operands) => {
+			bytes.push(24);
+			bytes.push(match operation {
+				MapOperation::New => 0,
+				MapOperation::Get => 1,
+				MapOperation::Set => 2,
+				MapOperation::Contains => 3,
+				MapOperation::Remove => 4,
+				MapOperation::Count => 5,
+				MapOperation::KeyAt => 6,
+			});
+			push_i64(bytes, operands.len() as i64);
+			for operand in operands.iter() {
+				push_i64(bytes, *operand as i64);
+			}
+		}
+		Instruction::LogicalAnd(address, offset, value) => {
+			bytes.push(25);
+			push_i64(bytes, *address as i64);
+			push_i64(bytes, *offset as i64);
+			bytes.push(*value as u8);
+		}
+		Instruction::LogicalOr(address, offset, value) => {
+			bytes.push(26);
+			push_i64(bytes, *address as i64);
+			push_i64(bytes, *offset as i64);
+			bytes.push(*value as u8);
+		}
+	}
+}
+
+/// Encodes a value compiled into an instruction. Programs never compile maps into their code, so
+/// only the fact that there is one is encoded
+fn encode_constant(bytes: &mut Vec<u8>, value: &Value) {
+	match value {
+		Value::Map(_) => bytes.push(0),
+		Value::Number(number) => {
+			bytes.push(1);
+			bytes.extend_from_slice(&number.to_bits().to_le_bytes());
+		}
+		Value::Null => bytes.push(2),
+		Value::String(string) => {
+			bytes.push(3);
+			bytes.extend_from_slice(&(string.len() as u64).to_le_bytes());
+			bytes.extend_from_slice(string.as_bytes());
+		}
+	}
+}
+
+fn push_i64(bytes: &mut Vec<u8>, value: i64) {
+	bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn number_math_tag(operation: NumberMathOperation) -> u8 {
+	match operation {
+		NumberMathOperation::Plus => 0,
+		NumberMathOperation::Minus => 1,
+		NumberMathOperation::Multiply => 2,
+		NumberMathOperation::Divide => 3,
+		NumberMathOperation::Modulus => 4,
+		NumberMathOperation::BitwiseAnd => 5,
+		NumberMathOperation::BitwiseOr => 6,
+		NumberMathOperation::BitwiseXor => 7,
+		NumberMathOperation::ShiftLeft => 8,
+		NumberMathOperation::ShiftRight => 9,
+		NumberMathOperation::Equal => 10,
+		NumberMathOperation::NotEqual => 11,
+		NumberMathOperation::LessThan => 12,
+		NumberMathOperation::GreaterThan => 13,
+		NumberMathOperation::LessThanEqualTo => 14,
+		NumberMathOperation::GreaterThanEqualTo => 15,
+	}
+}
+
+/// Encodes values in little endian. Maps are written out the first time they are seen and referred
+/// to by index afterwards, so maps shared between values stay shared and cycles terminate
+#[derive(Default)]
+pub(crate) struct SnapshotWriter {
+	bytes: Vec<u8>,
+	maps: HashMap<*const RefCell<Map>, u64>,
+}
+
+impl SnapshotWriter {
+	pub(crate) fn new() -> SnapshotWriter {
+		let mut writer = SnapshotWriter::default();
+		writer.bytes.extend_from_slice(SNAPSHOT_MAGIC);
+		writer.write_u32(SNAPSHOT_VERSION);
+		return writer;
+	}
+
+	pub(crate) fn finish(self) -> Vec<u8> {
+		self.bytes
+	}
+
+	pub(crate) fn write_u8(&mut self, value: u8) {
+		self.bytes.push(value);
+	}
+
+	pub(crate) fn write_u32(&mut self, value: u32) {
+		self.bytes.extend_from_slice(&value.to_le_bytes());
+	}
+
+	pub(crate) fn write_u64(&mut self, value: u64) {
+		self.bytes.extend_from_slice(&value.to_le_bytes());
+	}
+
+	pub(crate) fn write_usize(&mut self, value: usize) {
+		self.write_u64(value as u64);
+	}
+
+	pub(crate) fn write_string(&mut self, value: &str) {
+		self.write_usize(value.len());
+		self.bytes.extend_from_slice(value.as_bytes());
+	}
+
+	pub(crate) fn write_value(&mut self, value: &Value) {
+		match value {
+			Value::Null => self.write_u8(0),
+			Value::Number(number) => {
+				self.write_u8(1);
+				self.write_u64(number.to_bits());
+			}
+			Value::String(string) => {
+				self.write_u8(2);
+				self.write_string(string);
+			}
+			Value::Map(map) => {
+				self.write_u8(3);
+
+				let pointer = Rc::as_ptr(map);
+				if let Some(index) = self.maps.get(&pointer) {
+					self.write_u64(*index);
+					return;
+				}
+
+				let index = self.maps.len() as u64;
+				self.maps.insert(pointer, index);
+				self.write_u64(index);
+
+				let map = map.borrow();
+				self.write_usize(map.len());
+				for (key, value) in map.iter() {
+					match key {
+						MapKey::Number(bits) => {
+							self.write_u8(0);
+							self.write_u64(*bits);
+						}
+						MapKey::String(string) => {
+							self.write_u8(1);
+							self.write_string(string);
+						}
+					}
+
+					self.write_value(value);
+				}
+			}
+		}
+	}
+}
+
+/// Decodes what `SnapshotWriter` encoded
+pub(crate) struct SnapshotReader<'a> {
+	bytes: &'a [u8],
+	maps: Vec<Rc<RefCell<Map>>>,
+	position: usize,
+}
+
+impl<'a> SnapshotReader<'a> {
+	/// Checks the magic bytes and the version of the snapshot
+	pub(crate) fn new(bytes: &'a [u8]) -> Result<SnapshotReader<'a>, SnapshotError> {
+		if !bytes.starts_with(SNAPSHOT_MAGIC) {
+			return Err(SnapshotError::NotASnapshot);
+		}
+
+		let mut reader = SnapshotReader {
+			bytes,
+			maps: vec![],
+			position: SNAPSHOT_MAGIC.len(),
+		};
+
+		let version = reader.read_u32()?;
+		if version != SNAPSHOT_VERSION {
+			return Err(SnapshotError::UnsupportedVersion(version));
+		}
+
+		return Ok(reader);
+	}
+
+	/// Errors if there are bytes left over
+	pub(crate) fn finish(self) -> Result<(), SnapshotError> {
+		if self.position != self.bytes.len() {
+			return Err(SnapshotError::Corrupt(format!(
+				"{} bytes left over",
+				self.bytes.len() - self.position
+			)));
+		}
+
+		Ok(())
+	}
+
+	fn read_bytes(&mut self, count: usize) -> Result<&'a [u8], SnapshotError> {
+		let bytes = self
+			.position
+			.checked_add(count)
+			.and_then(|end| self.bytes.get(self.position..end))
+			.ok_or_else(|| SnapshotError::Corrupt("ended early".into()))?;
+
+		self.position += count;
+		return Ok(bytes);
+	}
+
+	pub(crate) fn read_u8(&mut self) -> Result<u8, SnapshotError> {
+		Ok(self.read_bytes(1)?[0])
+	}
+
+	pub(crate) fn read_u32(&mut self) -> Result<u32, SnapshotError> {
+		let bytes = self.read_bytes(4)?;
+		Ok(u32::from_le_bytes(
+			bytes.try_into().expect("Could not convert bytes"),
+		))
+	}
+
+	pub(crate) fn read_u64(&mut self) -> Result<u64, SnapshotError> {
+		let bytes = self.read_bytes(8)?;
+		Ok(u64::from_le_bytes(
+			bytes.try_into().expect("Could not convert bytes"),
+		))
+	}
+
+	pub(crate) fn read_usize(&mut self) -> Result<usize, SnapshotError> {
+		usize::try_from(self.read_u64()?)
+			.map_err(|_| SnapshotError::Corrupt("size does not fit in memory".into()))
+	}
+
+	/// Reads a count of items that take up at least one byte each, so corrupt counts are caught
+	/// before anything is allocated for them
+	pub(crate) fn read_count(&mut self) -> Result<usize, SnapshotError> {
+		let count = self.read_usize()?;
+		if count > self.bytes.len() - self.position {
+			return Err(SnapshotError::Corrupt(format!(
+				"count {} is larger than the snapshot",
+				count
+			)));
+		}
+
+		return Ok(count);
+	}
+
+	pub(crate) fn read_string(&mut self) -> Result<String, SnapshotError> {
+		let length = self.read_count()?;
+		String::from_utf8(self.read_bytes(length)?.to_vec())
+			.map_err(|_| SnapshotError::Corrupt("string is not UTF-8".into()))
+	}
+
+	pub(crate) fn read_value(&mut self) -> Result<Value, SnapshotError> {
+		match self.read_u8()? {
+			0 => Ok(Value::Null),
+			1 => Ok(Value::Number(f64::from_bits(self.read_u64()?))),
+			2 => Ok(Value::String(Rc::new(self.read_string()?))),
+			3 => {
+				let index = self.read_usize()?;
+				if let Some(map) = self.maps.get(index) {
+					return Ok(Value::Map(map.clone()));
+				}
+
+				if index != self.maps.len() {
+					return Err(SnapshotError::Corrupt(format!(
+						"map {} referred to before it was written",
+						index
+					)));
+				}
+
+				let map = Rc::new(RefCell::new(Map::new()));
+				self.maps.push(map.clone());
+
+				for _ in 0..self.read_count()? {
+					let key = match self.read_u8()? {
+						0 => MapKey::Number(self.read_u64()?),
+						1 => MapKey::String(Rc::new(self.read_string()?)),
+						tag => {
+							return Err(SnapshotError::Corrupt(format!(
+								"unknown map key tag {}",
+								tag
+							)))
+						}
+					};
+
+					let value = self.read_value()?;
+					map.borrow_mut().insert(key, value);
+				}
+
+				Ok(Value::Map(map))
+			}
+			tag => Err(SnapshotError::Corrupt(format!("unknown value tag {}", tag))),
+		}
+	}
+}
@@ -0,0 +1,21 @@
+use anyhow::Result;
+
+use crate::{Function, Instruction, Package, SourceSpan};
+
+/// Everything the interpreter needs to run source code that was compiled while it is running
+pub struct CompiledSource {
+	/// Functions the source declares, with function handles that continue from the functions
+	/// compiled so far
+	pub functions: Vec<Function>,
+	/// Instructions for the source's global scope
+	pub instructions: Vec<Instruction>,
+	pub packages: Vec<Package>,
+	/// Source span of every instruction of the global scope
+	pub spans: Vec<Option<SourceSpan>>,
+}
+
+/// Compiles source code while the interpreter is running. The compiler depends on the interpreter,
+/// so the interpreter only knows about it through this trait
+pub trait SourceCompiler {
+	fn compile(&mut self, contents: &str, file_name: &str) -> Result<CompiledSource>;
+}
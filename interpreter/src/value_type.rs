@@ -0,0 +1,70 @@
+use crate::Value;
+
+/// Type of a value the host passes to a script function, checked before the function is called
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValueType {
+	/// Numbers or strings
+	Any,
+	Map(Box<ValueType>, Box<ValueType>),
+	Number,
+	/// Null, or a value of the inner type
+	Optional(Box<ValueType>),
+	String,
+	/// A type the host cannot create values of, named like it is in scripts
+	Unsupported(String),
+}
+
+impl ValueType {
+	pub fn accepts(&self, value: &Value) -> bool {
+		match (self, value) {
+			(ValueType::Any, Value::Number(_) | Value::String(_)) => true,
+			(ValueType::Map(key_type, value_type), Value::Map(map)) => map
+				.borrow()
+				.iter()
+				.all(|(key, value)| key_type.accepts(&key.to_value()) && value_type.accepts(value)),
+			(ValueType::Number, Value::Number(_)) => true,
+			(ValueType::Optional(_), Value::Null) => true,
+			(ValueType::Optional(inner), value) => inner.accepts(value),
+			(ValueType::String, Value::String(_)) => true,
+			_ => false,
+		}
+	}
+}
+
+impl std::fmt::Display for ValueType {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ValueType::Any => f.write_str("any"),
+			ValueType::Map(key_type, value_type) => {
+				f.write_fmt(format_args!("map<{}, {}>", key_type, value_type))
+			}
+			ValueType::Number => f.write_str("number"),
+			ValueType::Optional(inner) => f.write_fmt(format_args!("{}?", inner)),
+			ValueType::String => f.write_str("string"),
+			ValueType::Unsupported(name) => f.write_str(name),
+		}
+	}
+}
+
+/// Checks the arguments the host passes to a function against the types of its arguments
+pub fn check_argument_types(
+	function_name: &str,
+	argument_types: &[ValueType],
+	arguments: &[Value],
+) -> Result<(), String> {
+	for (index, (argument_type, argument)) in
+		argument_types.iter().zip(arguments.iter()).enumerate()
+	{
+		if !argument_type.accepts(argument) {
+			return Err(format!(
+				"Argument {} of '{}' is a {}, found a {} value",
+				index + 1,
+				function_name,
+				argument_type,
+				argument.type_name()
+			));
+		}
+	}
+
+	Ok(())
+}
@@ -8,8 +8,24 @@ mod span;
 mod unit;
 mod value;
 
+pub use eggscript_interpreter::MapOperation;
+pub use eggscript_interpreter::RuntimeError;
+pub use eggscript_interpreter::SandboxConfig;
+pub use eggscript_interpreter::SourceSpan;
+pub use eggscript_interpreter::StackFrame;
 pub use lower::EggscriptLowerContext;
 pub use lower::LlvmLowerContext;
+pub use lower::ANY_TO_NUMBER_FUNCTION;
+pub use lower::ANY_TO_STRING_FUNCTION;
+pub use lower::CALL_DEPTH_GLOBAL;
+pub use lower::CATCH_FUNCTION;
+pub use lower::EXCEPTION_PENDING_GLOBAL;
+pub use lower::INTERRUPT_GLOBAL;
+pub use lower::MAX_CALL_DEPTH_GLOBAL;
+pub use lower::POLL_COUNTDOWN_GLOBAL;
+pub use lower::POLL_FUNCTION;
+pub use lower::THROW_FUNCTION;
+pub use mir::Dispatch;
 pub use mir::MIRInfo;
 pub use mir::Transition;
 pub use mir::MIR;
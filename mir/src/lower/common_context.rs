@@ -1,22 +1,24 @@
 use anyhow::{Context, Result};
+use eggscript_interpreter::MapOperation;
 use eggscript_types::{FunctionType, TypeHandle, TypeStore};
 use indexmap::IndexMap;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::usize;
 
-use crate::{MIRInfo, Span, Transition, Unit, UnitHandle};
+use crate::{MIRInfo, PrimitiveValue, Span, Transition, Unit, UnitHandle};
 
 pub struct CommonContext {
-	pub file_name: String,
+	/// Names of the files spans point into, indexed by `Span::file`
+	pub file_names: Vec<String>,
 	pub type_store: Arc<Mutex<TypeStore>>,
 	pub value_used_by: HashMap<usize, Vec<usize>>,
 }
 
 impl CommonContext {
-	pub fn new(type_store: Arc<Mutex<TypeStore>>, file_name: &str) -> Self {
+	pub fn new(type_store: Arc<Mutex<TypeStore>>, file_names: Vec<String>) -> Self {
 		CommonContext {
-			file_name: file_name.into(),
+			file_names,
 			type_store,
 			value_used_by: HashMap::new(),
 		}
@@ -30,26 +32,46 @@ impl CommonContext {
 		let type_store = self.type_store.lock().expect("Could not lock type store");
 		for unit in units.values() {
 			match &unit.transition {
-				Transition::Return(value) => {
+				Transition::Return(values) => {
 					assert!(function.is_some(), "return found in non-function unit");
 
 					let function = function.context("Return found in non-function scope")?;
 
 					assert!(
-						function.return_type.is_some() == value.is_some(),
+						function.return_type.is_some() == (values.len() != 0),
 						"malformed return statement"
 					);
 
-					if let Some(value) = value {
+					if values.len() == 1 {
 						assert!(
 							type_store.are_types_compatible(
 								function
 									.return_type
 									.expect("Expected return type where there was none"),
-								value.ty()
+								values[0].ty()
 							),
 							"return types not compatible"
 						);
+					} else if values.len() > 1 {
+						let element_types = type_store
+							.get_tuple_element_types(
+								function
+									.return_type
+									.expect("Expected return type where there was none"),
+							)
+							.context("Returned several values from function without tuple type")?;
+
+						assert!(
+							element_types.len() == values.len(),
+							"wrong amount of values in return statement"
+						);
+
+						for (element_type, value) in element_types.iter().zip(values.iter()) {
+							assert!(
+								type_store.are_types_compatible(*element_type, value.ty()),
+								"return types not compatible"
+							);
+						}
 					}
 				}
 				_ => {}
@@ -58,22 +80,24 @@ impl CommonContext {
 			for mir in unit.mir.iter() {
 				match &mir.info {
 					MIRInfo::Allocate(_, _) => {}
-					MIRInfo::BinaryOperation(result, left, right, _) => {
-						self.type_check(
-							&type_store,
-							result.ty(),
-							left.ty(),
-							&mir.span,
-							"result not compatible with left",
-						);
+					MIRInfo::BinaryOperation(result, left, right, operator) => {
+						if !operator.is_comparison() {
+							self.type_check(
+								&type_store,
+								result.ty(),
+								left.ty(),
+								&mir.span,
+								"result not compatible with left",
+							);
 
-						self.type_check(
-							&type_store,
-							result.ty(),
-							right.ty(),
-							&mir.span,
-							"result not compatible with right",
-						);
+							self.type_check(
+								&type_store,
+								result.ty(),
+								right.ty(),
+								&mir.span,
+								"result not compatible with right",
+							);
+						}
 
 						self.type_check(
 							&type_store,
@@ -83,26 +107,72 @@ impl CommonContext {
 							"left not compatible with right",
 						);
 					}
-					MIRInfo::CallFunction(function_name, _, arguments, _) => {
+					MIRInfo::CallFunction(function_name, _, _, arguments, _) => {
 						let mut index = 0;
 						let function = type_store
 							.get_function(function_name)
 							.expect("Could not get function");
 
+						// default arguments are filled in during lowering, so every declared argument
+						// has to be present
+						if arguments.len() < function.argument_types.len()
+							|| (arguments.len() > function.argument_types.len()
+								&& function.variadic_type.is_none())
+						{
+							println!(
+								"function '{}' called with wrong amount of arguments ({})",
+								function_name,
+								arguments.len()
+							);
+							println!("{}", self.print_span(&mir.span));
+							panic!();
+						}
+
 						for argument in arguments.iter() {
+							let argument_type =
+								if let Some(argument_type) = function.argument_types.get(index) {
+									*argument_type
+								} else {
+									function
+										.variadic_type
+										.expect("Could not get variadic argument type")
+								};
+
 							self.type_check(
 								&type_store,
 								argument.ty(),
-								*function
-									.argument_types
-									.get(index)
-									.expect("Could not get argument type"),
+								argument_type,
 								&mir.span,
 								&format!("argument #{} not compatible with value", index),
 							);
 							index += 1;
 						}
 					}
+					MIRInfo::Catch(_) => {}
+					MIRInfo::Destructure(lvalues, rvalue) => {
+						let element_types = type_store
+							.get_tuple_element_types(rvalue.ty())
+							.context("Could not destructure non-tuple value")?;
+
+						assert!(
+							element_types.len() == lvalues.len(),
+							"wrong amount of values in destructuring declaration"
+						);
+
+						let mut index = 0;
+						for (lvalue, element_type) in lvalues.iter().zip(element_types.iter()) {
+							self.type_check(
+								&type_store,
+								lvalue.ty(),
+								*element_type,
+								&mir.span,
+								&format!("lvalue #{} not compatible with tuple element", index),
+							);
+							index += 1;
+						}
+					}
+					MIRInfo::EnterTry(_) => {}
+					MIRInfo::ExitTry => {}
 					MIRInfo::LogicPhi(result, _, units_and_values) => {
 						for (_, value) in units_and_values.iter() {
 							self.type_check(
@@ -114,14 +184,31 @@ impl CommonContext {
 							);
 						}
 					}
+					MIRInfo::MapOperation(_, operation, operands) => {
+						if operation != &MapOperation::New {
+							assert!(
+								operands
+									.first()
+									.and_then(|map| type_store.get_map_types(map.ty()))
+									.is_some(),
+								"map operation on value that is not a map"
+							);
+						}
+					}
 					MIRInfo::StoreLiteral(lvalue, rvalue) => {
-						self.type_check(
-							&type_store,
-							lvalue.ty(),
-							rvalue.get_type_from_type_store(&type_store)?,
-							&mir.span,
-							"lvalue not compatible with rvalue",
-						);
+						// enum variants are stored as number literals
+						let is_enum_variant = matches!(rvalue, PrimitiveValue::Number(_))
+							&& type_store.get_enum_variants(lvalue.ty()).is_some();
+
+						if !is_enum_variant {
+							self.type_check(
+								&type_store,
+								lvalue.ty(),
+								rvalue.get_type_from_type_store(&type_store)?,
+								&mir.span,
+								"lvalue not compatible with rvalue",
+							);
+						}
 					}
 					MIRInfo::StoreValue(lvalue, rvalue) => {
 						self.type_check(
@@ -133,6 +220,37 @@ impl CommonContext {
 						);
 					}
 					MIRInfo::Unary(_, _, _) => {}
+					MIRInfo::IsNull(_, value) | MIRInfo::UnwrapOptional(_, value) => {
+						assert!(
+							type_store.get_optional_inner_type(value.ty()).is_some(),
+							"null checked value is not optional"
+						);
+					}
+					MIRInfo::FromAny(_, value) => {
+						assert!(type_store.is_any(value.ty()), "converted value is not any");
+					}
+					MIRInfo::ToAny(result, _) => {
+						assert!(
+							type_store.is_any(result.ty()),
+							"converted result is not any"
+						);
+					}
+					MIRInfo::WrapOptional(result, value) => {
+						let inner = type_store
+							.get_optional_inner_type(result.ty())
+							.context("Wrapped value is not optional")?;
+
+						if !type_store.is_null(value.ty()) {
+							self.type_check(
+								&type_store,
+								inner,
+								value.ty(),
+								&mir.span,
+								"optional not compatible with wrapped value",
+							);
+						}
+					}
+					MIRInfo::Yield(_) => {}
 				}
 			}
 		}
@@ -173,14 +291,20 @@ impl CommonContext {
 				}
 				Transition::Invalid => {}
 				Transition::Next => {}
-				Transition::Return(value) => {
-					if let Some(value) = value {
+				Transition::Return(values) => {
+					for value in values.iter() {
 						self.value_used_by
 							.entry(value.id())
 							.or_default()
 							.push(usize::MAX);
 					}
 				}
+				Transition::Throw(value) => {
+					self.value_used_by
+						.entry(value.id())
+						.or_default()
+						.push(usize::MAX);
+				}
 			}
 
 			for mir in unit.mir.iter() {
@@ -197,7 +321,7 @@ impl CommonContext {
 							.or_default()
 							.push(lvalue.id());
 					}
-					MIRInfo::CallFunction(_, _, arguments, result) => {
+					MIRInfo::CallFunction(_, _, _, arguments, result) => {
 						for argument in arguments.iter() {
 							self.value_used_by
 								.entry(argument.id())
@@ -205,6 +329,17 @@ impl CommonContext {
 								.push(result.id());
 						}
 					}
+					MIRInfo::Catch(_) => {}
+					MIRInfo::Destructure(lvalues, rvalue) => {
+						for lvalue in lvalues.iter() {
+							self.value_used_by
+								.entry(rvalue.id())
+								.or_default()
+								.push(lvalue.id());
+						}
+					}
+					MIRInfo::EnterTry(_) => {}
+					MIRInfo::ExitTry => {}
 					MIRInfo::LogicPhi(result, _, units_and_values) => {
 						for (_, value) in units_and_values.iter() {
 							self.value_used_by
@@ -213,6 +348,18 @@ impl CommonContext {
 								.push(result.id());
 						}
 					}
+					MIRInfo::MapOperation(result, _, operands) => {
+						// setting a key uses its operands for the map
+						let user = result
+							.as_ref()
+							.map_or_else(|| operands[0].id(), |result| result.id());
+						for operand in operands.iter() {
+							self.value_used_by
+								.entry(operand.id())
+								.or_default()
+								.push(user);
+						}
+					}
 					MIRInfo::StoreLiteral(_, _) => {}
 					MIRInfo::StoreValue(lvalue, rvalue) => {
 						self.value_used_by
@@ -220,19 +367,36 @@ impl CommonContext {
 							.or_default()
 							.push(lvalue.id());
 					}
-					MIRInfo::Unary(result, lvalue, _) => {
+					MIRInfo::Unary(result, lvalue, _)
+					| MIRInfo::FromAny(result, lvalue)
+					| MIRInfo::IsNull(result, lvalue)
+					| MIRInfo::ToAny(result, lvalue)
+					| MIRInfo::UnwrapOptional(result, lvalue)
+					| MIRInfo::WrapOptional(result, lvalue) => {
 						self.value_used_by
 							.entry(lvalue.id())
 							.or_default()
 							.push(result.id());
 					}
+					MIRInfo::Yield(value) => {
+						if let Some(value) = value {
+							self.value_used_by
+								.entry(value.id())
+								.or_default()
+								.push(usize::MAX);
+						}
+					}
 				}
 			}
 		}
 	}
 
 	fn print_span(&self, span: &Span) -> String {
-		let contents = std::fs::read_to_string(&self.file_name).expect("Could not read file");
+		let file_name = self
+			.file_names
+			.get(span.file() as usize)
+			.expect("Could not find span file");
+		let contents = std::fs::read_to_string(file_name).expect("Could not read file");
 		contents[span.start() as usize..span.end() as usize].into()
 	}
 }
@@ -1,5 +1,5 @@
 use anyhow::{Context, Result};
-use eggscript_interpreter::{Instruction, RelativeStackAddress};
+use eggscript_interpreter::{Instruction, RelativeStackAddress, SourceSpan};
 use eggscript_types::{FunctionType, TypeStore, P};
 use indexmap::IndexMap;
 use std::collections::HashMap;
@@ -7,23 +7,30 @@ use std::ops::Deref;
 use std::sync::{Arc, Mutex};
 
 use crate::lower::CommonContext;
-use crate::{MIRInfo, Transition, Unit, UnitHandle, Value, MIR};
+use crate::{Dispatch, MIRInfo, Transition, Unit, UnitHandle, Value, MIR};
 
 pub struct EggscriptLowerContext {
 	allocations: Vec<P<Value>>,
 	common_context: CommonContext,
+	/// Source span of every instruction that was lowered, instructions that come from transitions
+	/// take the span of the MIR before them
+	instruction_spans: Vec<Option<SourceSpan>>,
 	jump_instructions: Vec<(usize, UnitHandle)>,
+	/// Stack slots of the variables user code can name, for debuggers
+	local_slots: Vec<(String, usize)>,
 	unit_to_instruction: HashMap<UnitHandle, usize>,
 	units_containing_phi: HashMap<UnitHandle, Vec<UnitHandle>>,
 	value_to_stack: HashMap<usize, usize>,
 }
 
 impl EggscriptLowerContext {
-	pub fn new(type_store: Arc<Mutex<TypeStore>>, file_name: &str) -> Self {
+	pub fn new(type_store: Arc<Mutex<TypeStore>>, file_names: Vec<String>) -> Self {
 		EggscriptLowerContext {
 			allocations: Vec::new(),
-			common_context: CommonContext::new(type_store, file_name),
+			common_context: CommonContext::new(type_store, file_names),
+			instruction_spans: Vec::new(),
 			jump_instructions: Vec::new(),
+			local_slots: Vec::new(),
 			unit_to_instruction: HashMap::new(),
 			units_containing_phi: HashMap::new(),
 			value_to_stack: HashMap::new(),
@@ -34,7 +41,7 @@ impl EggscriptLowerContext {
 		&mut self,
 		units: &IndexMap<UnitHandle, Unit>,
 		function: Option<FunctionType>,
-	) -> Result<Vec<Instruction>> {
+	) -> Result<(Vec<Instruction>, Vec<Option<SourceSpan>>)> {
 		self.common_context.build_value_dependencies(&units);
 		self.common_context
 			.type_check_units(&units, function.as_ref())?;
@@ -57,12 +64,24 @@ impl EggscriptLowerContext {
 		return self.lower_units(units);
 	}
 
-	fn lower_units(&mut self, units: &IndexMap<UnitHandle, Unit>) -> Result<Vec<Instruction>> {
+	/// Names of the variables of the lowered code and the stack slots they live in, relative to
+	/// the base of the frame
+	pub fn local_slots(&self) -> Vec<(String, usize)> {
+		self.local_slots.clone()
+	}
+
+	fn lower_units(
+		&mut self,
+		units: &IndexMap<UnitHandle, Unit>,
+	) -> Result<(Vec<Instruction>, Vec<Option<SourceSpan>>)> {
 		let mut instructions = vec![];
 		for unit in units.values() {
 			let start = instructions.len();
 			self.unit_to_instruction.insert(unit.id, start);
 			instructions.append(&mut self.lower_unit(unit, start)?);
+
+			let span = self.instruction_spans.last().copied().flatten();
+			self.instruction_spans.resize(instructions.len(), span);
 		}
 
 		for (jump_instruction, parent_unit) in self.jump_instructions.iter() {
@@ -127,21 +146,46 @@ impl EggscriptLowerContext {
 							Instruction::JumpIfTrue(relative_jump, *value);
 					}
 				}
+				Instruction::PushCatch(unit_handle) => {
+					let index = *unit_handle as usize;
+					let relative_jump = *self
+						.unit_to_instruction
+						.get(&index)
+						.context("Could not find catch unit")? as isize
+						- *jump_instruction as isize;
+
+					instructions[*jump_instruction] = Instruction::PushCatch(relative_jump);
+				}
 				_ => unreachable!("{:?}", instruction),
 			}
 		}
 
+		let mut spans = std::mem::take(&mut self.instruction_spans);
 		if self.allocations.len() != 0 {
 			instructions.insert(0, Instruction::Reserve(self.allocations.len()));
+			spans.insert(0, None);
 		}
 
-		Ok(instructions)
+		Ok((instructions, spans))
 	}
 
 	fn lower_unit(&mut self, unit: &Unit, instruction_index: usize) -> Result<Vec<Instruction>> {
 		let mut instructions = vec![];
 		for mir in unit.mir.iter() {
-			instructions.append(&mut self.lower_mir(mir)?);
+			// the catch unit's position is only known once every unit is lowered
+			if let MIRInfo::EnterTry(catch_unit) = &mir.info {
+				self.jump_instructions
+					.push((instruction_index + instructions.len(), unit.id));
+
+				instructions.push(Instruction::PushCatch(*catch_unit as isize));
+			} else {
+				instructions.append(&mut self.lower_mir(mir)?);
+			}
+
+			self.instruction_spans.resize(
+				instruction_index + instructions.len(),
+				Some((&mir.span).into()),
+			);
 		}
 
 		// TODO try to remove need for this
@@ -199,8 +243,36 @@ impl EggscriptLowerContext {
 			}
 			Transition::Invalid => todo!(),
 			Transition::Next => {}
-			Transition::Return(value) => {
-				instructions.push(Instruction::Return(value.is_some()));
+			Transition::Return(values) => {
+				let type_store = self
+					.common_context
+					.type_store
+					.lock()
+					.expect("Could not lock type store");
+
+				// tuples returned from function calls take up one stack slot per element
+				let slot_count = values
+					.iter()
+					.map(|value| type_store.get_slot_count(value.ty()))
+					.sum();
+
+				instructions.push(Instruction::Return(slot_count));
+			}
+			Transition::Throw(value) => {
+				let stack_address = match value.deref() {
+					Value::Location { id, .. } => *self
+						.value_to_stack
+						.get(id)
+						.context("Could not get thrown value stack index")?
+						as RelativeStackAddress,
+					Value::Primitive { value, .. } => {
+						instructions.push(Instruction::Push(value.into()));
+						-1
+					}
+					Value::Temp { .. } => -1,
+				};
+
+				instructions.push(Instruction::Throw(stack_address));
 			}
 		}
 
@@ -214,14 +286,20 @@ impl EggscriptLowerContext {
 					self.allocations.push(value.clone());
 				}
 
-				self.value_to_stack.insert(
-					value.id(),
-					if let Some(stack_position) = stack_position {
-						*stack_position
-					} else {
-						self.value_to_stack.len()
-					},
-				);
+				let slot = if let Some(stack_position) = stack_position {
+					*stack_position
+				} else {
+					self.value_to_stack.len()
+				};
+
+				self.value_to_stack.insert(value.id(), slot);
+
+				// compiler generated variables have names user code cannot write
+				if let Value::Location { name, .. } = value.deref()
+					&& !name.contains(':')
+				{
+					self.local_slots.push((name.clone(), slot));
+				}
 
 				Ok(vec![])
 			}
@@ -291,7 +369,7 @@ impl EggscriptLowerContext {
 
 				Ok(instructions)
 			}
-			MIRInfo::CallFunction(name, function_handle, arguments, result) => {
+			MIRInfo::CallFunction(name, function_handle, dispatch, arguments, result) => {
 				let mut instructions = vec![];
 				for argument in arguments.iter() {
 					match argument.deref() {
@@ -320,18 +398,124 @@ impl EggscriptLowerContext {
 					.get_function(name)
 					.expect("Could not find function");
 
-				instructions.push(Instruction::CallFunction(*function_handle));
+				// the interpreter calls every function through its function table, so only parent
+				// calls need their own instruction
+				if let Dispatch::Parent(caller_handle) = dispatch {
+					instructions.push(Instruction::CallParentFunction(
+						*caller_handle,
+						arguments.len(),
+					));
+				} else {
+					instructions.push(Instruction::CallFunction(*function_handle, arguments.len()));
+				}
 
 				// if the result isn't used, then pop it from the stack
 				if !self.common_context.value_used_by.contains_key(&result.id())
-					&& function_type.return_type.is_some()
+					&& let Some(return_type) = function_type.return_type
 				{
-					instructions.push(Instruction::Pop);
+					for _ in 0..type_store.get_slot_count(return_type) {
+						instructions.push(Instruction::Pop);
+					}
 				}
 
 				Ok(instructions)
 			}
+			MIRInfo::Catch(value) => {
+				// the interpreter pushes the exception before it jumps to the catch unit
+				let stack_address = self.value_to_stack.get(&value.id()).context(format!(
+					"Value {} has not been allocated to stack",
+					value.id()
+				))?;
+
+				Ok(vec![Instruction::Store(*stack_address, -1)])
+			}
+			MIRInfo::Destructure(lvalues, _) => {
+				// the tuple's elements are on top of the stack, so store them last to first
+				let mut instructions = vec![];
+				for lvalue in lvalues.iter().rev() {
+					let stack_address = self.value_to_stack.get(&lvalue.id()).context(format!(
+						"Value {} has not been allocated to stack",
+						lvalue.id()
+					))?;
+
+					instructions.push(Instruction::Store(*stack_address, -1));
+				}
+
+				Ok(instructions)
+			}
+			MIRInfo::EnterTry(_) => unreachable!("try blocks are entered in lower_unit"),
+			MIRInfo::ExitTry => Ok(vec![Instruction::PopCatch]),
+			MIRInfo::FromAny(result, value) => {
+				let mut instructions = vec![];
+				let stack_address = match value.deref() {
+					Value::Location { id, .. } => *self
+						.value_to_stack
+						.get(id)
+						.context("Could not get converted value stack index")?
+						as RelativeStackAddress,
+					Value::Primitive { value, .. } => {
+						instructions.push(Instruction::Push(value.into()));
+						-1
+					}
+					Value::Temp { .. } => -1,
+				};
+
+				let is_string = self
+					.common_context
+					.type_store
+					.lock()
+					.expect("Could not lock type store")
+					.name_to_type_handle("string")
+					.is_some_and(|string| string == result.ty());
+
+				if is_string {
+					instructions.push(Instruction::ConvertToString(stack_address));
+				} else {
+					instructions.push(Instruction::ConvertToNumber(stack_address));
+				}
+
+				Ok(instructions)
+			}
+			MIRInfo::IsNull(_, value) => {
+				let stack_address = match value.deref() {
+					Value::Location { id, .. } => *self
+						.value_to_stack
+						.get(id)
+						.context("Could not get null checked value stack index")?
+						as RelativeStackAddress,
+					Value::Primitive { .. } => unreachable!(),
+					Value::Temp { .. } => -1,
+				};
+
+				Ok(vec![Instruction::IsNull(stack_address)])
+			}
 			MIRInfo::LogicPhi(_, _, _) => Ok(vec![]),
+			MIRInfo::MapOperation(result, operation, operands) => {
+				let mut operand_positions = vec![];
+				for operand in operands.iter() {
+					operand_positions.push(match operand.deref() {
+						Value::Location { id, .. } => *self
+							.value_to_stack
+							.get(id)
+							.context("Could not get map operand stack index")?
+							as RelativeStackAddress,
+						// primitive operands are stored in temps before the operation
+						Value::Primitive { .. } => unreachable!(),
+						Value::Temp { .. } => -1,
+					});
+				}
+
+				let mut instructions =
+					vec![Instruction::MapOperation(*operation, operand_positions)];
+
+				if let Some(result) = result
+					&& !self.common_context.value_used_by.contains_key(&result.id())
+				{
+					instructions.push(Instruction::Pop);
+				}
+
+				Ok(instructions)
+			}
 			MIRInfo::StoreLiteral(lvalue, rvalue) => {
 				let left_stack_address = match lvalue.deref() {
 					Value::Location { .. } => *self.value_to_stack.get(&lvalue.id()).context(
@@ -421,6 +605,46 @@ impl EggscriptLowerContext {
 
 				return Ok(instructions);
 			}
+			// optionals and `any` values are plain values in the interpreter, so they are only
+			// moved to the top of the stack where the result temp lives
+			MIRInfo::ToAny(_, value)
+			| MIRInfo::UnwrapOptional(_, value)
+			| MIRInfo::WrapOptional(_, value) => match value.deref() {
+				Value::Location { id, .. } => {
+					let stack_address = self
+						.value_to_stack
+						.get(id)
+						.context("Could not get optional value stack index")?;
+
+					Ok(vec![Instruction::CopyPush(*stack_address)])
+				}
+				Value::Primitive { value, .. } => Ok(vec![Instruction::Push(value.into())]),
+				Value::Temp { .. } => Ok(vec![]),
+			},
+			MIRInfo::Yield(value) => {
+				let stack_address = match value.as_ref().map(|value| value.deref()) {
+					Some(Value::Location { id, .. }) => *self
+						.value_to_stack
+						.get(id)
+						.context("Could not get yielded value stack index")?
+						as RelativeStackAddress,
+					Some(Value::Primitive { value, .. }) => {
+						return Ok(vec![
+							Instruction::Push(value.into()),
+							Instruction::Yield(-1),
+						]);
+					}
+					Some(Value::Temp { .. }) => -1,
+					None => {
+						return Ok(vec![
+							Instruction::Push(eggscript_interpreter::Value::Null),
+							Instruction::Yield(-1),
+						]);
+					}
+				};
+
+				Ok(vec![Instruction::Yield(stack_address)])
+			}
 		}
 	}
 }
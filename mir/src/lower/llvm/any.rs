@@ -0,0 +1,135 @@
+use anyhow::Result;
+use eggscript_interpreter::runtime::any::{ANY_NUMBER_TAG, ANY_STRING_TAG};
+use eggscript_types::P;
+use inkwell::types::StructType;
+use inkwell::values::FunctionValue;
+use inkwell::AddressSpace;
+
+use crate::Value;
+
+use super::LlvmLowerContext;
+
+/// `double(i8, double, ptr)` function that converts an `any` value to a number, raising an
+/// exception if it cannot be converted
+pub const ANY_TO_NUMBER_FUNCTION: &str = "eggscript_any_to_number";
+/// `ptr(i8, double, ptr)` function that converts an `any` value to a string
+pub const ANY_TO_STRING_FUNCTION: &str = "eggscript_any_to_string";
+
+impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
+	/// `any` values are a tag followed by a slot for each type the value can have
+	pub(crate) fn any_type(&self) -> StructType<'ctx> {
+		self.context.struct_type(
+			&[
+				self.context.i8_type().into(),
+				self.context.f64_type().into(),
+				self.context.ptr_type(AddressSpace::default()).into(),
+			],
+			false,
+		)
+	}
+
+	pub(crate) fn build_to_any(&mut self, result: &P<Value>, value: &P<Value>) -> Result<()> {
+		let is_string = self
+			.common_context
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.name_to_type_handle("string")
+			.is_some_and(|string| string == value.ty());
+
+		let (tag, slot) = if is_string {
+			(ANY_STRING_TAG, 2)
+		} else {
+			(ANY_NUMBER_TAG, 1)
+		};
+
+		let any = self.builder.build_insert_value(
+			self.any_type().const_zero(),
+			self.context.i8_type().const_int(tag as u64, false),
+			0,
+			"any_tag_",
+		)?;
+
+		let any = self.builder.build_insert_value(
+			any,
+			self.maybe_deref_llvm_value(value)?,
+			slot,
+			&format!("any{}_", result.id()),
+		)?;
+
+		self.value_to_basic_value
+			.insert(result.id(), any.into_struct_value().into());
+
+		Ok(())
+	}
+
+	pub(crate) fn build_from_any(
+		&mut self,
+		result: &P<Value>,
+		value: &P<Value>,
+		function: FunctionValue<'ctx>,
+	) -> Result<()> {
+		let is_string = self
+			.common_context
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.name_to_type_handle("string")
+			.is_some_and(|string| string == result.ty());
+
+		let parameter_types = [
+			self.context.i8_type().into(),
+			self.context.f64_type().into(),
+			self.context.ptr_type(AddressSpace::default()).into(),
+		];
+
+		let (name, function_type) = if is_string {
+			(
+				ANY_TO_STRING_FUNCTION,
+				self.context
+					.ptr_type(AddressSpace::default())
+					.fn_type(&parameter_types, false),
+			)
+		} else {
+			(
+				ANY_TO_NUMBER_FUNCTION,
+				self.context.f64_type().fn_type(&parameter_types, false),
+			)
+		};
+
+		let conversion_function = self
+			.module
+			.get_function(name)
+			.unwrap_or_else(|| self.module.add_function(name, function_type, None));
+
+		let any = self.maybe_deref_llvm_value(value)?.into_struct_value();
+		let mut arguments = vec![];
+		for index in 0..3 {
+			arguments.push(
+				self.builder
+					.build_extract_value(any, index, "any_field_")?
+					.into(),
+			);
+		}
+
+		let converted = self
+			.builder
+			.build_call(
+				conversion_function,
+				&arguments,
+				&format!("converted{}_", result.id()),
+			)?
+			.try_as_basic_value()
+			.left()
+			.expect("Expected converted value where there is none");
+
+		// strings are pointers themselves, so the value is stored the same way call results are
+		self.alloc_llvm_value(result)?;
+		self.builder
+			.build_store(self.value_to_llvm_pointer_value(result)?, converted)?;
+
+		self.build_exception_check(function)?;
+
+		Ok(())
+	}
+}
@@ -1,4 +1,4 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use eggscript_types::{FunctionType, KnownTypeInfo, Primitive, Type, TypeHandle, TypeStore, P};
 use indexmap::IndexMap;
 use inkwell::basic_block::BasicBlock;
@@ -8,7 +8,7 @@ use inkwell::passes::PassBuilderOptions;
 use inkwell::targets::{CodeModel, InitializationConfig, RelocMode, Target, TargetMachine};
 use inkwell::types::{BasicMetadataTypeEnum, BasicType, BasicTypeEnum};
 use inkwell::values::{BasicValueEnum, FloatValue, FunctionValue, PhiValue, PointerValue};
-use inkwell::{context, FloatPredicate, OptimizationLevel};
+use inkwell::{context, AddressSpace, FloatPredicate, OptimizationLevel};
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::path::Path;
@@ -24,9 +24,19 @@ pub struct LlvmLowerContext<'a, 'ctx> {
 	pub(crate) builder: &'a Builder<'ctx>,
 	pub(crate) common_context: CommonContext,
 	pub(crate) context: &'ctx context::Context,
+	/// Whether the function that is being lowered counts itself in the call depth
+	pub(crate) counts_call_depth: bool,
+	/// Catch unit of the unit that is being lowered
+	pub(crate) current_unwind: Option<UnitHandle>,
+	/// Name of the function the global scope is lowered into
+	pub(crate) entry_name: String,
 	pub(crate) module: &'a Module<'ctx>,
 	pub(crate) phi_value_for_unit: HashMap<usize, PhiValue<'ctx>>,
+	/// Block that control leaves a unit from, calls split units into several blocks
+	pub(crate) unit_end_blocks: HashMap<usize, BasicBlock<'ctx>>,
 	pub(crate) units_to_blocks: HashMap<usize, BasicBlock<'ctx>>,
+	/// Block that returns from the current function when an exception is not caught
+	pub(crate) unwind_block: Option<BasicBlock<'ctx>>,
 	pub(crate) value_to_basic_value: HashMap<usize, BasicValueEnum<'ctx>>,
 }
 
@@ -36,15 +46,20 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 		builder: &'a Builder<'ctx>,
 		module: &'a Module<'ctx>,
 		type_store: Arc<Mutex<TypeStore>>,
-		file_name: &str,
+		file_names: Vec<String>,
 	) -> Self {
 		LlvmLowerContext {
 			builder,
-			common_context: CommonContext::new(type_store, file_name),
+			common_context: CommonContext::new(type_store, file_names),
 			context,
+			counts_call_depth: false,
+			current_unwind: None,
+			entry_name: "entry".into(),
 			module,
 			phi_value_for_unit: HashMap::new(),
+			unit_end_blocks: HashMap::new(),
 			units_to_blocks: HashMap::new(),
+			unwind_block: None,
 			value_to_basic_value: HashMap::new(),
 		}
 	}
@@ -61,12 +76,17 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 		return self.lower_units(units, function.as_ref());
 	}
 
+	/// Global scopes loaded into the same execution engine need distinct names
+	pub fn set_entry_name(&mut self, name: &str) {
+		self.entry_name = name.into();
+	}
+
 	pub fn write(&self) {
 		self.module.write_bitcode_to_path(Path::new("./test.bc"));
 	}
 
 	pub fn pre_define_function(&self, function: &FunctionType) -> Result<()> {
-		let args = function
+		let mut args = function
 			.argument_types
 			.iter()
 			.map(|arg_type| self.type_to_llvm_basic_type(*arg_type))
@@ -75,6 +95,12 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 			.map(|ty| (*ty).into())
 			.collect::<Vec<BasicMetadataTypeEnum>>();
 
+		// variadic arguments are passed as an argument count followed by a pointer to an array
+		if function.variadic_type.is_some() {
+			args.push(self.context.i64_type().into());
+			args.push(self.context.ptr_type(AddressSpace::default()).into());
+		}
+
 		let fn_type = if let Some(return_type) = function.return_type {
 			self.type_to_llvm_basic_type(return_type)?
 				.fn_type(&args, false)
@@ -136,10 +162,44 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 		match ty {
 			Some(Type::FunctionReturn { .. }) => todo!(),
 			Some(Type::Known { info, .. }) => match info {
+				KnownTypeInfo::Enum(_) => Ok(self.context.f64_type().into()),
+				// maps live in the runtime, so JIT code only handles pointers to them
+				KnownTypeInfo::Map(_, _) => {
+					Ok(self.context.ptr_type(AddressSpace::default()).into())
+				}
+				// optionals are a pair of a flag that is set when there is a value, and the value
+				KnownTypeInfo::Optional(inner) => {
+					let inner = *inner;
+					drop(type_store);
+
+					Ok(self
+						.context
+						.struct_type(
+							&[
+								self.context.bool_type().into(),
+								self.type_to_llvm_basic_type(inner)?,
+							],
+							false,
+						)
+						.into())
+				}
 				KnownTypeInfo::Primitive(primitive) => match primitive {
+					Primitive::Any => Ok(self.any_type().into()),
 					Primitive::Number => Ok(self.context.f64_type().into()),
-					Primitive::Null => todo!(),
+					Primitive::Null => Ok(self.context.struct_type(&[], false).into()),
+					Primitive::String => Ok(self.context.ptr_type(AddressSpace::default()).into()),
 				},
+				KnownTypeInfo::Tuple(element_types) => {
+					let element_types = element_types.clone();
+					drop(type_store);
+
+					let mut llvm_element_types = vec![];
+					for element_type in element_types.into_iter() {
+						llvm_element_types.push(self.type_to_llvm_basic_type(element_type)?);
+					}
+
+					Ok(self.context.struct_type(&llvm_element_types, false).into())
+				}
 			},
 			Some(Type::Unknown { .. }) => todo!(),
 			None => unreachable!(),
@@ -154,7 +214,7 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 		let function_name = if let Some(function) = function {
 			&function.name
 		} else {
-			"entry"
+			&self.entry_name
 		};
 
 		let llvm_function = if let Some(llvm_function) = self.module.get_function(function_name) {
@@ -167,6 +227,20 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 			)
 		};
 
+		self.unwind_block = None;
+
+		// only script functions can recurse, the global scope runs once
+		self.counts_call_depth = function.is_some();
+		if self.counts_call_depth {
+			let first_unit = units.values().next().expect("Could not get first unit");
+			let first_block = self.get_unit_block(first_unit.id, llvm_function);
+			let prologue_block = self.context.prepend_basic_block(first_block, "prologue");
+
+			self.builder.position_at_end(prologue_block);
+			self.current_unwind = None;
+			self.build_call_depth_prologue(llvm_function, first_block)?;
+		}
+
 		for unit in units.values() {
 			self.lower_unit(&unit, llvm_function)?;
 		}
@@ -174,11 +248,26 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 		let units_vector = units.values().collect::<Vec<&Unit>>();
 		for i in 0..units_vector.len() {
 			let unit = units_vector.get(i).expect("Could not get unit");
+
+			// units are laid out in order, so branches to earlier units are the back-edges of loops
+			let branch_target = match &unit.transition {
+				Transition::Goto(target)
+				| Transition::GotoIfFalse(target, _)
+				| Transition::GotoIfTrue(target, _) => Some(target),
+				_ => None,
+			};
+
+			if let Some(target) = branch_target
+				&& units.get_index_of(target).is_some_and(|index| index <= i)
+			{
+				self.build_back_edge_poll(unit, llvm_function)?;
+			}
+
 			match &unit.transition {
 				Transition::Goto(other) => {
 					self.builder.position_at_end(
 						*self
-							.units_to_blocks
+							.unit_end_blocks
 							.get(&unit.id)
 							.expect("Could not find unit"),
 					);
@@ -193,7 +282,7 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 				Transition::GotoIfFalse(else_unit, value) => {
 					self.builder.position_at_end(
 						*self
-							.units_to_blocks
+							.unit_end_blocks
 							.get(&unit.id)
 							.expect("Could not find unit"),
 					);
@@ -222,7 +311,7 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 				Transition::GotoIfTrue(then_unit, value) => {
 					self.builder.position_at_end(
 						*self
-							.units_to_blocks
+							.unit_end_blocks
 							.get(&unit.id)
 							.expect("Could not find unit"),
 					);
@@ -252,13 +341,15 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 				Transition::Next => {
 					self.builder.position_at_end(
 						*self
-							.units_to_blocks
+							.unit_end_blocks
 							.get(&unit.id)
 							.expect("Could not find unit"),
 					);
 
 					if i + 1 >= units_vector.len() {
 						if let Some(function) = function {
+							self.build_call_depth_epilogue()?;
+
 							// TODO fix type issue
 							self.builder.build_return(Some(
 								&self
@@ -279,15 +370,24 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 							.expect("Could not find branch target unit"),
 					)?;
 				}
-				Transition::Return(value) => {
+				Transition::Return(values) => {
 					self.builder.position_at_end(
 						*self
-							.units_to_blocks
+							.unit_end_blocks
 							.get(&unit.id)
 							.expect("Could not find unit"),
 					);
 
-					if let Some(value) = value {
+					self.build_call_depth_epilogue()?;
+
+					if values.len() > 1 {
+						let mut llvm_values = vec![];
+						for value in values.iter() {
+							llvm_values.push(self.maybe_deref_llvm_value(value)?);
+						}
+
+						self.builder.build_aggregate_return(&llvm_values)?;
+					} else if let Some(value) = values.first() {
 						// TODO fix type issue
 						self.builder
 							.build_return(Some(&self.maybe_deref_llvm_value(value)?))?;
@@ -295,6 +395,17 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 						self.builder.build_return(None)?;
 					}
 				}
+				Transition::Throw(value) => {
+					self.builder.position_at_end(
+						*self
+							.unit_end_blocks
+							.get(&unit.id)
+							.expect("Could not find unit"),
+					);
+
+					self.current_unwind = unit.unwind;
+					self.build_throw(value, llvm_function)?;
+				}
 			}
 		}
 
@@ -332,7 +443,7 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 						// need to dereference pointer
 						let float_value = if is_pointer_value {
 							let block = *self
-								.units_to_blocks
+								.unit_end_blocks
 								.get(unit)
 								.expect("Could not find block");
 
@@ -363,7 +474,7 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 							.add_incoming(&[(
 								&float_value,
 								*self
-									.units_to_blocks
+									.unit_end_blocks
 									.get(unit)
 									.expect("Could not find block"),
 							)]);
@@ -374,13 +485,16 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 		}
 
 		if function.is_none() {
-			self.builder.position_at_end(
-				*self
-					.units_to_blocks
-					.get(&units_vector.last().expect("Could not get last unit").id)
+			// files that only declare functions and packages have no global code
+			let last_block = match units_vector.last() {
+				Some(unit) => *self
+					.unit_end_blocks
+					.get(&unit.id)
 					.expect("Could not find unit"),
-			);
+				None => self.context.append_basic_block(llvm_function, "entry"),
+			};
 
+			self.builder.position_at_end(last_block);
 			self.builder.build_return(None)?;
 		}
 
@@ -390,17 +504,21 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 	}
 
 	fn lower_unit(&mut self, unit: &Unit, function: FunctionValue<'ctx>) -> Result<()> {
-		let block = self
-			.context
-			.append_basic_block(function, &format!("unit{}", unit.id));
-
-		self.units_to_blocks.insert(unit.id, block);
+		let block = self.get_unit_block(unit.id, function);
 		self.builder.position_at_end(block);
+		self.current_unwind = unit.unwind;
 
 		for mir in unit.mir.iter() {
 			self.lower_mir(unit.id, mir, function)?;
 		}
 
+		self.unit_end_blocks.insert(
+			unit.id,
+			self.builder
+				.get_insert_block()
+				.expect("Could not get unit end block"),
+		);
+
 		Ok(())
 	}
 
@@ -416,6 +534,7 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 				.into_float_value()),
 			Value::Primitive { value, .. } => match value {
 				PrimitiveValue::Number(value) => Ok(self.context.f64_type().const_float(*value)),
+				PrimitiveValue::Null | PrimitiveValue::String(_) => unreachable!(),
 			},
 			Value::Temp { id, .. } => {
 				let basic_value = self
@@ -446,10 +565,11 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 			.lock()
 			.expect("Could not lock type store");
 
-		let is_primitive = type_store
+		let ty = type_store
 			.get_type(value.ty())
-			.expect("Could not find value type")
-			.is_primitive();
+			.expect("Could not find value type");
+
+		let is_primitive = ty.is_primitive() || ty.is_tuple();
 
 		drop(type_store);
 
@@ -474,14 +594,25 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 				return Ok(basic_value.clone());
 			}
 			Value::Primitive { value, .. } => match value {
+				PrimitiveValue::Null => {
+					Ok(self.context.struct_type(&[], false).const_zero().into())
+				}
 				PrimitiveValue::Number(value) => {
 					Ok(self.context.f64_type().const_float(*value).into())
 				}
+				PrimitiveValue::String(value) => Ok(self
+					.builder
+					.build_global_string_ptr(value, "string_")?
+					.as_pointer_value()
+					.into()),
 			},
 		}
 	}
 
-	fn value_to_llvm_pointer_value(&self, value: &P<Value>) -> Result<PointerValue<'ctx>> {
+	pub(crate) fn value_to_llvm_pointer_value(
+		&self,
+		value: &P<Value>,
+	) -> Result<PointerValue<'ctx>> {
 		Ok(self
 			.value_to_basic_value
 			.get(&value.id())
@@ -489,22 +620,34 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 			.into_pointer_value())
 	}
 
-	fn alloc_llvm_value(&mut self, value: &P<Value>) -> Result<()> {
+	pub(crate) fn alloc_llvm_value(&mut self, value: &P<Value>) -> Result<()> {
 		if !self.value_to_basic_value.contains_key(&value.id()) {
-			self.value_to_basic_value.insert(
-				value.id(),
-				self.builder
-					.build_alloca(
-						self.type_to_llvm_basic_type(value.ty())?,
-						&format!("temp{}_", value.id()),
-					)?
-					.into(),
-			);
+			let alloca = self.build_entry_alloca(value, &format!("temp{}_", value.id()))?;
+			self.value_to_basic_value.insert(value.id(), alloca.into());
 		}
 
 		Ok(())
 	}
 
+	/// Allocas are built in the entry block, so values that are first stored inside of a loop
+	/// body are allocated once and can be used after the loop
+	fn build_entry_alloca(&self, value: &P<Value>, name: &str) -> Result<PointerValue<'ctx>> {
+		let entry_block = self
+			.builder
+			.get_insert_block()
+			.and_then(|block| block.get_parent())
+			.and_then(|function| function.get_first_basic_block())
+			.context("Could not get entry block")?;
+
+		let builder = self.context.create_builder();
+		match entry_block.get_first_instruction() {
+			Some(instruction) => builder.position_before(&instruction),
+			None => builder.position_at_end(entry_block),
+		}
+
+		Ok(builder.build_alloca(self.type_to_llvm_basic_type(value.ty())?, name)?)
+	}
+
 	fn lower_mir(
 		&mut self,
 		current_unit: UnitHandle,
@@ -513,10 +656,7 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 	) -> Result<()> {
 		match &mir.info {
 			MIRInfo::Allocate(value, argument_position) => {
-				let alloca = self.builder.build_alloca(
-					self.type_to_llvm_basic_type(value.ty())?,
-					&format!("variable{}_", value.id()),
-				)?;
+				let alloca = self.build_entry_alloca(value, &format!("variable{}_", value.id()))?;
 
 				if let Some(argument_position) = argument_position {
 					let params = function.get_params();
@@ -594,18 +734,12 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 					BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr => unreachable!(),
 				}
 			}
-			MIRInfo::CallFunction(name, _, arguments, return_value) => {
+			MIRInfo::CallFunction(name, function_handle, dispatch, arguments, return_value) => {
 				let llvm_function = self
 					.module
 					.get_function(name)
 					.context("Could not find LLVM function")?;
 
-				let mut args = vec![];
-				for argument in arguments.iter() {
-					// TODO fix type issue
-					args.push(self.maybe_deref_llvm_value(argument)?.into());
-				}
-
 				let type_store = self
 					.common_context
 					.type_store
@@ -613,17 +747,85 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 					.expect("Could not lock type store");
 				let function_type = type_store
 					.get_function(name)
-					.context("Could not find function")?;
+					.context("Could not find function")?
+					.clone();
 
-				let llvm_return_value = self.builder.build_call(
-					llvm_function,
-					&args,
-					&format!("returnval{}_", return_value.id()),
-				)?;
+				drop(type_store);
 
-				if function_type.return_type.is_some() {
-					drop(type_store);
+				let argument_count = function_type.argument_types.len();
+
+				let mut args = vec![];
+				for argument in arguments.iter().take(argument_count) {
+					// TODO fix type issue
+					args.push(self.maybe_deref_llvm_value(argument)?.into());
+				}
 
+				if let Some(variadic_type) = function_type.variadic_type {
+					let variadic_arguments = &arguments[argument_count..];
+					let element_type = self.type_to_llvm_basic_type(variadic_type)?;
+					let array_type = element_type.array_type(variadic_arguments.len() as u32);
+					let array = self
+						.builder
+						.build_alloca(array_type, &format!("variadic{}_", return_value.id()))?;
+
+					let mut index = 0;
+					for argument in variadic_arguments.iter() {
+						let element = unsafe {
+							self.builder.build_in_bounds_gep(
+								array_type,
+								array,
+								&[
+									self.context.i64_type().const_zero(),
+									self.context.i64_type().const_int(index, false),
+								],
+								"variadic_element_",
+							)?
+						};
+
+						self.builder
+							.build_store(element, self.maybe_deref_llvm_value(argument)?)?;
+						index += 1;
+					}
+
+					args.push(
+						self.context
+							.i64_type()
+							.const_int(variadic_arguments.len() as u64, false)
+							.into(),
+					);
+					args.push(array.into());
+				}
+
+				let llvm_return_value =
+					if let Some(slot_name) = dispatch.slot_name(*function_handle) {
+						// the host fills in slots when it rebinds functions, so they are only
+						// declared here
+						let ptr_type = self.context.ptr_type(AddressSpace::default());
+						let slot = self
+							.module
+							.get_global(&slot_name)
+							.unwrap_or_else(|| self.module.add_global(ptr_type, None, &slot_name));
+
+						let function_pointer = self
+							.builder
+							.build_load(ptr_type, slot.as_pointer_value(), "function_pointer_")?
+							.into_pointer_value();
+
+						self.builder.build_indirect_call(
+							llvm_function.get_type(),
+							function_pointer,
+							&args,
+							&format!("returnval{}_", return_value.id()),
+						)?
+					} else {
+						self.builder.build_call(
+							llvm_function,
+							&args,
+							&format!("returnval{}_", return_value.id()),
+						)?
+					};
+
+				if function_type.return_type.is_some() {
 					self.alloc_llvm_value(return_value)?;
 
 					self.builder.build_store(
@@ -634,6 +836,45 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 							.expect("Expected return basic value where there is none"),
 					)?;
 				}
+
+				self.build_exception_check(function)?;
+			}
+			MIRInfo::Catch(value) => self.build_catch(value, function)?,
+			MIRInfo::Destructure(lvalues, rvalue) => {
+				let tuple = self.maybe_deref_llvm_value(rvalue)?.into_struct_value();
+
+				let mut index = 0;
+				for lvalue in lvalues.iter() {
+					let element = self.builder.build_extract_value(
+						tuple,
+						index,
+						&format!("element{}_", lvalue.id()),
+					)?;
+
+					self.builder
+						.build_store(self.value_to_llvm_pointer_value(lvalue)?, element)?;
+					index += 1;
+				}
+			}
+			// exceptional edges are known statically, so try blocks need no bookkeeping at runtime
+			MIRInfo::EnterTry(_) | MIRInfo::ExitTry => {}
+			MIRInfo::FromAny(result, value) => self.build_from_any(result, value, function)?,
+			MIRInfo::IsNull(result, value) => {
+				let optional = self.maybe_deref_llvm_value(value)?.into_struct_value();
+				let has_value = self
+					.builder
+					.build_extract_value(optional, 0, &format!("has_value{}_", result.id()))?
+					.into_int_value();
+
+				let is_null = self.builder.build_not(has_value, "is_null_")?;
+				let is_null = self.builder.build_int_z_extend(
+					is_null,
+					self.context.i64_type(),
+					"z_extend_",
+				)?;
+
+				self.value_to_basic_value
+					.insert(result.id(), self.build_int_to_double_cast(is_null)?.into());
 			}
 			MIRInfo::LogicPhi(result, _, _) => {
 				// TODO type stuff???
@@ -644,16 +885,34 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 
 				self.phi_value_for_unit.insert(current_unit, phi_result);
 			}
+			MIRInfo::MapOperation(result, operation, operands) => {
+				self.build_map_operation(result, *operation, operands, function)?
+			}
 			MIRInfo::StoreLiteral(value, primitive_value) => {
 				self.alloc_llvm_value(value)?;
 
 				match primitive_value {
+					PrimitiveValue::Null => {
+						// only optionals can be null, and their flag is cleared
+						self.builder.build_store(
+							self.value_to_llvm_pointer_value(&value)?,
+							self.type_to_llvm_basic_type(value.ty())?.const_zero(),
+						)?;
+					}
 					PrimitiveValue::Number(number) => {
 						self.builder.build_store(
 							self.value_to_llvm_pointer_value(&value)?,
 							self.context.f64_type().const_float(*number),
 						)?;
 					}
+					PrimitiveValue::String(string) => {
+						self.builder.build_store(
+							self.value_to_llvm_pointer_value(&value)?,
+							self.builder
+								.build_global_string_ptr(string, "string_")?
+								.as_pointer_value(),
+						)?;
+					}
 				}
 			}
 			MIRInfo::StoreValue(lvalue, rvalue) => {
@@ -686,6 +945,59 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 
 				self.value_to_basic_value.insert(result_value.id(), result);
 			}
+			MIRInfo::ToAny(result, value) => self.build_to_any(result, value)?,
+			MIRInfo::UnwrapOptional(result, value) => {
+				let optional = self.maybe_deref_llvm_value(value)?.into_struct_value();
+				let inner = self.builder.build_extract_value(
+					optional,
+					1,
+					&format!("unwrapped{}_", result.id()),
+				)?;
+
+				// strings are pointers themselves, so the value is stored the same way call
+				// results are
+				self.alloc_llvm_value(result)?;
+				self.builder
+					.build_store(self.value_to_llvm_pointer_value(result)?, inner)?;
+			}
+			MIRInfo::WrapOptional(result, value) => {
+				let optional_type = self
+					.type_to_llvm_basic_type(result.ty())?
+					.into_struct_type();
+
+				let type_store = self
+					.common_context
+					.type_store
+					.lock()
+					.expect("Could not lock type store");
+				let is_null = type_store.is_null(value.ty());
+				drop(type_store);
+
+				let optional = if is_null {
+					optional_type.const_zero()
+				} else {
+					let optional = self.builder.build_insert_value(
+						optional_type.get_undef(),
+						self.context.bool_type().const_int(1, false),
+						0,
+						"has_value_",
+					)?;
+
+					self.builder
+						.build_insert_value(
+							optional,
+							self.maybe_deref_llvm_value(value)?,
+							1,
+							&format!("wrapped{}_", result.id()),
+						)?
+						.into_struct_value()
+				};
+
+				self.value_to_basic_value
+					.insert(result.id(), optional.into());
+			}
+			// compiled code runs to completion, it has nowhere to save its state to
+			MIRInfo::Yield(_) => bail!("yield is only supported by the interpreter"),
 		}
 
 		Ok(())
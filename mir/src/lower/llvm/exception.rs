@@ -0,0 +1,168 @@
+use anyhow::Result;
+use eggscript_types::P;
+use inkwell::basic_block::BasicBlock;
+use inkwell::values::FunctionValue;
+use inkwell::{AddressSpace, IntPredicate};
+
+use crate::{UnitHandle, Value};
+
+use super::LlvmLowerContext;
+
+/// Flag the host sets while an exception is pending, the host maps it onto its runtime
+pub const EXCEPTION_PENDING_GLOBAL: &str = "eggscript_exception_pending";
+/// `void(ptr)` function that raises the given string as an exception
+pub const THROW_FUNCTION: &str = "eggscript_throw";
+/// `ptr()` function that clears the pending exception and returns it
+pub const CATCH_FUNCTION: &str = "eggscript_catch";
+
+impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
+	/// Catch units are branched to before they are lowered, so blocks are created on first use
+	pub(crate) fn get_unit_block(
+		&mut self,
+		unit: UnitHandle,
+		function: FunctionValue<'ctx>,
+	) -> BasicBlock<'ctx> {
+		if let Some(block) = self.units_to_blocks.get(&unit) {
+			return *block;
+		}
+
+		let block = self
+			.context
+			.append_basic_block(function, &format!("unit{}", unit));
+
+		self.units_to_blocks.insert(unit, block);
+
+		return block;
+	}
+
+	/// Block that exceptions raised in the current unit branch to. Exceptions outside of try blocks
+	/// return a dummy value, and the caller's own check after the call passes them on
+	pub(crate) fn get_unwind_block(
+		&mut self,
+		function: FunctionValue<'ctx>,
+	) -> Result<BasicBlock<'ctx>> {
+		if let Some(catch_unit) = self.current_unwind {
+			return Ok(self.get_unit_block(catch_unit, function));
+		}
+
+		if let Some(block) = self.unwind_block {
+			return Ok(block);
+		}
+
+		let current_block = self
+			.builder
+			.get_insert_block()
+			.expect("Could not get current block");
+
+		let block = self.context.append_basic_block(function, "unwind");
+		self.builder.position_at_end(block);
+		self.build_call_depth_epilogue()?;
+
+		if let Some(return_type) = function.get_type().get_return_type() {
+			self.builder.build_return(Some(&return_type.const_zero()))?;
+		} else {
+			self.builder.build_return(None)?;
+		}
+
+		self.builder.position_at_end(current_block);
+		self.unwind_block = Some(block);
+
+		Ok(block)
+	}
+
+	/// Branches to the unwind block if the call that was just built raised an exception
+	pub(crate) fn build_exception_check(&mut self, function: FunctionValue<'ctx>) -> Result<()> {
+		let i8_type = self.context.i8_type();
+		let pending = self
+			.module
+			.get_global(EXCEPTION_PENDING_GLOBAL)
+			.unwrap_or_else(|| {
+				self.module
+					.add_global(i8_type, None, EXCEPTION_PENDING_GLOBAL)
+			});
+
+		let pending = self
+			.builder
+			.build_load(i8_type, pending.as_pointer_value(), "exception_pending_")?
+			.into_int_value();
+
+		let is_pending = self.builder.build_int_compare(
+			IntPredicate::NE,
+			pending,
+			i8_type.const_zero(),
+			"is_exception_pending_",
+		)?;
+
+		let unwind_block = self.get_unwind_block(function)?;
+		let continue_block = self.context.append_basic_block(function, "after_call");
+
+		self.builder
+			.build_conditional_branch(is_pending, unwind_block, continue_block)?;
+
+		self.builder.position_at_end(continue_block);
+
+		Ok(())
+	}
+
+	pub(crate) fn throw_function(&self) -> FunctionValue<'ctx> {
+		self.module.get_function(THROW_FUNCTION).unwrap_or_else(|| {
+			self.module.add_function(
+				THROW_FUNCTION,
+				self.context.void_type().fn_type(
+					&[self.context.ptr_type(AddressSpace::default()).into()],
+					false,
+				),
+				None,
+			)
+		})
+	}
+
+	pub(crate) fn build_throw(
+		&mut self,
+		value: &P<Value>,
+		function: FunctionValue<'ctx>,
+	) -> Result<()> {
+		self.builder.build_call(
+			self.throw_function(),
+			&[self.maybe_deref_llvm_value(value)?.into()],
+			"throw_",
+		)?;
+
+		let unwind_block = self.get_unwind_block(function)?;
+		self.builder.build_unconditional_branch(unwind_block)?;
+
+		Ok(())
+	}
+
+	pub(crate) fn build_catch(
+		&mut self,
+		value: &P<Value>,
+		function: FunctionValue<'ctx>,
+	) -> Result<()> {
+		let ptr_type = self.context.ptr_type(AddressSpace::default());
+		let catch_function = self.module.get_function(CATCH_FUNCTION).unwrap_or_else(|| {
+			self.module
+				.add_function(CATCH_FUNCTION, ptr_type.fn_type(&[], false), None)
+		});
+
+		let exception = self
+			.builder
+			.build_call(catch_function, &[], "exception_")?
+			.try_as_basic_value()
+			.left()
+			.expect("Expected exception where there is none");
+
+		let variable = self
+			.value_to_basic_value
+			.get(&value.id())
+			.expect("Could not find catch variable")
+			.into_pointer_value();
+
+		self.builder.build_store(variable, exception)?;
+
+		// exceptions raised by the sandbox stay pending when they are caught, so they keep unwinding
+		self.build_exception_check(function)?;
+
+		Ok(())
+	}
+}
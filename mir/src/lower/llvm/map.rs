@@ -0,0 +1,185 @@
+use anyhow::{Context, Result};
+use eggscript_interpreter::runtime::any::{ANY_NUMBER_TAG, ANY_STRING_TAG};
+use eggscript_interpreter::runtime::map::{
+	MAP_CONTAINS_FUNCTION, MAP_COUNT_FUNCTION, MAP_GET_NUMBER_FUNCTION, MAP_GET_STRING_FUNCTION,
+	MAP_KEY_AT_NUMBER_FUNCTION, MAP_KEY_AT_STRING_FUNCTION, MAP_NEW_FUNCTION, MAP_REMOVE_FUNCTION,
+	MAP_SET_FUNCTION,
+};
+use eggscript_interpreter::MapOperation;
+use eggscript_types::P;
+use inkwell::types::{BasicMetadataTypeEnum, BasicType, BasicTypeEnum};
+use inkwell::values::{BasicMetadataValueEnum, BasicValueEnum, FunctionValue};
+use inkwell::AddressSpace;
+
+use crate::Value;
+
+use super::LlvmLowerContext;
+
+impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
+	/// Maps live in the runtime, so every operation on them is a call to one of its functions
+	pub(crate) fn build_map_operation(
+		&mut self,
+		result: &Option<P<Value>>,
+		operation: MapOperation,
+		operands: &Vec<P<Value>>,
+		function: FunctionValue<'ctx>,
+	) -> Result<()> {
+		let ptr_type = self.context.ptr_type(AddressSpace::default());
+		let f64_type = self.context.f64_type();
+
+		let returns_string = result
+			.as_ref()
+			.is_some_and(|result| self.is_string_type(result));
+
+		let converted = match operation {
+			MapOperation::New => {
+				let map = self
+					.build_map_call(MAP_NEW_FUNCTION, Some(ptr_type.into()), &[])?
+					.context("Expected map where there is none")?;
+
+				for entry in operands.chunks(2) {
+					let mut arguments = vec![map.into()];
+					arguments.append(&mut self.build_tagged_arguments(&entry[0])?);
+					arguments.append(&mut self.build_tagged_arguments(&entry[1])?);
+					self.build_map_call(MAP_SET_FUNCTION, None, &arguments)?;
+				}
+
+				map
+			}
+			MapOperation::Get => {
+				let (name, return_type): (_, BasicTypeEnum) = if returns_string {
+					(MAP_GET_STRING_FUNCTION, ptr_type.into())
+				} else {
+					(MAP_GET_NUMBER_FUNCTION, f64_type.into())
+				};
+
+				let mut arguments = vec![self.maybe_deref_llvm_value(&operands[0])?.into()];
+				arguments.append(&mut self.build_tagged_arguments(&operands[1])?);
+				self.build_map_call(name, Some(return_type), &arguments)?
+					.context("Expected map value where there is none")?
+			}
+			MapOperation::Set => {
+				let mut arguments = vec![self.maybe_deref_llvm_value(&operands[0])?.into()];
+				arguments.append(&mut self.build_tagged_arguments(&operands[1])?);
+				arguments.append(&mut self.build_tagged_arguments(&operands[2])?);
+				self.build_map_call(MAP_SET_FUNCTION, None, &arguments)?;
+				self.build_exception_check(function)?;
+
+				return Ok(());
+			}
+			MapOperation::Contains | MapOperation::Remove => {
+				let name = if operation == MapOperation::Contains {
+					MAP_CONTAINS_FUNCTION
+				} else {
+					MAP_REMOVE_FUNCTION
+				};
+
+				let mut arguments = vec![self.maybe_deref_llvm_value(&operands[0])?.into()];
+				arguments.append(&mut self.build_tagged_arguments(&operands[1])?);
+				self.build_map_call(name, Some(f64_type.into()), &arguments)?
+					.context("Expected result where there is none")?
+			}
+			MapOperation::Count => self
+				.build_map_call(
+					MAP_COUNT_FUNCTION,
+					Some(f64_type.into()),
+					&[self.maybe_deref_llvm_value(&operands[0])?.into()],
+				)?
+				.context("Expected count where there is none")?,
+			MapOperation::KeyAt => {
+				let (name, return_type): (_, BasicTypeEnum) = if returns_string {
+					(MAP_KEY_AT_STRING_FUNCTION, ptr_type.into())
+				} else {
+					(MAP_KEY_AT_NUMBER_FUNCTION, f64_type.into())
+				};
+
+				self.build_map_call(
+					name,
+					Some(return_type),
+					&[
+						self.maybe_deref_llvm_value(&operands[0])?.into(),
+						self.maybe_deref_llvm_value(&operands[1])?.into(),
+					],
+				)?
+				.context("Expected key where there is none")?
+			}
+		};
+
+		let result = result.as_ref().context("Map operation has no result")?;
+
+		// strings and maps are pointers themselves, so the value is stored the same way call
+		// results are
+		self.alloc_llvm_value(result)?;
+		self.builder
+			.build_store(self.value_to_llvm_pointer_value(result)?, converted)?;
+
+		self.build_exception_check(function)?;
+
+		Ok(())
+	}
+
+	fn is_string_type(&self, value: &P<Value>) -> bool {
+		self.common_context
+			.type_store
+			.lock()
+			.expect("Could not lock type store")
+			.name_to_type_handle("string")
+			.is_some_and(|string| string == value.ty())
+	}
+
+	/// Keys and values are passed as a tag followed by a number and a string, like `any` values
+	fn build_tagged_arguments(
+		&self,
+		value: &P<Value>,
+	) -> Result<Vec<BasicMetadataValueEnum<'ctx>>> {
+		let ptr_type = self.context.ptr_type(AddressSpace::default());
+		let f64_type = self.context.f64_type();
+		let i8_type = self.context.i8_type();
+		let llvm_value = self.maybe_deref_llvm_value(value)?;
+
+		if self.is_string_type(value) {
+			return Ok(vec![
+				i8_type.const_int(ANY_STRING_TAG as u64, false).into(),
+				f64_type.const_zero().into(),
+				llvm_value.into(),
+			]);
+		} else {
+			return Ok(vec![
+				i8_type.const_int(ANY_NUMBER_TAG as u64, false).into(),
+				llvm_value.into(),
+				ptr_type.const_null().into(),
+			]);
+		}
+	}
+
+	fn build_map_call(
+		&self,
+		name: &str,
+		return_type: Option<BasicTypeEnum<'ctx>>,
+		arguments: &[BasicMetadataValueEnum<'ctx>],
+	) -> Result<Option<BasicValueEnum<'ctx>>> {
+		let map_function = self.module.get_function(name).unwrap_or_else(|| {
+			let parameter_types = arguments
+				.iter()
+				.map(|argument| match argument {
+					BasicMetadataValueEnum::FloatValue(_) => self.context.f64_type().into(),
+					BasicMetadataValueEnum::IntValue(_) => self.context.i8_type().into(),
+					_ => self.context.ptr_type(AddressSpace::default()).into(),
+				})
+				.collect::<Vec<BasicMetadataTypeEnum>>();
+
+			let function_type = match return_type {
+				Some(return_type) => return_type.fn_type(&parameter_types, false),
+				None => self.context.void_type().fn_type(&parameter_types, false),
+			};
+
+			self.module.add_function(name, function_type, None)
+		});
+
+		Ok(self
+			.builder
+			.build_call(map_function, arguments, "map_call_")?
+			.try_as_basic_value()
+			.left())
+	}
+}
@@ -76,8 +76,20 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 						&format!("add_result{}_", result_value.id()),
 					)?
 					.into()),
+				Primitive::Any => {
+					unreachable!("any operands are converted to numbers when the AST is lowered")
+				}
 				Primitive::Null => todo!(),
+				Primitive::String => {
+					unreachable!("operators on strings are rejected when the AST is lowered")
+				}
 			},
+			KnownTypeInfo::Enum(_)
+			| KnownTypeInfo::Map(_, _)
+			| KnownTypeInfo::Optional(_)
+			| KnownTypeInfo::Tuple(_) => {
+				unreachable!()
+			}
 		}
 	}
 
@@ -110,8 +122,20 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 						&format!("sub_result{}_", result_value.id()),
 					)?
 					.into()),
+				Primitive::Any => {
+					unreachable!("any operands are converted to numbers when the AST is lowered")
+				}
 				Primitive::Null => todo!(),
+				Primitive::String => {
+					unreachable!("operators on strings are rejected when the AST is lowered")
+				}
 			},
+			KnownTypeInfo::Enum(_)
+			| KnownTypeInfo::Map(_, _)
+			| KnownTypeInfo::Optional(_)
+			| KnownTypeInfo::Tuple(_) => {
+				unreachable!()
+			}
 		}
 	}
 
@@ -145,8 +169,20 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 						&format!("mul_result{}_", result_value.id()),
 					)?
 					.into()),
+				Primitive::Any => {
+					unreachable!("any operands are converted to numbers when the AST is lowered")
+				}
 				Primitive::Null => todo!(),
+				Primitive::String => {
+					unreachable!("operators on strings are rejected when the AST is lowered")
+				}
 			},
+			KnownTypeInfo::Enum(_)
+			| KnownTypeInfo::Map(_, _)
+			| KnownTypeInfo::Optional(_)
+			| KnownTypeInfo::Tuple(_) => {
+				unreachable!()
+			}
 		}
 	}
 
@@ -180,8 +216,20 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 						&format!("div_result{}_", result_value.id()),
 					)?
 					.into()),
+				Primitive::Any => {
+					unreachable!("any operands are converted to numbers when the AST is lowered")
+				}
 				Primitive::Null => todo!(),
+				Primitive::String => {
+					unreachable!("operators on strings are rejected when the AST is lowered")
+				}
 			},
+			KnownTypeInfo::Enum(_)
+			| KnownTypeInfo::Map(_, _)
+			| KnownTypeInfo::Optional(_)
+			| KnownTypeInfo::Tuple(_) => {
+				unreachable!()
+			}
 		}
 	}
 
@@ -215,8 +263,20 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 						&format!("mod_result{}_", result_value.id()),
 					)?
 					.into()),
+				Primitive::Any => {
+					unreachable!("any operands are converted to numbers when the AST is lowered")
+				}
 				Primitive::Null => todo!(),
+				Primitive::String => {
+					unreachable!("operators on strings are rejected when the AST is lowered")
+				}
 			},
+			KnownTypeInfo::Enum(_)
+			| KnownTypeInfo::Map(_, _)
+			| KnownTypeInfo::Optional(_)
+			| KnownTypeInfo::Tuple(_) => {
+				unreachable!()
+			}
 		}
 	}
 
@@ -249,8 +309,20 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 					self.value_to_llvm_float_value(right_operand)?,
 					&format!("cmp_result{}_", result_value.id()),
 				)?,
+				Primitive::Any => {
+					unreachable!("any operands are converted to numbers when the AST is lowered")
+				}
 				Primitive::Null => todo!(),
+				Primitive::String => {
+					unreachable!("operators on strings are rejected when the AST is lowered")
+				}
 			},
+			KnownTypeInfo::Enum(_)
+			| KnownTypeInfo::Map(_, _)
+			| KnownTypeInfo::Optional(_)
+			| KnownTypeInfo::Tuple(_) => {
+				unreachable!()
+			}
 		};
 
 		let result =
@@ -290,8 +362,20 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 
 					Ok(self.build_int_to_double_cast(result)?.into())
 				}
+				Primitive::Any => {
+					unreachable!("any operands are converted to numbers when the AST is lowered")
+				}
 				Primitive::Null => todo!(),
+				Primitive::String => {
+					unreachable!("operators on strings are rejected when the AST is lowered")
+				}
 			},
+			KnownTypeInfo::Enum(_)
+			| KnownTypeInfo::Map(_, _)
+			| KnownTypeInfo::Optional(_)
+			| KnownTypeInfo::Tuple(_) => {
+				unreachable!()
+			}
 		}
 	}
 
@@ -325,8 +409,20 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 
 					Ok(self.build_int_to_double_cast(result)?.into())
 				}
+				Primitive::Any => {
+					unreachable!("any operands are converted to numbers when the AST is lowered")
+				}
 				Primitive::Null => todo!(),
+				Primitive::String => {
+					unreachable!("operators on strings are rejected when the AST is lowered")
+				}
 			},
+			KnownTypeInfo::Enum(_)
+			| KnownTypeInfo::Map(_, _)
+			| KnownTypeInfo::Optional(_)
+			| KnownTypeInfo::Tuple(_) => {
+				unreachable!()
+			}
 		}
 	}
 
@@ -360,8 +456,20 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 
 					Ok(self.build_int_to_double_cast(result)?.into())
 				}
+				Primitive::Any => {
+					unreachable!("any operands are converted to numbers when the AST is lowered")
+				}
 				Primitive::Null => todo!(),
+				Primitive::String => {
+					unreachable!("operators on strings are rejected when the AST is lowered")
+				}
 			},
+			KnownTypeInfo::Enum(_)
+			| KnownTypeInfo::Map(_, _)
+			| KnownTypeInfo::Optional(_)
+			| KnownTypeInfo::Tuple(_) => {
+				unreachable!()
+			}
 		}
 	}
 
@@ -397,8 +505,20 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 
 					Ok(self.build_int_to_double_cast(result)?.into())
 				}
+				Primitive::Any => {
+					unreachable!("any operands are converted to numbers when the AST is lowered")
+				}
 				Primitive::Null => todo!(),
+				Primitive::String => {
+					unreachable!("operators on strings are rejected when the AST is lowered")
+				}
 			},
+			KnownTypeInfo::Enum(_)
+			| KnownTypeInfo::Map(_, _)
+			| KnownTypeInfo::Optional(_)
+			| KnownTypeInfo::Tuple(_) => {
+				unreachable!()
+			}
 		}
 	}
 
@@ -434,8 +554,20 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 
 					Ok(self.build_int_to_double_cast(result)?.into())
 				}
+				Primitive::Any => {
+					unreachable!("any operands are converted to numbers when the AST is lowered")
+				}
 				Primitive::Null => todo!(),
+				Primitive::String => {
+					unreachable!("operators on strings are rejected when the AST is lowered")
+				}
 			},
+			KnownTypeInfo::Enum(_)
+			| KnownTypeInfo::Map(_, _)
+			| KnownTypeInfo::Optional(_)
+			| KnownTypeInfo::Tuple(_) => {
+				unreachable!()
+			}
 		}
 	}
 
@@ -468,8 +600,20 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 
 					Ok(self.build_int_to_double_cast(not_result)?.into())
 				}
+				Primitive::Any => {
+					unreachable!("any operands are converted to numbers when the AST is lowered")
+				}
 				Primitive::Null => todo!(),
+				Primitive::String => {
+					unreachable!("operators on strings are rejected when the AST is lowered")
+				}
 			},
+			KnownTypeInfo::Enum(_)
+			| KnownTypeInfo::Map(_, _)
+			| KnownTypeInfo::Optional(_)
+			| KnownTypeInfo::Tuple(_) => {
+				unreachable!()
+			}
 		}
 	}
 
@@ -501,8 +645,20 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 						&format!("neg_result{}_", result_value.id()),
 					)?
 					.into()),
+				Primitive::Any => {
+					unreachable!("any operands are converted to numbers when the AST is lowered")
+				}
 				Primitive::Null => todo!(),
+				Primitive::String => {
+					unreachable!("operators on strings are rejected when the AST is lowered")
+				}
 			},
+			KnownTypeInfo::Enum(_)
+			| KnownTypeInfo::Map(_, _)
+			| KnownTypeInfo::Optional(_)
+			| KnownTypeInfo::Tuple(_) => {
+				unreachable!()
+			}
 		}
 	}
 
@@ -533,8 +689,20 @@ impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
 					self.value_to_llvm_float_value(rvalue)?,
 					&format!("not_result{}_", result_value.id()),
 				)?,
+				Primitive::Any => {
+					unreachable!("any operands are converted to numbers when the AST is lowered")
+				}
 				Primitive::Null => todo!(),
+				Primitive::String => {
+					unreachable!("operators on strings are rejected when the AST is lowered")
+				}
 			},
+			KnownTypeInfo::Enum(_)
+			| KnownTypeInfo::Map(_, _)
+			| KnownTypeInfo::Optional(_)
+			| KnownTypeInfo::Tuple(_) => {
+				unreachable!()
+			}
 		};
 
 		let result =
@@ -1,4 +1,19 @@
+mod any;
 mod context;
+mod exception;
+mod map;
 mod math;
+mod sandbox;
+mod stack;
 
+pub use any::ANY_TO_NUMBER_FUNCTION;
+pub use any::ANY_TO_STRING_FUNCTION;
 pub use context::LlvmLowerContext;
+pub use exception::CATCH_FUNCTION;
+pub use exception::EXCEPTION_PENDING_GLOBAL;
+pub use exception::THROW_FUNCTION;
+pub use sandbox::INTERRUPT_GLOBAL;
+pub use sandbox::POLL_COUNTDOWN_GLOBAL;
+pub use sandbox::POLL_FUNCTION;
+pub use stack::CALL_DEPTH_GLOBAL;
+pub use stack::MAX_CALL_DEPTH_GLOBAL;
@@ -0,0 +1,152 @@
+use anyhow::Result;
+use inkwell::basic_block::BasicBlock;
+use inkwell::values::{BasicValue, FunctionValue, IntValue, PointerValue};
+use inkwell::{AddressSpace, AtomicOrdering, IntPredicate};
+
+use super::LlvmLowerContext;
+use crate::Unit;
+
+/// Flag the host sets from any thread to interrupt the script, the host maps it onto its runtime
+pub const INTERRUPT_GLOBAL: &str = "eggscript_interrupted";
+/// Loop iterations left until JIT code polls the host, the host maps it onto its runtime
+pub const POLL_COUNTDOWN_GLOBAL: &str = "eggscript_poll_countdown";
+/// `void(ptr)` function that takes the interrupt flag and checks the limits of the sandbox, and
+/// raises an exception that cannot be caught once the script was interrupted or ran into a limit
+pub const POLL_FUNCTION: &str = "eggscript_poll";
+
+impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
+	fn interrupt_flag(&self) -> PointerValue<'ctx> {
+		self.module
+			.get_global(INTERRUPT_GLOBAL)
+			.unwrap_or_else(|| {
+				self.module
+					.add_global(self.context.i8_type(), None, INTERRUPT_GLOBAL)
+			})
+			.as_pointer_value()
+	}
+
+	/// The host sets the flag from another thread, so it is read atomically
+	fn build_is_interrupted(&self) -> Result<IntValue<'ctx>> {
+		let i8_type = self.context.i8_type();
+		let interrupted =
+			self.builder
+				.build_load(i8_type, self.interrupt_flag(), "interrupted_")?;
+
+		let instruction = interrupted
+			.as_instruction_value()
+			.expect("Expected load instruction");
+		instruction
+			.set_atomic_ordering(AtomicOrdering::Monotonic)
+			.map_err(anyhow::Error::msg)?;
+		instruction.set_alignment(1).map_err(anyhow::Error::msg)?;
+
+		Ok(self.builder.build_int_compare(
+			IntPredicate::NE,
+			interrupted.into_int_value(),
+			i8_type.const_zero(),
+			"is_interrupted_",
+		)?)
+	}
+
+	fn build_poll_call(&mut self, function: FunctionValue<'ctx>) -> Result<()> {
+		let poll_function = self.module.get_function(POLL_FUNCTION).unwrap_or_else(|| {
+			self.module.add_function(
+				POLL_FUNCTION,
+				self.context.void_type().fn_type(
+					&[self.context.ptr_type(AddressSpace::default()).into()],
+					false,
+				),
+				None,
+			)
+		});
+
+		self.builder
+			.build_call(poll_function, &[self.interrupt_flag().into()], "poll_")?;
+		self.build_exception_check(function)?;
+
+		Ok(())
+	}
+
+	/// Counts down the loop iterations until the next poll, built before the branch of a back-edge.
+	/// Polls only cost a call once the countdown runs out or the script was interrupted
+	pub(crate) fn build_back_edge_poll(
+		&mut self,
+		unit: &Unit,
+		function: FunctionValue<'ctx>,
+	) -> Result<()> {
+		self.builder.position_at_end(
+			*self
+				.unit_end_blocks
+				.get(&unit.id)
+				.expect("Could not find unit"),
+		);
+		self.current_unwind = unit.unwind;
+
+		let i64_type = self.context.i64_type();
+		let countdown_global = self
+			.module
+			.get_global(POLL_COUNTDOWN_GLOBAL)
+			.unwrap_or_else(|| {
+				self.module
+					.add_global(i64_type, None, POLL_COUNTDOWN_GLOBAL)
+			})
+			.as_pointer_value();
+
+		let countdown = self
+			.builder
+			.build_load(i64_type, countdown_global, "poll_countdown_")?
+			.into_int_value();
+		let countdown = self.builder.build_int_sub(
+			countdown,
+			i64_type.const_int(1, false),
+			"poll_countdown_",
+		)?;
+		self.builder.build_store(countdown_global, countdown)?;
+
+		let is_countdown_over = self.builder.build_int_compare(
+			IntPredicate::SLT,
+			countdown,
+			i64_type.const_zero(),
+			"is_countdown_over_",
+		)?;
+		let is_interrupted = self.build_is_interrupted()?;
+		let is_due = self
+			.builder
+			.build_or(is_countdown_over, is_interrupted, "is_poll_due_")?;
+
+		let poll_block = self.context.append_basic_block(function, "poll");
+		let continue_block = self.context.append_basic_block(function, "after_poll");
+		self.builder
+			.build_conditional_branch(is_due, poll_block, continue_block)?;
+
+		self.builder.position_at_end(poll_block);
+		self.build_poll_call(function)?;
+		self.builder.build_unconditional_branch(continue_block)?;
+
+		// the branch of the back-edge leaves the unit from the block after the poll, so phis of the
+		// units it branches to have to refer to that block
+		self.unit_end_blocks.insert(unit.id, continue_block);
+
+		Ok(())
+	}
+
+	/// Polls before continuing at `continue_block` if the script was interrupted, built into the
+	/// prologues of functions so recursion can be interrupted too
+	pub(crate) fn build_call_interrupt_check(
+		&mut self,
+		function: FunctionValue<'ctx>,
+		continue_block: BasicBlock<'ctx>,
+	) -> Result<()> {
+		let is_interrupted = self.build_is_interrupted()?;
+
+		let poll_block = self.context.append_basic_block(function, "interrupt");
+		self.builder
+			.build_conditional_branch(is_interrupted, poll_block, continue_block)?;
+
+		self.builder.position_at_end(poll_block);
+		self.build_poll_call(function)?;
+		self.builder.build_unconditional_branch(continue_block)?;
+
+		Ok(())
+	}
+}
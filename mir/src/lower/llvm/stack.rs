@@ -0,0 +1,101 @@
+use anyhow::Result;
+use eggscript_interpreter::runtime::stack::STACK_OVERFLOW;
+use inkwell::basic_block::BasicBlock;
+use inkwell::values::{FunctionValue, GlobalValue};
+use inkwell::IntPredicate;
+
+use super::LlvmLowerContext;
+
+/// Counter of the script functions that are running, the host maps it onto its runtime
+pub const CALL_DEPTH_GLOBAL: &str = "eggscript_call_depth";
+/// Call depth at which calls raise a stack overflow, the host maps it onto its runtime
+pub const MAX_CALL_DEPTH_GLOBAL: &str = "eggscript_max_call_depth";
+
+impl<'a, 'ctx> LlvmLowerContext<'a, 'ctx> {
+	fn get_i64_global(&self, name: &str) -> GlobalValue<'ctx> {
+		self.module
+			.get_global(name)
+			.unwrap_or_else(|| self.module.add_global(self.context.i64_type(), None, name))
+	}
+
+	/// Counts the function as running, and raises a stack overflow instead of running it if that
+	/// makes too many functions run at once
+	pub(crate) fn build_call_depth_prologue(
+		&mut self,
+		function: FunctionValue<'ctx>,
+		first_block: BasicBlock<'ctx>,
+	) -> Result<()> {
+		let i64_type = self.context.i64_type();
+		let depth_global = self.get_i64_global(CALL_DEPTH_GLOBAL).as_pointer_value();
+		let max_depth_global = self
+			.get_i64_global(MAX_CALL_DEPTH_GLOBAL)
+			.as_pointer_value();
+
+		let depth = self
+			.builder
+			.build_load(i64_type, depth_global, "call_depth_")?
+			.into_int_value();
+		let depth =
+			self.builder
+				.build_int_add(depth, i64_type.const_int(1, false), "call_depth_")?;
+		self.builder.build_store(depth_global, depth)?;
+
+		let max_depth = self
+			.builder
+			.build_load(i64_type, max_depth_global, "max_call_depth_")?
+			.into_int_value();
+		let is_overflow = self.builder.build_int_compare(
+			IntPredicate::UGT,
+			depth,
+			max_depth,
+			"is_stack_overflow_",
+		)?;
+
+		let overflow_block = self.context.append_basic_block(function, "stack_overflow");
+		let interrupt_check_block = self.context.append_basic_block(function, "check_interrupt");
+		self.builder.build_conditional_branch(
+			is_overflow,
+			overflow_block,
+			interrupt_check_block,
+		)?;
+
+		// the unwind block counts the function as returned again
+		self.builder.position_at_end(overflow_block);
+		let message = self
+			.builder
+			.build_global_string_ptr(STACK_OVERFLOW, "stack_overflow_")?
+			.as_pointer_value();
+		self.builder
+			.build_call(self.throw_function(), &[message.into()], "throw_")?;
+
+		let unwind_block = self.get_unwind_block(function)?;
+		self.builder.build_unconditional_branch(unwind_block)?;
+
+		self.builder.position_at_end(interrupt_check_block);
+		self.build_call_interrupt_check(function, first_block)?;
+
+		Ok(())
+	}
+
+	/// Counts the function as returned, built before every return of functions that have a
+	/// prologue
+	pub(crate) fn build_call_depth_epilogue(&mut self) -> Result<()> {
+		if !self.counts_call_depth {
+			return Ok(());
+		}
+
+		let i64_type = self.context.i64_type();
+		let depth_global = self.get_i64_global(CALL_DEPTH_GLOBAL).as_pointer_value();
+
+		let depth = self
+			.builder
+			.build_load(i64_type, depth_global, "call_depth_")?
+			.into_int_value();
+		let depth =
+			self.builder
+				.build_int_sub(depth, i64_type.const_int(1, false), "call_depth_")?;
+		self.builder.build_store(depth_global, depth)?;
+
+		Ok(())
+	}
+}
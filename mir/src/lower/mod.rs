@@ -5,3 +5,13 @@ mod llvm;
 pub use common_context::CommonContext;
 pub use eggscript::EggscriptLowerContext;
 pub use llvm::LlvmLowerContext;
+pub use llvm::ANY_TO_NUMBER_FUNCTION;
+pub use llvm::ANY_TO_STRING_FUNCTION;
+pub use llvm::CALL_DEPTH_GLOBAL;
+pub use llvm::CATCH_FUNCTION;
+pub use llvm::EXCEPTION_PENDING_GLOBAL;
+pub use llvm::INTERRUPT_GLOBAL;
+pub use llvm::MAX_CALL_DEPTH_GLOBAL;
+pub use llvm::POLL_COUNTDOWN_GLOBAL;
+pub use llvm::POLL_FUNCTION;
+pub use llvm::THROW_FUNCTION;
@@ -1,3 +1,4 @@
+use eggscript_interpreter::MapOperation;
 use eggscript_types::P;
 use std::ops::Deref;
 
@@ -30,11 +31,12 @@ impl std::fmt::Display for MIR {
 					rvalue.deref(),
 				))
 			}
-			MIRInfo::CallFunction(function_name, function_handle, arguments, result) => {
+			MIRInfo::CallFunction(function_name, function_handle, dispatch, arguments, result) => {
 				f.write_fmt(format_args!(
-					"{}{} = {}[{}](",
+					"{}{} = {}{}[{}](",
 					INDENT,
 					result.deref(),
+					dispatch,
 					function_name,
 					function_handle,
 				))?;
@@ -53,6 +55,38 @@ impl std::fmt::Display for MIR {
 
 				f.write_str(");\n")
 			}
+			MIRInfo::Catch(value) => {
+				f.write_fmt(format_args!("{}{} = catch;\n", INDENT, value.deref()))
+			}
+			MIRInfo::Destructure(lvalues, rvalue) => {
+				let lvalues = lvalues
+					.iter()
+					.map(|lvalue| format!("{}", lvalue.deref()))
+					.collect::<Vec<_>>();
+
+				f.write_fmt(format_args!(
+					"{}{} = {};\n",
+					INDENT,
+					lvalues.join(", "),
+					rvalue.deref()
+				))
+			}
+			MIRInfo::EnterTry(catch_unit) => {
+				f.write_fmt(format_args!("{}try catch {};\n", INDENT, catch_unit))
+			}
+			MIRInfo::ExitTry => f.write_fmt(format_args!("{}end try;\n", INDENT)),
+			MIRInfo::FromAny(result, value) => f.write_fmt(format_args!(
+				"{}{} = from any {};\n",
+				INDENT,
+				result.deref(),
+				value.deref()
+			)),
+			MIRInfo::IsNull(result, value) => f.write_fmt(format_args!(
+				"{}{} = {} == null;\n",
+				INDENT,
+				result.deref(),
+				value.deref()
+			)),
 			MIRInfo::LogicPhi(result, operator, units_and_values) => {
 				let operator_name = match operator {
 					LogicOperator::And => "and",
@@ -72,6 +106,29 @@ impl std::fmt::Display for MIR {
 					units_and_values.join(", "),
 				))
 			}
+			MIRInfo::MapOperation(result, operation, operands) => {
+				let operands = operands
+					.iter()
+					.map(|operand| format!("{}", operand.deref()))
+					.collect::<Vec<_>>();
+
+				if let Some(result) = result {
+					f.write_fmt(format_args!(
+						"{}{} = map {:?}({});\n",
+						INDENT,
+						result.deref(),
+						operation,
+						operands.join(", ")
+					))
+				} else {
+					f.write_fmt(format_args!(
+						"{}map {:?}({});\n",
+						INDENT,
+						operation,
+						operands.join(", ")
+					))
+				}
+			}
 			MIRInfo::StoreLiteral(value, primitive_value) => f.write_fmt(format_args!(
 				"{}{} = #{};\n",
 				INDENT,
@@ -91,6 +148,28 @@ impl std::fmt::Display for MIR {
 				operator,
 				rvalue.deref()
 			)),
+			MIRInfo::ToAny(result, value) => f.write_fmt(format_args!(
+				"{}{} = to any {};\n",
+				INDENT,
+				result.deref(),
+				value.deref()
+			)),
+			MIRInfo::UnwrapOptional(result, value) => f.write_fmt(format_args!(
+				"{}{} = unwrap {};\n",
+				INDENT,
+				result.deref(),
+				value.deref()
+			)),
+			MIRInfo::WrapOptional(result, value) => f.write_fmt(format_args!(
+				"{}{} = wrap {};\n",
+				INDENT,
+				result.deref(),
+				value.deref()
+			)),
+			MIRInfo::Yield(Some(value)) => {
+				f.write_fmt(format_args!("{}yield {};\n", INDENT, value.deref()))
+			}
+			MIRInfo::Yield(None) => f.write_fmt(format_args!("{}yield;\n", INDENT)),
 		}
 	}
 }
@@ -110,11 +189,78 @@ impl MIR {
 pub enum MIRInfo {
 	Allocate(P<Value>, Option<usize>),
 	BinaryOperation(P<Value>, P<Value>, P<Value>, BinaryOperator),
-	CallFunction(String, usize, Vec<P<Value>>, P<Value>),
+	CallFunction(String, usize, Dispatch, Vec<P<Value>>, P<Value>),
+	/// Stores the exception that is being handled, starts every catch unit
+	Catch(P<Value>),
+	Destructure(Vec<P<Value>>, P<Value>),
+	/// Starts a try block whose exceptions are handled by the given catch unit
+	EnterTry(UnitHandle),
+	/// Ends a try block that finished without raising an exception
+	ExitTry,
+	/// Converts an `any` value to the result's type, raising an exception if it cannot be converted
+	FromAny(P<Value>, P<Value>),
+	/// Stores whether an optional value is null as a number
+	IsNull(P<Value>, P<Value>),
 	LogicPhi(P<Value>, LogicOperator, Vec<(UnitHandle, P<Value>)>),
+	/// Runtime map operation, the first operand is the map except when creating one
+	MapOperation(Option<P<Value>>, MapOperation, Vec<P<Value>>),
 	StoreLiteral(P<Value>, PrimitiveValue),
 	StoreValue(P<Value>, P<Value>),
 	Unary(P<Value>, P<Value>, UnaryOperator),
+	/// Turns a number or string into an `any` value that remembers its type
+	ToAny(P<Value>, P<Value>),
+	/// Gets the value out of an optional that is known to not be null
+	UnwrapOptional(P<Value>, P<Value>),
+	/// Turns a value of the inner type or null into an optional
+	WrapOptional(P<Value>, P<Value>),
+	/// Pauses the program and hands the value to the host, `None` yields null
+	Yield(Option<P<Value>>),
+}
+
+/// How a function call finds the function it calls
+#[derive(Clone, Debug, PartialEq)]
+pub enum Dispatch {
+	/// Packages cannot override natives, so they are always called directly
+	Direct,
+	/// Packages can override script functions, so calls to them go through the function table
+	Table,
+	/// `Parent::` call made by the package function with the given id, which goes to whatever the
+	/// package function overrides
+	Parent(usize),
+}
+
+impl Dispatch {
+	/// Name of the global that holds the function calls to an overridden function go to
+	pub fn function_slot_name(function_handle: usize) -> String {
+		format!("function_slot{}", function_handle)
+	}
+
+	/// Name of the global that holds the function a package function's `Parent::` calls go to
+	pub fn parent_slot_name(function_handle: usize) -> String {
+		format!("parent_slot{}", function_handle)
+	}
+
+	/// Returns the name of the global an indirect call loads the function from, or `None` if the
+	/// call is direct
+	pub fn slot_name(&self, function_handle: usize) -> Option<String> {
+		match self {
+			Dispatch::Direct => None,
+			Dispatch::Table => Some(Dispatch::function_slot_name(function_handle)),
+			Dispatch::Parent(caller_handle) => Some(Dispatch::parent_slot_name(*caller_handle)),
+		}
+	}
+}
+
+impl std::fmt::Display for Dispatch {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Dispatch::Direct => Ok(()),
+			Dispatch::Table => f.write_str("table "),
+			Dispatch::Parent(function_handle) => {
+				f.write_fmt(format_args!("parent[{}] ", function_handle))
+			}
+		}
+	}
 }
 
 #[derive(Clone, Debug)]
@@ -124,7 +270,9 @@ pub enum Transition {
 	GotoIfTrue(UnitHandle, P<Value>),
 	Invalid,
 	Next,
-	Return(Option<P<Value>>),
+	Return(Vec<P<Value>>),
+	/// Raises an exception, which continues at the unit's catch unit or unwinds out of the function
+	Throw(P<Value>),
 }
 
 impl Transition {
@@ -156,13 +304,21 @@ impl std::fmt::Display for Transition {
 			)),
 			Transition::Invalid => f.write_fmt(format_args!("{}invalid;", INDENT)),
 			Transition::Next => f.write_fmt(format_args!("{}next;", INDENT)),
-			Transition::Return(value) => {
-				if let Some(value) = value {
-					f.write_fmt(format_args!("{}return {};", INDENT, value.deref()))
-				} else {
+			Transition::Return(values) => {
+				if values.len() == 0 {
 					f.write_fmt(format_args!("{}return;", INDENT))
+				} else {
+					let values = values
+						.iter()
+						.map(|value| format!("{}", value.deref()))
+						.collect::<Vec<_>>();
+
+					f.write_fmt(format_args!("{}return {};", INDENT, values.join(", ")))
 				}
 			}
+			Transition::Throw(value) => {
+				f.write_fmt(format_args!("{}throw {};", INDENT, value.deref()))
+			}
 		}
 	}
 }
@@ -22,6 +22,21 @@ pub enum BinaryOperator {
 	GreaterThanEqualTo,
 }
 
+impl BinaryOperator {
+	/// Comparisons always result in a number, no matter what is being compared
+	pub fn is_comparison(&self) -> bool {
+		match self {
+			BinaryOperator::Equal
+			| BinaryOperator::NotEqual
+			| BinaryOperator::LessThan
+			| BinaryOperator::GreaterThan
+			| BinaryOperator::LessThanEqualTo
+			| BinaryOperator::GreaterThanEqualTo => true,
+			_ => false,
+		}
+	}
+}
+
 impl std::fmt::Display for BinaryOperator {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		match self {
@@ -102,5 +117,5 @@ impl Into<NumberUnaryOperation> for &UnaryOperator {
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum LogicOperator {
 	And,
-	Or
+	Or,
 }
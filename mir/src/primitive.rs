@@ -1,15 +1,22 @@
 use anyhow::{Context, Result};
 use eggscript_types::{TypeHandle, TypeStore};
+use std::rc::Rc;
 
 #[derive(Clone, Debug)]
 pub enum PrimitiveValue {
+	Null,
 	Number(f64),
+	String(String),
 }
 
 impl Into<eggscript_interpreter::Value> for &PrimitiveValue {
 	fn into(self) -> eggscript_interpreter::Value {
 		match self {
+			PrimitiveValue::Null => eggscript_interpreter::Value::Null,
 			PrimitiveValue::Number(number) => eggscript_interpreter::Value::Number(*number),
+			PrimitiveValue::String(string) => {
+				eggscript_interpreter::Value::String(Rc::new(string.clone()))
+			}
 		}
 	}
 }
@@ -17,7 +24,9 @@ impl Into<eggscript_interpreter::Value> for &PrimitiveValue {
 impl std::fmt::Display for PrimitiveValue {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		match self {
+			PrimitiveValue::Null => f.write_str("null"),
 			PrimitiveValue::Number(value) => f.write_fmt(format_args!("{}", value)),
+			PrimitiveValue::String(value) => f.write_fmt(format_args!("{:?}", value)),
 		}
 	}
 }
@@ -25,9 +34,15 @@ impl std::fmt::Display for PrimitiveValue {
 impl PrimitiveValue {
 	pub fn get_type_from_type_store(&self, type_store: &TypeStore) -> Result<TypeHandle> {
 		match self {
+			PrimitiveValue::Null => type_store
+				.name_to_type_handle("null")
+				.context("Could not get 'null' type"),
 			PrimitiveValue::Number(_) => type_store
 				.name_to_type_handle("number")
 				.context("Could not get 'number' type"),
+			PrimitiveValue::String(_) => type_store
+				.name_to_type_handle("string")
+				.context("Could not get 'string' type"),
 		}
 	}
 }
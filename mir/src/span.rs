@@ -1,12 +1,15 @@
+use eggscript_interpreter::SourceSpan;
+
 #[derive(Debug)]
 pub struct Span {
 	start: u32,
 	end: u32,
+	file: u32,
 }
 
 impl Span {
-	pub fn new(start: u32, end: u32) -> Span {
-		Span { start, end }
+	pub fn new(start: u32, end: u32, file: u32) -> Span {
+		Span { start, end, file }
 	}
 
 	pub fn start(&self) -> u32 {
@@ -16,4 +19,19 @@ impl Span {
 	pub fn end(&self) -> u32 {
 		self.end
 	}
+
+	/// Index of the file the span points into
+	pub fn file(&self) -> u32 {
+		self.file
+	}
+}
+
+impl From<&Span> for SourceSpan {
+	fn from(span: &Span) -> Self {
+		SourceSpan {
+			file: span.file,
+			start: span.start,
+			end: span.end,
+		}
+	}
 }
@@ -10,6 +10,9 @@ pub struct Unit {
 	pub id: usize,
 	pub mir: Vec<MIR>,
 	pub transition: Transition,
+	/// Catch unit that exceptions raised in this unit go to, `None` if they unwind out of the
+	/// function
+	pub unwind: Option<UnitHandle>,
 }
 
 impl Unit {
@@ -18,6 +21,7 @@ impl Unit {
 			id: 0,
 			mir: Vec::new(),
 			transition: Transition::Invalid,
+			unwind: None,
 		}
 	}
 
@@ -51,13 +55,18 @@ impl Unit {
 			Transition::Invalid => None,
 			Transition::Next => None,
 			Transition::Return(_) => None,
+			Transition::Throw(_) => None,
 		}
 	}
 }
 
 impl std::fmt::Display for Unit {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		f.write_fmt(format_args!("block {} {{\n", self.id))?;
+		if let Some(unwind) = self.unwind {
+			f.write_fmt(format_args!("block {} unwind {} {{\n", self.id, unwind))?;
+		} else {
+			f.write_fmt(format_args!("block {} {{\n", self.id))?;
+		}
 
 		for mir in self.mir.iter() {
 			f.write_fmt(format_args!("{}", mir))?;
@@ -102,6 +111,7 @@ impl UnitStore {
 				id,
 				mir,
 				transition,
+				unwind: None,
 			},
 		);
 
@@ -119,6 +129,18 @@ impl UnitStore {
 		Ok(())
 	}
 
+	/// Makes exceptions raised in the units go to `catch_unit`, units that already have a catch unit
+	/// belong to a nested try block and keep theirs
+	pub fn set_unwind(&mut self, units: &[UnitHandle], catch_unit: UnitHandle) {
+		for unit in units.iter() {
+			if let Some(unit) = self.unit_id_to_unit.get_mut(unit)
+				&& unit.unwind.is_none()
+			{
+				unit.unwind = Some(catch_unit);
+			}
+		}
+	}
+
 	pub fn get_unit(&self, unit: &UnitHandle) -> Option<&Unit> {
 		self.unit_id_to_unit.get(unit)
 	}
@@ -133,13 +155,17 @@ impl UnitStore {
 		let mut spans_to_combine = vec![];
 		let mut span = vec![];
 		for i in 0..units.len() {
-			// do not combine jump targets
-			if span.len() > 0 && self.jump_targets.contains_key(&units[i]) {
+			let unit = &self.unit_id_to_unit[&units[i]];
+
+			// do not combine jump targets, or units that send exceptions to different places
+			if span.len() > 0
+				&& (self.jump_targets.contains_key(&units[i])
+					|| self.unit_id_to_unit[&span[0]].unwind != unit.unwind)
+			{
 				spans_to_combine.push(span);
 				span = vec![];
 			}
 
-			let unit = &self.unit_id_to_unit[&units[i]];
 			match unit.transition {
 				Transition::Next => {}
 				_ => {
@@ -229,6 +255,11 @@ impl UnitStore {
 			if let Some(target) = unit.transition.jump_target() {
 				self.jump_targets.insert(target, unit.id);
 			}
+
+			// exceptions jump to catch units
+			if let Some(target) = unit.unwind {
+				self.jump_targets.insert(target, unit.id);
+			}
 		}
 
 		let units = self.combine_units(units);
@@ -96,6 +96,10 @@ impl ValueStore {
 		return (value, true);
 	}
 
+	pub fn get_location(&self, name: &str) -> Option<P<Value>> {
+		self.name_to_value.get(name).cloned()
+	}
+
 	pub fn new_temp(&mut self, ty: TypeHandle) -> P<Value> {
 		let id = self.values.len();
 
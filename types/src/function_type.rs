@@ -5,5 +5,10 @@ pub struct FunctionType {
 	pub argument_types: Vec<TypeHandle>,
 	pub id: TypeHandle,
 	pub name: String,
+	/// Amount of arguments a call has to provide, the rest of `argument_types` have default values
+	pub required_argument_count: usize,
 	pub return_type: Option<TypeHandle>,
+	/// Type of the trailing variadic arguments, if the function accepts any. Only natives can be
+	/// variadic, scripts have no syntax for declaring variadic arguments
+	pub variadic_type: Option<TypeHandle>,
 }
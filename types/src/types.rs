@@ -4,13 +4,24 @@ use crate::FunctionType;
 
 #[derive(Clone, Debug)]
 pub enum Primitive {
+	/// Dynamically typed value that carries its type at runtime
+	Any,
 	Number,
 	Null,
+	String,
 }
 
 #[derive(Debug)]
 pub enum KnownTypeInfo {
+	/// Named set of variants, stored as the variant's index
+	Enum(Vec<String>),
+	/// Hash map from keys of the first type to values of the second type, values of map types
+	/// are references to the same map
+	Map(TypeHandle, TypeHandle),
+	/// Either a value of the inner type or null
+	Optional(TypeHandle),
 	Primitive(Primitive),
+	Tuple(Vec<TypeHandle>),
 }
 
 #[derive(Debug)]
@@ -66,7 +77,24 @@ impl Type {
 	pub fn is_primitive(&self) -> bool {
 		match self {
 			Type::Known { info, .. } => match info {
+				KnownTypeInfo::Enum(_) => true,
+				KnownTypeInfo::Map(_, _) => true,
+				KnownTypeInfo::Optional(_) => true,
 				KnownTypeInfo::Primitive(_) => true,
+				KnownTypeInfo::Tuple(_) => false,
+			},
+			_ => false,
+		}
+	}
+
+	pub fn is_tuple(&self) -> bool {
+		match self {
+			Type::Known { info, .. } => match info {
+				KnownTypeInfo::Enum(_) => false,
+				KnownTypeInfo::Map(_, _) => false,
+				KnownTypeInfo::Optional(_) => false,
+				KnownTypeInfo::Primitive(_) => false,
+				KnownTypeInfo::Tuple(_) => true,
 			},
 			_ => false,
 		}
@@ -96,11 +124,11 @@ impl TypeStore {
 			name: "number".into(),
 		});
 
-		/*type_store.create_type(Type::Known {
+		type_store.create_type(Type::Known {
 			id: 0,
 			info: KnownTypeInfo::Primitive(Primitive::String),
 			name: "string".into(),
-		});*/
+		});
 
 		type_store.create_type(Type::Known {
 			id: 0,
@@ -108,6 +136,19 @@ impl TypeStore {
 			name: "null".into(),
 		});
 
+		type_store.create_type(Type::Known {
+			id: 0,
+			info: KnownTypeInfo::Primitive(Primitive::Any),
+			name: "any".into(),
+		});
+
+		for name in ["number", "string"] {
+			let inner = type_store
+				.name_to_type_handle(name)
+				.expect("Could not get primitive type");
+			type_store.create_optional_type(inner);
+		}
+
 		return type_store;
 	}
 
@@ -128,13 +169,17 @@ impl TypeStore {
 		&mut self,
 		name: &str,
 		argument_types: Vec<TypeHandle>,
+		required_argument_count: usize,
 		return_type: Option<TypeHandle>,
+		variadic_type: Option<TypeHandle>,
 	) -> FunctionType {
 		let ty = FunctionType {
 			argument_types,
 			id: 0, // TODO ugh
 			name: name.into(),
+			required_argument_count,
 			return_type,
+			variadic_type,
 		};
 
 		self.functions.insert(name.into(), ty.clone());
@@ -142,6 +187,88 @@ impl TypeStore {
 		return ty;
 	}
 
+	/// Tuple types are identified by their name, so creating the same tuple twice returns the
+	/// same handle
+	pub fn create_tuple_type(&mut self, element_types: Vec<TypeHandle>) -> Option<TypeHandle> {
+		let element_names = element_types
+			.iter()
+			.map(|element_type| Some(self.get_type(*element_type)?.get_name()?.to_string()))
+			.collect::<Option<Vec<String>>>()?;
+
+		let name = format!("({})", element_names.join(", "));
+		if let Some(handle) = self.name_to_type_handle(&name) {
+			return Some(handle);
+		}
+
+		Some(self.create_type(Type::Known {
+			id: 0,
+			info: KnownTypeInfo::Tuple(element_types),
+			name,
+		}))
+	}
+
+	/// Enums are lowered to numbers, so a value of an enum type is the index of its variant.
+	/// Declaring the same enum again returns the existing handle, and a different declaration
+	/// with a name that is already taken returns `None`
+	pub fn create_enum_type(&mut self, name: &str, variants: Vec<String>) -> Option<TypeHandle> {
+		if let Some(handle) = self.name_to_type_handle(name) {
+			if self.get_enum_variants(handle) == Some(&variants) {
+				return Some(handle);
+			} else {
+				return None;
+			}
+		}
+
+		let handle = self.create_type(Type::Known {
+			id: 0,
+			info: KnownTypeInfo::Enum(variants),
+			name: name.into(),
+		});
+
+		self.create_optional_type(handle);
+
+		return Some(handle);
+	}
+
+	/// Optional types are named after their inner type with a `?` appended, so creating the same
+	/// optional type twice returns the same handle
+	pub fn create_optional_type(&mut self, inner: TypeHandle) -> Option<TypeHandle> {
+		let name = format!("{}?", self.get_type(inner)?.get_name()?);
+		if let Some(handle) = self.name_to_type_handle(&name) {
+			return Some(handle);
+		}
+
+		Some(self.create_type(Type::Known {
+			id: 0,
+			info: KnownTypeInfo::Optional(inner),
+			name,
+		}))
+	}
+
+	/// Map types are named after their key and value types, so creating the same map type twice
+	/// returns the same handle
+	pub fn create_map_type(&mut self, key: TypeHandle, value: TypeHandle) -> Option<TypeHandle> {
+		let name = format!(
+			"map<{}, {}>",
+			self.get_type(key)?.get_name()?,
+			self.get_type(value)?.get_name()?
+		);
+
+		if let Some(handle) = self.name_to_type_handle(&name) {
+			return Some(handle);
+		}
+
+		let handle = self.create_type(Type::Known {
+			id: 0,
+			info: KnownTypeInfo::Map(key, value),
+			name,
+		});
+
+		self.create_optional_type(handle);
+
+		return Some(handle);
+	}
+
 	pub fn create_unknown(&mut self) -> TypeHandle {
 		let type_handle = self.types.len();
 		let ty = Type::Unknown { id: type_handle };
@@ -164,6 +291,30 @@ impl TypeStore {
 		self.name_to_type.get(name).copied()
 	}
 
+	/// Finds the type that a type annotation names, creating the map types it uses
+	pub fn resolve_type_name(&mut self, name: &str) -> Option<TypeHandle> {
+		let name = name.trim();
+
+		if let Some(inner) = name.strip_suffix('?') {
+			let inner = self.resolve_type_name(inner)?;
+			return self.create_optional_type(inner);
+		}
+
+		if let Some(arguments) = name
+			.strip_prefix("map")
+			.map(|arguments| arguments.trim_start())
+			.and_then(|arguments| arguments.strip_prefix('<'))
+			.and_then(|arguments| arguments.strip_suffix('>'))
+		{
+			let (key, value) = arguments.split_once(',')?;
+			let key = self.resolve_type_name(key)?;
+			let value = self.resolve_type_name(value)?;
+			return self.create_map_type(key, value);
+		}
+
+		return self.name_to_type_handle(name);
+	}
+
 	pub fn resolve_type(&self, ty: TypeHandle) -> Option<TypeHandle> {
 		let ty = self.types.get(ty)?;
 		match ty {
@@ -180,6 +331,58 @@ impl TypeStore {
 		self.types.get(ty)
 	}
 
+	pub fn get_tuple_element_types(&self, ty: TypeHandle) -> Option<&Vec<TypeHandle>> {
+		match self.get_type(self.resolve_type(ty)?)?.get_info()? {
+			KnownTypeInfo::Tuple(element_types) => Some(element_types),
+			_ => None,
+		}
+	}
+
+	pub fn get_enum_variants(&self, ty: TypeHandle) -> Option<&Vec<String>> {
+		match self.get_type(self.resolve_type(ty)?)?.get_info()? {
+			KnownTypeInfo::Enum(variants) => Some(variants),
+			_ => None,
+		}
+	}
+
+	/// Key and value types of a map type
+	pub fn get_map_types(&self, ty: TypeHandle) -> Option<(TypeHandle, TypeHandle)> {
+		match self.get_type(self.resolve_type(ty)?)?.get_info()? {
+			KnownTypeInfo::Map(key, value) => Some((*key, *value)),
+			_ => None,
+		}
+	}
+
+	pub fn get_optional_inner_type(&self, ty: TypeHandle) -> Option<TypeHandle> {
+		match self.get_type(self.resolve_type(ty)?)?.get_info()? {
+			KnownTypeInfo::Optional(inner) => Some(*inner),
+			_ => None,
+		}
+	}
+
+	pub fn is_any(&self, ty: TypeHandle) -> bool {
+		let Some(any) = self.name_to_type_handle("any") else {
+			return false;
+		};
+
+		return self.are_types_compatible(ty, any);
+	}
+
+	pub fn is_null(&self, ty: TypeHandle) -> bool {
+		let Some(null) = self.name_to_type_handle("null") else {
+			return false;
+		};
+
+		return self.are_types_compatible(ty, null);
+	}
+
+	/// Amount of stack slots a value of the type takes up in the interpreter
+	pub fn get_slot_count(&self, ty: TypeHandle) -> usize {
+		self.get_tuple_element_types(ty)
+			.map(|element_types| element_types.len())
+			.unwrap_or(1)
+	}
+
 	pub fn are_types_compatible(&self, type1: TypeHandle, type2: TypeHandle) -> bool {
 		let type1 = self.resolve_type(type1);
 		let type2 = self.resolve_type(type2);